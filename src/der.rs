@@ -0,0 +1,270 @@
+//! Minimal ASN.1 DER reader.
+//!
+//! Only definite lengths and single-byte tags are accepted, which covers the X.509 and PKCS#7
+//! structures embedded in executables. Offsets are relative to the slice handed to
+//! [`DerElement::parse`], so elements from a `WIN_CERTIFICATE` payload can be mapped back to the
+//! file by adding the payload offset.
+
+use crate::errors::FileParseError;
+
+/// `BOOLEAN`.
+pub const TAG_BOOLEAN: u8 = 0x01;
+/// `INTEGER`.
+pub const TAG_INTEGER: u8 = 0x02;
+/// `BIT STRING`.
+pub const TAG_BIT_STRING: u8 = 0x03;
+/// `OCTET STRING`.
+pub const TAG_OCTET_STRING: u8 = 0x04;
+/// `NULL`.
+pub const TAG_NULL: u8 = 0x05;
+/// `OBJECT IDENTIFIER`.
+pub const TAG_OID: u8 = 0x06;
+/// `UTF8String`.
+pub const TAG_UTF8_STRING: u8 = 0x0C;
+/// `PrintableString`.
+pub const TAG_PRINTABLE_STRING: u8 = 0x13;
+/// `T61String` (decoded as Latin-1).
+pub const TAG_T61_STRING: u8 = 0x14;
+/// `IA5String`.
+pub const TAG_IA5_STRING: u8 = 0x16;
+/// `UTCTime`.
+pub const TAG_UTC_TIME: u8 = 0x17;
+/// `GeneralizedTime`.
+pub const TAG_GENERALIZED_TIME: u8 = 0x18;
+/// `BMPString` (UTF-16BE).
+pub const TAG_BMP_STRING: u8 = 0x1E;
+/// `SEQUENCE` / `SEQUENCE OF`.
+pub const TAG_SEQUENCE: u8 = 0x30;
+/// `SET` / `SET OF`.
+pub const TAG_SET: u8 = 0x31;
+
+/// Constructed context-specific tag `[n]`.
+pub const fn context(n: u8) -> u8 {
+    0xA0 | n
+}
+
+/// One tag-length-value element.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DerElement<'a> {
+    /// Identifier octet.
+    pub tag: u8,
+    /// Offset of the identifier octet.
+    pub offset: usize,
+    /// Identifier plus length octets.
+    pub header_length: usize,
+    /// Contents octets.
+    pub contents: &'a [u8],
+}
+
+impl<'a> DerElement<'a> {
+    /// Parses the element starting at `offset` in `data`.
+    pub fn parse(data: &'a [u8], offset: usize) -> Result<Self, FileParseError> {
+        let tag = *data.get(offset).ok_or(FileParseError::BufferOverflow)?;
+        if tag & 0x1F == 0x1F {
+            return Err(FileParseError::UnsupportedFeature(
+                "multi-byte DER tag".to_string(),
+            ));
+        }
+        let first = *data.get(offset + 1).ok_or(FileParseError::BufferOverflow)?;
+        let (length, header_length) = match first {
+            0x00..=0x7F => (first as usize, 2),
+            0x80 => {
+                return Err(FileParseError::UnsupportedFeature(
+                    "indefinite DER length".to_string(),
+                ))
+            }
+            _ => {
+                let count = (first & 0x7F) as usize;
+                if count > 4 {
+                    return Err(FileParseError::ValueTooLarge);
+                }
+                let bytes = data
+                    .get(offset + 2..offset + 2 + count)
+                    .ok_or(FileParseError::BufferOverflow)?;
+                let length = bytes
+                    .iter()
+                    .fold(0usize, |acc, byte| (acc << 8) | *byte as usize);
+                (length, 2 + count)
+            }
+        };
+        let start = offset + header_length;
+        let end = start
+            .checked_add(length)
+            .ok_or(FileParseError::BufferOverflow)?;
+        let contents = data.get(start..end).ok_or(FileParseError::BufferOverflow)?;
+        Ok(DerElement {
+            tag,
+            offset,
+            header_length,
+            contents,
+        })
+    }
+
+    /// Parses the element at `offset` and checks its tag.
+    pub fn parse_tagged(data: &'a [u8], offset: usize, tag: u8) -> Result<Self, FileParseError> {
+        DerElement::parse(data, offset)?.expect(tag)
+    }
+
+    /// Returns `self` when the tag matches, otherwise [`FileParseError::InvalidFileFormat`].
+    pub fn expect(self, tag: u8) -> Result<Self, FileParseError> {
+        if self.tag == tag {
+            Ok(self)
+        } else {
+            Err(FileParseError::InvalidFileFormat)
+        }
+    }
+
+    /// Offset one past the last contents octet.
+    pub fn end(&self) -> usize {
+        self.offset + self.header_length + self.contents.len()
+    }
+
+    /// `true` for constructed encodings (bit 6 of the tag).
+    pub fn is_constructed(&self) -> bool {
+        self.tag & 0x20 != 0
+    }
+
+    /// Parses the contents as a run of elements, keeping offsets relative to the outer slice.
+    pub fn children(&self) -> Result<Vec<DerElement<'a>>, FileParseError> {
+        let base = self.offset + self.header_length;
+        let mut children = Vec::new();
+        let mut cursor = 0;
+        while cursor < self.contents.len() {
+            let mut child = DerElement::parse(self.contents, cursor)?;
+            cursor = child.end();
+            child.offset += base;
+            children.push(child);
+        }
+        Ok(children)
+    }
+
+    /// Decodes an `OBJECT IDENTIFIER` in dotted form.
+    pub fn oid(&self) -> Result<String, FileParseError> {
+        if self.tag != TAG_OID || self.contents.is_empty() {
+            return Err(FileParseError::InvalidFileFormat);
+        }
+        let mut arcs: Vec<u64> = Vec::new();
+        let mut value = 0u64;
+        for byte in self.contents {
+            if value > u64::MAX >> 7 {
+                return Err(FileParseError::ValueTooLarge);
+            }
+            value = (value << 7) | (byte & 0x7F) as u64;
+            if byte & 0x80 == 0 {
+                if arcs.is_empty() {
+                    let first = (value / 40).min(2);
+                    arcs.push(first);
+                    arcs.push(value - first * 40);
+                } else {
+                    arcs.push(value);
+                }
+                value = 0;
+            }
+        }
+        Ok(arcs
+            .iter()
+            .map(u64::to_string)
+            .collect::<Vec<_>>()
+            .join("."))
+    }
+
+    /// `INTEGER` magnitude bytes with the sign-padding zero removed.
+    pub fn integer_bytes(&self) -> Result<&'a [u8], FileParseError> {
+        if self.tag != TAG_INTEGER || self.contents.is_empty() {
+            return Err(FileParseError::InvalidFileFormat);
+        }
+        match self.contents {
+            [0, rest @ ..] if !rest.is_empty() => Ok(rest),
+            bytes => Ok(bytes),
+        }
+    }
+
+    /// Small non-negative `INTEGER` value.
+    pub fn integer_u64(&self) -> Result<u64, FileParseError> {
+        let bytes = self.integer_bytes()?;
+        if bytes.len() > 8 || self.contents[0] & 0x80 != 0 {
+            return Err(FileParseError::ValueTooLarge);
+        }
+        Ok(bytes
+            .iter()
+            .fold(0u64, |acc, byte| (acc << 8) | *byte as u64))
+    }
+
+    /// Decodes string and time types to text.
+    pub fn string(&self) -> Result<String, FileParseError> {
+        match self.tag {
+            TAG_UTF8_STRING | TAG_PRINTABLE_STRING | TAG_IA5_STRING | TAG_UTC_TIME
+            | TAG_GENERALIZED_TIME => Ok(String::from_utf8_lossy(self.contents).into_owned()),
+            TAG_T61_STRING => Ok(self.contents.iter().map(|b| *b as char).collect()),
+            TAG_BMP_STRING => {
+                let units: Vec<u16> = self
+                    .contents
+                    .as_chunks::<2>()
+                    .0
+                    .iter()
+                    .map(|chunk| u16::from_be_bytes(*chunk))
+                    .collect();
+                Ok(String::from_utf16_lossy(&units))
+            }
+            _ => Err(FileParseError::InvalidFileFormat),
+        }
+    }
+
+    /// Full encoding (header and contents) sliced from `data`, the slice this element came from.
+    pub fn raw<'b>(&self, data: &'b [u8]) -> Result<&'b [u8], FileParseError> {
+        data.get(self.offset..self.end())
+            .ok_or(FileParseError::BufferOverflow)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_long_form_length_and_children() {
+        let mut data = vec![TAG_SEQUENCE, 0x81, 0x85, TAG_OCTET_STRING, 0x81, 0x80];
+        data.extend_from_slice(&[0xAB; 0x80]);
+        data.extend_from_slice(&[TAG_NULL, 0x00]);
+        let sequence = DerElement::parse(&data, 0).unwrap();
+        assert_eq!(sequence.header_length, 3);
+        assert_eq!(sequence.end(), data.len());
+        let children = sequence.children().unwrap();
+        assert_eq!(children.len(), 2);
+        assert_eq!(children[0].offset, 3);
+        assert_eq!(children[0].contents.len(), 0x80);
+        assert_eq!(children[1].offset, 3 + 3 + 0x80);
+        assert_eq!(children[1].raw(&data).unwrap(), [TAG_NULL, 0x00]);
+    }
+
+    #[test]
+    fn decodes_oid_integer_and_strings() {
+        let oid = [
+            TAG_OID, 0x09, 0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x07, 0x02,
+        ];
+        assert_eq!(
+            DerElement::parse(&oid, 0).unwrap().oid().unwrap(),
+            "1.2.840.113549.1.7.2"
+        );
+
+        let integer = [TAG_INTEGER, 0x03, 0x00, 0x80, 0x01];
+        let integer = DerElement::parse(&integer, 0).unwrap();
+        assert_eq!(integer.integer_bytes().unwrap(), [0x80, 0x01]);
+        assert_eq!(integer.integer_u64().unwrap(), 0x8001);
+
+        let bmp = [TAG_BMP_STRING, 0x04, 0x00, b'H', 0x00, b'i'];
+        assert_eq!(DerElement::parse(&bmp, 0).unwrap().string().unwrap(), "Hi");
+    }
+
+    #[test]
+    fn rejects_indefinite_length_and_truncation() {
+        assert!(matches!(
+            DerElement::parse(&[TAG_SEQUENCE, 0x80, 0x00, 0x00], 0),
+            Err(FileParseError::UnsupportedFeature(_))
+        ));
+        assert!(matches!(
+            DerElement::parse(&[TAG_OCTET_STRING, 0x05, 0x00], 0),
+            Err(FileParseError::BufferOverflow)
+        ));
+    }
+}
@@ -0,0 +1,603 @@
+//! Dependency-free message digests used by image hashing (Authenticode, imphash).
+//!
+//! [`Sha1`] and [`Sha256`] are streaming implementations of FIPS 180-4; [`Hasher`] selects one at
+//! runtime from a [`HashAlgorithm`]. [`Md5`] (RFC 1321) only backs fingerprint hashes such as
+//! [`PE::imphash`](crate::pe::PE::imphash).
+
+/// Digest algorithm selector.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    /// SHA-1 (20-byte digest).
+    Sha1,
+    /// SHA-256 (32-byte digest).
+    Sha256,
+}
+
+/// Streaming SHA-1.
+#[derive(Clone)]
+pub struct Sha1 {
+    state: [u32; 5],
+    block: BlockBuffer,
+}
+
+/// Streaming SHA-256.
+#[derive(Clone)]
+pub struct Sha256 {
+    state: [u32; 8],
+    block: BlockBuffer,
+}
+
+/// Streaming MD5.
+#[derive(Clone)]
+pub struct Md5 {
+    state: [u32; 4],
+    block: BlockBuffer,
+}
+
+/// Runtime-selected streaming digest.
+#[derive(Clone)]
+pub enum Hasher {
+    /// SHA-1 state.
+    Sha1(Sha1),
+    /// SHA-256 state.
+    Sha256(Sha256),
+}
+
+/// 64-byte block accumulator shared by the MD-style digests.
+#[derive(Clone)]
+struct BlockBuffer {
+    buffer: [u8; 64],
+    filled: usize,
+    length: u64,
+}
+
+/// Lowercase hexadecimal rendering of a digest.
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+impl HashAlgorithm {
+    /// Digest size in bytes.
+    pub fn digest_size(self) -> usize {
+        match self {
+            HashAlgorithm::Sha1 => 20,
+            HashAlgorithm::Sha256 => 32,
+        }
+    }
+}
+
+impl BlockBuffer {
+    fn new() -> Self {
+        BlockBuffer {
+            buffer: [0; 64],
+            filled: 0,
+            length: 0,
+        }
+    }
+
+    /// Feeds `data`, calling `compress` for every complete block.
+    fn update(&mut self, mut data: &[u8], mut compress: impl FnMut(&[u8; 64])) {
+        self.length = self.length.wrapping_add(data.len() as u64);
+        if self.filled > 0 {
+            let take = (64 - self.filled).min(data.len());
+            self.buffer[self.filled..self.filled + take].copy_from_slice(&data[..take]);
+            self.filled += take;
+            data = &data[take..];
+            if self.filled < 64 {
+                return;
+            }
+            compress(&self.buffer);
+            self.filled = 0;
+        }
+        let (blocks, rest) = data.as_chunks::<64>();
+        for block in blocks {
+            compress(block);
+        }
+        self.buffer[..rest.len()].copy_from_slice(rest);
+        self.filled = rest.len();
+    }
+
+    /// Appends `0x80`, zero padding, and the big-endian bit length.
+    fn finish(&mut self, compress: impl FnMut(&[u8; 64])) {
+        let bit_length = self.length.wrapping_mul(8).to_be_bytes();
+        self.pad(bit_length, compress);
+    }
+
+    /// Appends `0x80`, zero padding, and the little-endian bit length (MD5).
+    fn finish_le(&mut self, compress: impl FnMut(&[u8; 64])) {
+        let bit_length = self.length.wrapping_mul(8).to_le_bytes();
+        self.pad(bit_length, compress);
+    }
+
+    fn pad(&mut self, bit_length: [u8; 8], mut compress: impl FnMut(&[u8; 64])) {
+        self.buffer[self.filled] = 0x80;
+        self.buffer[self.filled + 1..].fill(0);
+        if self.filled >= 56 {
+            compress(&self.buffer);
+            self.buffer.fill(0);
+        }
+        self.buffer[56..].copy_from_slice(&bit_length);
+        compress(&self.buffer);
+    }
+}
+
+impl Default for Sha1 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sha1 {
+    /// Creates a hasher in the initial state.
+    pub fn new() -> Self {
+        Sha1 {
+            state: [
+                0x6745_2301,
+                0xEFCD_AB89,
+                0x98BA_DCFE,
+                0x1032_5476,
+                0xC3D2_E1F0,
+            ],
+            block: BlockBuffer::new(),
+        }
+    }
+
+    /// Absorbs `data`.
+    pub fn update(&mut self, data: &[u8]) {
+        let state = &mut self.state;
+        self.block.update(data, |block| sha1_compress(state, block));
+    }
+
+    /// Pads the message and returns the digest.
+    pub fn finalize(mut self) -> [u8; 20] {
+        let state = &mut self.state;
+        self.block.finish(|block| sha1_compress(state, block));
+        let mut out = [0u8; 20];
+        for (chunk, word) in out.chunks_exact_mut(4).zip(self.state) {
+            chunk.copy_from_slice(&word.to_be_bytes());
+        }
+        out
+    }
+
+    /// One-shot digest of `data`.
+    pub fn digest(data: &[u8]) -> [u8; 20] {
+        let mut hasher = Self::new();
+        hasher.update(data);
+        hasher.finalize()
+    }
+}
+
+impl Default for Sha256 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sha256 {
+    /// Creates a hasher in the initial state.
+    pub fn new() -> Self {
+        Sha256 {
+            state: [
+                0x6A09_E667,
+                0xBB67_AE85,
+                0x3C6E_F372,
+                0xA54F_F53A,
+                0x510E_527F,
+                0x9B05_688C,
+                0x1F83_D9AB,
+                0x5BE0_CD19,
+            ],
+            block: BlockBuffer::new(),
+        }
+    }
+
+    /// Absorbs `data`.
+    pub fn update(&mut self, data: &[u8]) {
+        let state = &mut self.state;
+        self.block
+            .update(data, |block| sha256_compress(state, block));
+    }
+
+    /// Pads the message and returns the digest.
+    pub fn finalize(mut self) -> [u8; 32] {
+        let state = &mut self.state;
+        self.block.finish(|block| sha256_compress(state, block));
+        let mut out = [0u8; 32];
+        for (chunk, word) in out.chunks_exact_mut(4).zip(self.state) {
+            chunk.copy_from_slice(&word.to_be_bytes());
+        }
+        out
+    }
+
+    /// One-shot digest of `data`.
+    pub fn digest(data: &[u8]) -> [u8; 32] {
+        let mut hasher = Self::new();
+        hasher.update(data);
+        hasher.finalize()
+    }
+}
+
+impl Default for Md5 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Md5 {
+    /// Creates a hasher in the initial state.
+    pub fn new() -> Self {
+        Md5 {
+            state: [0x6745_2301, 0xEFCD_AB89, 0x98BA_DCFE, 0x1032_5476],
+            block: BlockBuffer::new(),
+        }
+    }
+
+    /// Absorbs `data`.
+    pub fn update(&mut self, data: &[u8]) {
+        let state = &mut self.state;
+        self.block.update(data, |block| md5_compress(state, block));
+    }
+
+    /// Pads the message and returns the digest.
+    pub fn finalize(mut self) -> [u8; 16] {
+        let state = &mut self.state;
+        self.block.finish_le(|block| md5_compress(state, block));
+        let mut out = [0u8; 16];
+        for (chunk, word) in out.chunks_exact_mut(4).zip(self.state) {
+            chunk.copy_from_slice(&word.to_le_bytes());
+        }
+        out
+    }
+
+    /// One-shot digest of `data`.
+    pub fn digest(data: &[u8]) -> [u8; 16] {
+        let mut hasher = Self::new();
+        hasher.update(data);
+        hasher.finalize()
+    }
+}
+
+impl Hasher {
+    /// Creates a hasher for `algorithm`.
+    pub fn new(algorithm: HashAlgorithm) -> Self {
+        match algorithm {
+            HashAlgorithm::Sha1 => Hasher::Sha1(Sha1::new()),
+            HashAlgorithm::Sha256 => Hasher::Sha256(Sha256::new()),
+        }
+    }
+
+    /// Absorbs `data`.
+    pub fn update(&mut self, data: &[u8]) {
+        match self {
+            Hasher::Sha1(hasher) => hasher.update(data),
+            Hasher::Sha256(hasher) => hasher.update(data),
+        }
+    }
+
+    /// Pads the message and returns the digest bytes.
+    pub fn finalize(self) -> Vec<u8> {
+        match self {
+            Hasher::Sha1(hasher) => hasher.finalize().to_vec(),
+            Hasher::Sha256(hasher) => hasher.finalize().to_vec(),
+        }
+    }
+}
+
+fn sha1_compress(state: &mut [u32; 5], block: &[u8; 64]) {
+    let mut w = [0u32; 80];
+    for (i, word) in block.as_chunks::<4>().0.iter().enumerate() {
+        w[i] = u32::from_be_bytes(*word);
+    }
+    for i in 16..80 {
+        w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e] = *state;
+    for (i, word) in w.iter().enumerate() {
+        let (f, k) = match i {
+            0..=19 => ((b & c) | (!b & d), 0x5A82_7999),
+            20..=39 => (b ^ c ^ d, 0x6ED9_EBA1),
+            40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1B_BCDC),
+            _ => (b ^ c ^ d, 0xCA62_C1D6),
+        };
+        let temp = a
+            .rotate_left(5)
+            .wrapping_add(f)
+            .wrapping_add(e)
+            .wrapping_add(k)
+            .wrapping_add(*word);
+        e = d;
+        d = c;
+        c = b.rotate_left(30);
+        b = a;
+        a = temp;
+    }
+
+    for (slot, value) in state.iter_mut().zip([a, b, c, d, e]) {
+        *slot = slot.wrapping_add(value);
+    }
+}
+
+const SHA256_K: [u32; 64] = [
+    0x428A_2F98,
+    0x7137_4491,
+    0xB5C0_FBCF,
+    0xE9B5_DBA5,
+    0x3956_C25B,
+    0x59F1_11F1,
+    0x923F_82A4,
+    0xAB1C_5ED5,
+    0xD807_AA98,
+    0x1283_5B01,
+    0x2431_85BE,
+    0x550C_7DC3,
+    0x72BE_5D74,
+    0x80DE_B1FE,
+    0x9BDC_06A7,
+    0xC19B_F174,
+    0xE49B_69C1,
+    0xEFBE_4786,
+    0x0FC1_9DC6,
+    0x240C_A1CC,
+    0x2DE9_2C6F,
+    0x4A74_84AA,
+    0x5CB0_A9DC,
+    0x76F9_88DA,
+    0x983E_5152,
+    0xA831_C66D,
+    0xB003_27C8,
+    0xBF59_7FC7,
+    0xC6E0_0BF3,
+    0xD5A7_9147,
+    0x06CA_6351,
+    0x1429_2967,
+    0x27B7_0A85,
+    0x2E1B_2138,
+    0x4D2C_6DFC,
+    0x5338_0D13,
+    0x650A_7354,
+    0x766A_0ABB,
+    0x81C2_C92E,
+    0x9272_2C85,
+    0xA2BF_E8A1,
+    0xA81A_664B,
+    0xC24B_8B70,
+    0xC76C_51A3,
+    0xD192_E819,
+    0xD699_0624,
+    0xF40E_3585,
+    0x106A_A070,
+    0x19A4_C116,
+    0x1E37_6C08,
+    0x2748_774C,
+    0x34B0_BCB5,
+    0x391C_0CB3,
+    0x4ED8_AA4A,
+    0x5B9C_CA4F,
+    0x682E_6FF3,
+    0x748F_82EE,
+    0x78A5_636F,
+    0x84C8_7814,
+    0x8CC7_0208,
+    0x90BE_FFFA,
+    0xA450_6CEB,
+    0xBEF9_A3F7,
+    0xC671_78F2,
+];
+
+fn sha256_compress(state: &mut [u32; 8], block: &[u8; 64]) {
+    let mut w = [0u32; 64];
+    for (i, word) in block.as_chunks::<4>().0.iter().enumerate() {
+        w[i] = u32::from_be_bytes(*word);
+    }
+    for i in 16..64 {
+        let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+        let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+        w[i] = w[i - 16]
+            .wrapping_add(s0)
+            .wrapping_add(w[i - 7])
+            .wrapping_add(s1);
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *state;
+    for (k, word) in SHA256_K.iter().zip(w) {
+        let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+        let ch = (e & f) ^ (!e & g);
+        let temp1 = h
+            .wrapping_add(s1)
+            .wrapping_add(ch)
+            .wrapping_add(*k)
+            .wrapping_add(word);
+        let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+        let maj = (a & b) ^ (a & c) ^ (b & c);
+        let temp2 = s0.wrapping_add(maj);
+        h = g;
+        g = f;
+        f = e;
+        e = d.wrapping_add(temp1);
+        d = c;
+        c = b;
+        b = a;
+        a = temp1.wrapping_add(temp2);
+    }
+
+    for (slot, value) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+        *slot = slot.wrapping_add(value);
+    }
+}
+
+/// MD5 additive constants, `floor(abs(sin(i + 1)) * 2^32)`.
+const MD5_K: [u32; 64] = [
+    0xD76A_A478,
+    0xE8C7_B756,
+    0x2420_70DB,
+    0xC1BD_CEEE,
+    0xF57C_0FAF,
+    0x4787_C62A,
+    0xA830_4613,
+    0xFD46_9501,
+    0x6980_98D8,
+    0x8B44_F7AF,
+    0xFFFF_5BB1,
+    0x895C_D7BE,
+    0x6B90_1122,
+    0xFD98_7193,
+    0xA679_438E,
+    0x49B4_0821,
+    0xF61E_2562,
+    0xC040_B340,
+    0x265E_5A51,
+    0xE9B6_C7AA,
+    0xD62F_105D,
+    0x0244_1453,
+    0xD8A1_E681,
+    0xE7D3_FBC8,
+    0x21E1_CDE6,
+    0xC337_07D6,
+    0xF4D5_0D87,
+    0x455A_14ED,
+    0xA9E3_E905,
+    0xFCEF_A3F8,
+    0x676F_02D9,
+    0x8D2A_4C8A,
+    0xFFFA_3942,
+    0x8771_F681,
+    0x6D9D_6122,
+    0xFDE5_380C,
+    0xA4BE_EA44,
+    0x4BDE_CFA9,
+    0xF6BB_4B60,
+    0xBEBF_BC70,
+    0x289B_7EC6,
+    0xEAA1_27FA,
+    0xD4EF_3085,
+    0x0488_1D05,
+    0xD9D4_D039,
+    0xE6DB_99E5,
+    0x1FA2_7CF8,
+    0xC4AC_5665,
+    0xF429_2244,
+    0x432A_FF97,
+    0xAB94_23A7,
+    0xFC93_A039,
+    0x655B_59C3,
+    0x8F0C_CC92,
+    0xFFEF_F47D,
+    0x8584_5DD1,
+    0x6FA8_7E4F,
+    0xFE2C_E6E0,
+    0xA301_4314,
+    0x4E08_11A1,
+    0xF753_7E82,
+    0xBD3A_F235,
+    0x2AD7_D2BB,
+    0xEB86_D391,
+];
+
+/// Per-round left rotations.
+const MD5_S: [u32; 16] = [7, 12, 17, 22, 5, 9, 14, 20, 4, 11, 16, 23, 6, 10, 15, 21];
+
+fn md5_compress(state: &mut [u32; 4], block: &[u8; 64]) {
+    let mut m = [0u32; 16];
+    for (i, word) in block.as_chunks::<4>().0.iter().enumerate() {
+        m[i] = u32::from_le_bytes(*word);
+    }
+
+    let [mut a, mut b, mut c, mut d] = *state;
+    for i in 0..64 {
+        let (f, g) = match i {
+            0..=15 => ((b & c) | (!b & d), i),
+            16..=31 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+            32..=47 => (b ^ c ^ d, (3 * i + 5) % 16),
+            _ => (c ^ (b | !d), (7 * i) % 16),
+        };
+        let rotated = a
+            .wrapping_add(f)
+            .wrapping_add(MD5_K[i])
+            .wrapping_add(m[g])
+            .rotate_left(MD5_S[(i / 16) * 4 + i % 4]);
+        a = d;
+        d = c;
+        c = b;
+        b = b.wrapping_add(rotated);
+    }
+
+    for (slot, value) in state.iter_mut().zip([a, b, c, d]) {
+        *slot = slot.wrapping_add(value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sha1_known_vectors() {
+        assert_eq!(
+            to_hex(&Sha1::digest(b"")),
+            "da39a3ee5e6b4b0d3255bfef95601890afd80709"
+        );
+        assert_eq!(
+            to_hex(&Sha1::digest(b"abc")),
+            "a9993e364706816aba3e25717850c26c9cd0d89d"
+        );
+        assert_eq!(
+            to_hex(&Sha1::digest(
+                b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"
+            )),
+            "84983e441c3bd26ebaae4aa1f95129e5e54670f1"
+        );
+    }
+
+    #[test]
+    fn sha256_known_vectors() {
+        assert_eq!(
+            to_hex(&Sha256::digest(b"")),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(
+            to_hex(&Sha256::digest(b"abc")),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            to_hex(&Sha256::digest(
+                b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"
+            )),
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+        );
+    }
+
+    #[test]
+    fn md5_known_vectors() {
+        assert_eq!(
+            to_hex(&Md5::digest(b"")),
+            "d41d8cd98f00b204e9800998ecf8427e"
+        );
+        assert_eq!(
+            to_hex(&Md5::digest(b"abc")),
+            "900150983cd24fb0d6963f7d28e17f72"
+        );
+        assert_eq!(
+            to_hex(&Md5::digest(
+                b"12345678901234567890123456789012345678901234567890123456789012345678901234567890"
+            )),
+            "57edf4a22be3c955ac49da2e2107b67a"
+        );
+    }
+
+    #[test]
+    fn streaming_matches_one_shot() {
+        let data: Vec<u8> = (0..1000u32).map(|i| (i * 7) as u8).collect();
+        let mut hasher = Hasher::new(HashAlgorithm::Sha256);
+        for chunk in data.chunks(37) {
+            hasher.update(chunk);
+        }
+        assert_eq!(hasher.finalize(), Sha256::digest(&data));
+
+        let mut hasher = Hasher::new(HashAlgorithm::Sha1);
+        for chunk in data.chunks(63) {
+            hasher.update(chunk);
+        }
+        assert_eq!(hasher.finalize(), Sha1::digest(&data));
+    }
+}
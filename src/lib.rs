@@ -84,8 +84,10 @@
 //! ```
 
 // Standard
+pub mod der;
 pub mod errors;
 pub mod field;
+pub mod hash;
 pub mod reloc;
 pub mod strings;
 pub mod utils;
@@ -0,0 +1,274 @@
+//! ARM64 exception directory (`IMAGE_ARM64_RUNTIME_FUNCTION_ENTRY`) and `.xdata` records.
+//!
+//! ARM64 `.pdata` entries are two dwords: the function start RVA and either packed unwind data
+//! (`Flag != 0`) or the RVA of an `.xdata` exception information record (`Flag == 0`).
+
+use crate::errors::FileParseError;
+use crate::field::Field;
+use crate::utils::extract_u32;
+
+/// `Flag` value: `UnwindData` is the RVA of an `.xdata` record.
+pub const PDATA_REF_TO_FULL_XDATA: u8 = 0;
+/// `Flag` value: packed unwind data for a function with a canonical prolog and epilog.
+pub const PDATA_PACKED_UNWIND_FUNCTION: u8 = 1;
+/// `Flag` value: packed unwind data for a fragment without a prolog.
+pub const PDATA_PACKED_UNWIND_FRAGMENT: u8 = 2;
+
+/// `IMAGE_ARM64_RUNTIME_FUNCTION_ENTRY` — 8 bytes.
+pub struct Arm64RuntimeFunction {
+    /// Start RVA of the function (`BeginAddress`).
+    pub begin_address: Field<u32>,
+    /// Packed unwind data or `.xdata` RVA (`UnwindData`).
+    pub unwind_data: Field<u32>,
+}
+
+/// Fields decoded from packed `UnwindData` (`Flag` 1 or 2).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Arm64PackedUnwind {
+    /// `Flag` (1 = function, 2 = fragment without prolog).
+    pub flag: u8,
+    /// Function length in bytes (`Function Length * 4`).
+    pub function_length: u32,
+    /// Number of saved non-volatile FP registers `d8`-`d15` (`RegF`; 0 means none).
+    pub reg_f: u8,
+    /// Number of saved non-volatile integer registers `x19`-`x28` (`RegI`).
+    pub reg_i: u8,
+    /// `true` when the prolog homes `x0`-`x7` (`H`).
+    pub homes_parameters: bool,
+    /// Frame chaining mode (`CR`).
+    pub cr: u8,
+    /// Stack frame size in bytes (`Frame Size * 16`).
+    pub frame_size: u32,
+}
+
+/// One epilog scope word in an `.xdata` record.
+pub struct Arm64EpilogScope {
+    /// Packed `Epilog Start Offset` / `Epilog Start Index` word.
+    pub raw: Field<u32>,
+}
+
+impl Arm64EpilogScope {
+    /// Epilog start offset in bytes relative to the function start.
+    pub fn start_offset(&self) -> u32 {
+        (self.raw.value & 0x3_FFFF) * 4
+    }
+
+    /// Byte index of the first unwind code describing this epilog.
+    pub fn start_index(&self) -> u16 {
+        (self.raw.value >> 22) as u16
+    }
+}
+
+/// ARM64 `.xdata` exception information record.
+pub struct Arm64XData {
+    /// Absolute file offset of the first header word.
+    pub offset: usize,
+    /// First header word (`Function Length`, `Vers`, `X`, `E`, `Epilog Count`, `Code Words`).
+    pub header: Field<u32>,
+    /// Extended header word when `Epilog Count` and `Code Words` are both zero.
+    pub extended_header: Option<Field<u32>>,
+    /// Epilog scopes (empty when `E` is set).
+    pub epilog_scopes: Vec<Arm64EpilogScope>,
+    /// Absolute file offset of the unwind code bytes.
+    pub unwind_codes_offset: usize,
+    /// Raw unwind code bytes (`Code Words * 4`).
+    pub unwind_codes: Vec<u8>,
+    /// Exception handler RVA (`X` set).
+    pub exception_handler: Option<Field<u32>>,
+    /// Absolute file offset of the language-specific handler data.
+    pub handler_data_offset: Option<usize>,
+}
+
+impl Arm64XData {
+    /// Parses an `.xdata` record at `offset`.
+    pub fn parse(buffer: &[u8], offset: usize) -> Result<Self, FileParseError> {
+        let header = Field::new(extract_u32(buffer, offset)?, offset, 4);
+        let mut cursor = offset + 4;
+
+        let mut epilog_count = (header.value >> 22) & 0x1F;
+        let mut code_words = header.value >> 27;
+        let extended_header = if epilog_count == 0 && code_words == 0 {
+            let word = Field::new(extract_u32(buffer, cursor)?, cursor, 4);
+            epilog_count = word.value & 0xFFFF;
+            code_words = (word.value >> 16) & 0xFF;
+            cursor += 4;
+            Some(word)
+        } else {
+            None
+        };
+
+        let single_epilog = header.value & (1 << 21) != 0;
+        let mut epilog_scopes = Vec::new();
+        if !single_epilog {
+            for _ in 0..epilog_count {
+                epilog_scopes.push(Arm64EpilogScope {
+                    raw: Field::new(extract_u32(buffer, cursor)?, cursor, 4),
+                });
+                cursor += 4;
+            }
+        }
+
+        let unwind_codes_offset = cursor;
+        let codes_len = code_words as usize * 4;
+        let unwind_codes = buffer
+            .get(cursor..cursor + codes_len)
+            .ok_or(FileParseError::BufferOverflow)?
+            .to_vec();
+        cursor += codes_len;
+
+        let (exception_handler, handler_data_offset) = if header.value & (1 << 20) != 0 {
+            (
+                Some(Field::new(extract_u32(buffer, cursor)?, cursor, 4)),
+                Some(cursor + 4),
+            )
+        } else {
+            (None, None)
+        };
+
+        Ok(Arm64XData {
+            offset,
+            header,
+            extended_header,
+            epilog_scopes,
+            unwind_codes_offset,
+            unwind_codes,
+            exception_handler,
+            handler_data_offset,
+        })
+    }
+
+    /// Function length in bytes (`Function Length * 4`).
+    pub fn function_length(&self) -> u32 {
+        (self.header.value & 0x3_FFFF) * 4
+    }
+
+    /// `Vers` (currently always 0).
+    pub fn version(&self) -> u8 {
+        ((self.header.value >> 18) & 0x3) as u8
+    }
+
+    /// `X` — exception handler data follows the unwind codes.
+    pub fn has_exception_data(&self) -> bool {
+        self.header.value & (1 << 20) != 0
+    }
+
+    /// `E` — a single epilog is packed into the header instead of scope words.
+    pub fn single_epilog(&self) -> bool {
+        self.header.value & (1 << 21) != 0
+    }
+
+    /// Epilog scope count, or the first epilog unwind code index when [`Self::single_epilog`].
+    pub fn epilog_count(&self) -> u32 {
+        match &self.extended_header {
+            Some(word) => word.value & 0xFFFF,
+            None => (self.header.value >> 22) & 0x1F,
+        }
+    }
+
+    /// Number of 32-bit words holding unwind codes.
+    pub fn code_words(&self) -> u32 {
+        match &self.extended_header {
+            Some(word) => (word.value >> 16) & 0xFF,
+            None => self.header.value >> 27,
+        }
+    }
+}
+
+impl Arm64RuntimeFunction {
+    /// Size of `IMAGE_ARM64_RUNTIME_FUNCTION_ENTRY` in bytes.
+    pub const SIZE: usize = 8;
+
+    /// Parses one runtime function entry at `offset`.
+    pub fn parse(buffer: &[u8], offset: usize) -> Result<Self, FileParseError> {
+        if buffer.len() < offset + Self::SIZE {
+            return Err(FileParseError::BufferOverflow);
+        }
+
+        Ok(Arm64RuntimeFunction {
+            begin_address: Field::new(extract_u32(buffer, offset)?, offset, 4),
+            unwind_data: Field::new(extract_u32(buffer, offset + 4)?, offset + 4, 4),
+        })
+    }
+
+    /// `Flag` (low two bits of [`Self::unwind_data`]).
+    pub fn flag(&self) -> u8 {
+        (self.unwind_data.value & 0x3) as u8
+    }
+
+    /// Decodes packed unwind data; `None` when the entry references `.xdata`.
+    pub fn packed(&self) -> Option<Arm64PackedUnwind> {
+        let flag = self.flag();
+        if flag == PDATA_REF_TO_FULL_XDATA {
+            return None;
+        }
+        let data = self.unwind_data.value;
+        Some(Arm64PackedUnwind {
+            flag,
+            function_length: ((data >> 2) & 0x7FF) * 4,
+            reg_f: ((data >> 13) & 0x7) as u8,
+            reg_i: ((data >> 16) & 0xF) as u8,
+            homes_parameters: (data >> 20) & 0x1 != 0,
+            cr: ((data >> 21) & 0x3) as u8,
+            frame_size: ((data >> 23) & 0x1FF) * 16,
+        })
+    }
+
+    /// RVA of the `.xdata` record when `Flag == 0`.
+    pub fn xdata_rva(&self) -> Option<u32> {
+        (self.flag() == PDATA_REF_TO_FULL_XDATA).then_some(self.unwind_data.value)
+    }
+
+    /// Parses the `.xdata` record; `Ok(None)` for packed entries.
+    pub fn xdata(
+        &self,
+        buffer: &[u8],
+        rva_to_offset: impl Fn(u32) -> Result<usize, FileParseError>,
+    ) -> Result<Option<Arm64XData>, FileParseError> {
+        match self.xdata_rva() {
+            Some(rva) => Ok(Some(Arm64XData::parse(buffer, rva_to_offset(rva)?)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Parsed ARM64 exception directory.
+pub struct Arm64ExceptionDirectory {
+    /// Absolute file offset of the first entry.
+    pub offset: usize,
+    /// Runtime function entries.
+    pub entries: Vec<Arm64RuntimeFunction>,
+}
+
+impl Arm64ExceptionDirectory {
+    /// Parses `IMAGE_ARM64_RUNTIME_FUNCTION_ENTRY` records from the exception data directory.
+    pub fn parse(buffer: &[u8], offset: usize, size: usize) -> Result<Self, FileParseError> {
+        if size == 0 {
+            return Ok(Arm64ExceptionDirectory {
+                offset,
+                entries: Vec::new(),
+            });
+        }
+
+        if !size.is_multiple_of(Arm64RuntimeFunction::SIZE) {
+            return Err(FileParseError::InvalidFileFormat);
+        }
+
+        let end = offset
+            .checked_add(size)
+            .ok_or(FileParseError::BufferOverflow)?;
+        if buffer.len() < end {
+            return Err(FileParseError::BufferOverflow);
+        }
+
+        let count = size / Arm64RuntimeFunction::SIZE;
+        let mut entries = Vec::with_capacity(count);
+        for i in 0..count {
+            entries.push(Arm64RuntimeFunction::parse(
+                buffer,
+                offset + i * Arm64RuntimeFunction::SIZE,
+            )?);
+        }
+
+        Ok(Arm64ExceptionDirectory { offset, entries })
+    }
+}
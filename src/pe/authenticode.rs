@@ -0,0 +1,93 @@
+//! Authenticode image digest.
+//!
+//! The digest covers the whole file except the optional header `CheckSum`, the
+//! `IMAGE_DIRECTORY_ENTRY_SECURITY` entry, and the certificate table it points to. Section data is
+//! hashed in `PointerToRawData` order, followed by any trailing data outside the certificate table.
+
+use crate::errors::FileParseError;
+use crate::hash::{HashAlgorithm, Hasher};
+use crate::pe::{header, PE};
+
+impl PE {
+    /// Computes the Authenticode digest of the image with `algorithm`.
+    pub fn authenticode_digest(&self, algorithm: HashAlgorithm) -> Result<Vec<u8>, FileParseError> {
+        let buffer = &self.buffer;
+        let checksum = &self.optional_header.checksum;
+        let size_of_headers = self.optional_header.size_of_headers.value as usize;
+
+        // Ranges excluded from the header hash, in file order.
+        let mut skips = vec![(checksum.offset, checksum.offset + checksum.size)];
+        let mut certificates = None;
+        if self.optional_header.has_data_directory(header::SECURITY) {
+            let security = &self.optional_header.data_directories[header::SECURITY];
+            let start = security.virtual_address.offset;
+            skips.push((start, security.size.offset + security.size.size));
+            let (offset, size) = (
+                security.virtual_address.value as usize,
+                security.size.value as usize,
+            );
+            if offset != 0 && size != 0 {
+                let end = offset
+                    .checked_add(size)
+                    .ok_or(FileParseError::BufferOverflow)?;
+                if end > buffer.len() {
+                    return Err(FileParseError::BufferOverflow);
+                }
+                certificates = Some((offset, end));
+            }
+        }
+
+        let mut hasher = Hasher::new(algorithm);
+        let headers = buffer
+            .get(..size_of_headers)
+            .ok_or(FileParseError::BufferOverflow)?;
+        let mut cursor = 0;
+        for (start, end) in skips {
+            hasher.update(
+                headers
+                    .get(cursor..start)
+                    .ok_or(FileParseError::BufferOverflow)?,
+            );
+            cursor = end;
+        }
+        hasher.update(
+            headers
+                .get(cursor..)
+                .ok_or(FileParseError::BufferOverflow)?,
+        );
+
+        let mut sections: Vec<_> = self
+            .sections
+            .iter()
+            .filter(|section| section.size_of_raw_data.value != 0)
+            .collect();
+        sections.sort_by_key(|section| section.pointer_to_raw_data.value);
+        let mut sum_of_bytes_hashed = size_of_headers;
+        for section in sections {
+            let start = section.pointer_to_raw_data.value as usize;
+            let end = start
+                .checked_add(section.size_of_raw_data.value as usize)
+                .ok_or(FileParseError::BufferOverflow)?;
+            hasher.update(
+                buffer
+                    .get(start..end)
+                    .ok_or(FileParseError::BufferOverflow)?,
+            );
+            sum_of_bytes_hashed = sum_of_bytes_hashed.max(end);
+        }
+
+        // Trailing data (overlay, COFF symbols) minus the certificate table.
+        if sum_of_bytes_hashed < buffer.len() {
+            let trailing = sum_of_bytes_hashed..buffer.len();
+            match certificates {
+                Some((start, end)) if start < trailing.end && end > trailing.start => {
+                    hasher.update(&buffer[trailing.start..start.max(trailing.start)]);
+                    hasher.update(&buffer[end.min(trailing.end)..]);
+                }
+                _ => hasher.update(&buffer[trailing]),
+            }
+        }
+
+        Ok(hasher.finalize())
+    }
+}
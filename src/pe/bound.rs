@@ -2,12 +2,28 @@
 //!
 //! The bound import data directory points at an array of descriptors. Module
 //! name offsets are relative to the start of the bound import table, not RVAs.
+//!
+//! A bound image stores precomputed addresses in its IATs and marks each bound import descriptor
+//! with a `TimeDateStamp` of `-1`. The loader keeps those addresses when the timestamps recorded
+//! here match the DLLs (and forwarded-to DLLs) it actually loads.
+
+use std::collections::HashMap;
+use std::ops::Range;
 
 use crate::errors::FileParseError;
 use crate::field::Field;
+use crate::pe::export::{Exports, FunctionExport};
+use crate::pe::header::{self, PEType};
+use crate::pe::import_builder::ImportSymbol;
 use crate::pe::section::PeSection;
+use crate::pe::PE;
 use crate::utils::{extract_u16, extract_u32};
 
+/// Import descriptor `TimeDateStamp` / `ForwarderChain` of DLLs bound through this directory.
+const BOUND_MARKER: u32 = u32::MAX;
+/// Longest forwarder chain followed while binding.
+const MAX_FORWARDER_DEPTH: usize = 16;
+
 /// `IMAGE_BOUND_FORWARDER_REF` — 8 bytes.
 pub struct BoundForwarderRef {
     /// Bound timestamp of the forwarded module.
@@ -141,6 +157,329 @@ impl BoundImportDirectory {
     }
 }
 
+impl PE {
+    /// Removes the bound import directory and unbinds every import descriptor.
+    ///
+    /// The directory bytes are zeroed, IAT slots of bound descriptors are restored from their ILT
+    /// and `TimeDateStamp` is cleared (a `ForwarderChain` of `-1` is cleared too).
+    pub fn strip_bound_imports(&mut self) -> Result<(), FileParseError> {
+        let imports = self.imports()?;
+        let bound: Vec<_> = imports
+            .dlls
+            .into_iter()
+            .filter(|dll| dll.descriptor.time_date_stamp.value != 0)
+            .collect();
+        if let Some(dll) = bound
+            .iter()
+            .find(|dll| dll.descriptor.original_first_thunk.value == 0)
+        {
+            return Err(FileParseError::UnsupportedFeature(format!(
+                "bound import of {} has no ILT to restore its IAT from",
+                dll.dll_name
+            )));
+        }
+
+        if self
+            .optional_header
+            .has_data_directory(header::BOUND_IMPORT)
+        {
+            let entry = &self.optional_header.data_directories[header::BOUND_IMPORT];
+            let (rva, size) = (entry.virtual_address.value, entry.size.value);
+            if rva != 0 {
+                let start = self.rva_to_offset(rva)?;
+                self.buffer
+                    .get_mut(start..start + size as usize)
+                    .ok_or(FileParseError::BufferOverflow)?
+                    .fill(0);
+            }
+            self.sync_data_directory_rva(header::BOUND_IMPORT, 0)?;
+            self.sync_data_directory_size(header::BOUND_IMPORT, 0)?;
+        }
+
+        for mut dll in bound {
+            self.restore_iat_from_lookup(&dll)?;
+            let descriptor = &mut dll.descriptor;
+            descriptor.time_date_stamp.update(&mut self.buffer, 0)?;
+            if descriptor.forwarder_chain.value == BOUND_MARKER {
+                descriptor.forwarder_chain.update(&mut self.buffer, 0)?;
+            }
+        }
+        self.sync_layout()
+    }
+
+    /// Binds imports against the DLLs returned by `resolver` and returns how many were bound.
+    ///
+    /// A DLL is bound when `resolver` returns its image, its descriptor has an ILT, and every
+    /// symbol resolves to an export, following forwarders through `resolver`. Its IAT slots then
+    /// hold the export VAs for the target's preferred `ImageBase` and its descriptor is marked
+    /// bound. The new bound import directory, with one forwarder reference per forwarded-to DLL,
+    /// is written right after the section table; existing bindings are stripped only once it is
+    /// known to fit there. Inserting a section later moves the directory past the new header.
+    pub fn bind_imports<'a>(
+        &mut self,
+        mut resolver: impl FnMut(&str) -> Option<&'a PE>,
+    ) -> Result<usize, FileParseError> {
+        if !self
+            .optional_header
+            .has_data_directory(header::BOUND_IMPORT)
+        {
+            return Err(FileParseError::UnsupportedFeature(
+                "image has no bound import data directory slot".to_string(),
+            ));
+        }
+        let thunk_size = match self.optional_header.pe_type()? {
+            PEType::PE32 => 4,
+            PEType::PE32Plus => 8,
+        };
+
+        let mut exports = HashMap::new();
+        let mut bindings = Vec::new();
+        'dlls: for dll in self.imports()?.dlls {
+            if dll.descriptor.original_first_thunk.value == 0 {
+                continue;
+            }
+            let Some(target) = resolver(&dll.dll_name) else {
+                continue;
+            };
+            let mut forwards: Vec<(String, u32)> = Vec::new();
+            let mut slots = Vec::with_capacity(dll.entries.len());
+            for entry in &dll.entries {
+                let symbol = ImportSymbol::from(entry);
+                let Some(address) = bind_symbol(
+                    &mut resolver,
+                    &mut exports,
+                    &dll.dll_name,
+                    target,
+                    symbol,
+                    &mut forwards,
+                )?
+                else {
+                    continue 'dlls;
+                };
+                slots.push(address);
+            }
+            let module = (
+                dll.dll_name.clone(),
+                target.coff_header.time_date_stamp.value,
+            );
+            bindings.push((dll, slots, module, forwards));
+        }
+
+        let mut names: Vec<String> = Vec::new();
+        let mut name_index = |name: &str| match names.iter().position(|known| known == name) {
+            Some(index) => index,
+            None => {
+                names.push(name.to_string());
+                names.len() - 1
+            }
+        };
+        let mut records = Vec::new();
+        for (_, _, (name, stamp), forwards) in &bindings {
+            records.push((*stamp, name_index(name), forwards.len() as u16));
+            for (forward, stamp) in forwards {
+                records.push((*stamp, name_index(forward), 0));
+            }
+        }
+        let names_start = (records.len() + 1) * BoundImportDescriptor::SIZE;
+        let mut name_offsets = Vec::with_capacity(names.len());
+        let mut table = vec![0u8; names_start];
+        for name in &names {
+            name_offsets
+                .push(u16::try_from(table.len()).map_err(|_| FileParseError::ValueTooLarge)?);
+            table.extend_from_slice(name.as_bytes());
+            table.push(0);
+        }
+        table.resize(table.len().next_multiple_of(4), 0);
+        for (index, (stamp, name, count)) in records.iter().enumerate() {
+            let at = index * BoundImportDescriptor::SIZE;
+            table[at..at + 4].copy_from_slice(&stamp.to_le_bytes());
+            table[at + 4..at + 6].copy_from_slice(&name_offsets[*name].to_le_bytes());
+            table[at + 6..at + 8].copy_from_slice(&count.to_le_bytes());
+        }
+
+        let (start, limit) = self.header_slack();
+        let end = start + table.len();
+        if !bindings.is_empty() {
+            let current = self.bound_import_range()?;
+            let slack = self
+                .buffer
+                .get(start..end)
+                .ok_or(FileParseError::BufferOverflow)?;
+            let taken = (start..end)
+                .zip(slack)
+                .any(|(offset, &byte)| byte != 0 && !current.contains(&offset));
+            if end > limit || taken {
+                return Err(FileParseError::UnsupportedFeature(
+                    "no zero header slack for the bound import directory".to_string(),
+                ));
+            }
+        }
+        self.strip_bound_imports()?;
+
+        if !bindings.is_empty() {
+            self.buffer[start..end].copy_from_slice(&table);
+            let rva = u32::try_from(start).map_err(|_| FileParseError::ValueTooLarge)?;
+            self.sync_data_directory_rva(header::BOUND_IMPORT, rva)?;
+            self.sync_data_directory_size(header::BOUND_IMPORT, table.len() as u32)?;
+        }
+
+        let bound = bindings.len();
+        for (mut dll, slots, _, _) in bindings {
+            let first_thunk = dll.descriptor.first_thunk.value;
+            for (index, address) in slots.iter().enumerate() {
+                let offset = self.rva_to_offset(first_thunk + (index * thunk_size) as u32)?;
+                self.buffer[offset..offset + thunk_size]
+                    .copy_from_slice(&address.to_le_bytes()[..thunk_size]);
+            }
+            let descriptor = &mut dll.descriptor;
+            descriptor
+                .time_date_stamp
+                .update(&mut self.buffer, BOUND_MARKER)?;
+            descriptor
+                .forwarder_chain
+                .update(&mut self.buffer, BOUND_MARKER)?;
+        }
+        self.sync_layout()?;
+        Ok(bound)
+    }
+}
+
+impl PE {
+    /// Moves a bound import directory overlapping the section header about to be written at
+    /// `header` right past it, or strips the binding when the header slack has no zero room left.
+    pub(crate) fn make_room_for_section_header(
+        &mut self,
+        header: Range<usize>,
+    ) -> Result<(), FileParseError> {
+        let current = self.bound_import_range()?;
+        if current.is_empty() || current.end <= header.start || current.start >= header.end {
+            return Ok(());
+        }
+        let (_, limit) = self.header_slack();
+        let moved = header.end..header.end + current.len();
+        let free = moved.end <= limit
+            && self
+                .buffer
+                .get(current.end.max(moved.start)..moved.end)
+                .is_some_and(|rest| rest.iter().all(|&byte| byte == 0));
+        if !free {
+            return self.strip_bound_imports();
+        }
+        let table = self.buffer[current.clone()].to_vec();
+        self.buffer[current].fill(0);
+        self.buffer[moved.clone()].copy_from_slice(&table);
+        let rva = u32::try_from(moved.start).map_err(|_| FileParseError::ValueTooLarge)?;
+        self.sync_data_directory_rva(header::BOUND_IMPORT, rva)
+    }
+
+    /// File range of the bound import directory; empty when there is none.
+    fn bound_import_range(&self) -> Result<Range<usize>, FileParseError> {
+        if !self
+            .optional_header
+            .has_data_directory(header::BOUND_IMPORT)
+        {
+            return Ok(0..0);
+        }
+        let entry = &self.optional_header.data_directories[header::BOUND_IMPORT];
+        let (rva, size) = (entry.virtual_address.value, entry.size.value as usize);
+        if rva == 0 {
+            return Ok(0..0);
+        }
+        let start = self.rva_to_offset(rva)?;
+        Ok(start..start + size)
+    }
+
+    /// End of the section table and the first offset past the header slack behind it.
+    fn header_slack(&self) -> (usize, usize) {
+        let start = match self.sections.last() {
+            Some(section) => section.characteristics.offset + section.characteristics.size,
+            None => {
+                self.optional_header.magic.offset
+                    + self.coff_header.size_of_optional_header.value as usize
+            }
+        };
+        let limit = self
+            .sections
+            .iter()
+            .filter(|section| section.size_of_raw_data.value != 0)
+            .map(|section| section.pointer_to_raw_data.value as usize)
+            .min()
+            .unwrap_or(self.buffer.len())
+            .min(self.optional_header.size_of_headers.value as usize);
+        (start, limit)
+    }
+}
+
+/// Resolves `symbol` exported by `target` to a VA, following forwarders through `resolver` and
+/// recording each forwarded-to DLL with its timestamp in `forwards`. `Ok(None)` when unresolved.
+fn bind_symbol<'a>(
+    resolver: &mut impl FnMut(&str) -> Option<&'a PE>,
+    exports: &mut HashMap<String, Option<Exports>>,
+    module: &str,
+    target: &'a PE,
+    symbol: ImportSymbol,
+    forwards: &mut Vec<(String, u32)>,
+) -> Result<Option<u64>, FileParseError> {
+    let (mut module, mut target, mut symbol) = (module.to_string(), target, symbol);
+    for _ in 0..MAX_FORWARDER_DEPTH {
+        let key = module.to_ascii_lowercase();
+        if !exports.contains_key(&key) {
+            exports.insert(key.clone(), target.exports()?);
+        }
+        let Some(table) = &exports[&key] else {
+            return Ok(None);
+        };
+        let ordinal = match &symbol {
+            ImportSymbol::Ordinal(ordinal) => Some(*ordinal),
+            ImportSymbol::Name { name, .. } => table
+                .named
+                .iter()
+                .find(|export| export.name == *name)
+                .map(|export| export.ordinal),
+        };
+        let function = ordinal.and_then(|ordinal| {
+            table.functions.iter().find(|function| match function {
+                FunctionExport::Local { ordinal: o, .. }
+                | FunctionExport::Forwarder { ordinal: o, .. } => *o == ordinal,
+            })
+        });
+        match function {
+            None => return Ok(None),
+            Some(FunctionExport::Local { function_rva, .. }) => {
+                let base = target.optional_header.image_base.value.value();
+                return Ok(Some(base + function_rva.value as u64));
+            }
+            Some(FunctionExport::Forwarder { forwarder, .. }) => {
+                let Some((dll, name)) = forwarder.rsplit_once('.') else {
+                    return Ok(None);
+                };
+                let next = format!("{dll}.dll");
+                let Some(next_target) = resolver(&next) else {
+                    return Ok(None);
+                };
+                if !forwards
+                    .iter()
+                    .any(|(known, _)| known.eq_ignore_ascii_case(&next))
+                {
+                    forwards.push((next.clone(), next_target.coff_header.time_date_stamp.value));
+                }
+                symbol = match name.strip_prefix('#') {
+                    Some(ordinal) => match ordinal.parse() {
+                        Ok(ordinal) => ImportSymbol::Ordinal(ordinal),
+                        Err(_) => return Ok(None),
+                    },
+                    None => ImportSymbol::Name {
+                        hint: 0,
+                        name: name.to_string(),
+                    },
+                };
+                (module, target) = (next, next_target);
+            }
+        }
+    }
+    Ok(None)
+}
+
 fn read_name(buffer: &[u8], table_base: usize, name_offset: u16) -> Result<String, FileParseError> {
     let off = table_base
         .checked_add(name_offset as usize)
@@ -1,7 +1,12 @@
 //! Authenticode certificate table (`IMAGE_DIRECTORY_ENTRY_SECURITY`).
+//!
+//! The table is not mapped into memory: it lives in the overlay, starts on an 8-byte file offset,
+//! and every `WIN_CERTIFICATE` entry is padded to a multiple of 8 bytes.
 
 use crate::errors::FileParseError;
 use crate::field::Field;
+use crate::pe::pkcs7::SignedData;
+use crate::pe::{header, PE};
 use crate::utils::{extract_u16, extract_u32};
 
 /// `WIN_CERT_REVISION_2_0`.
@@ -21,7 +26,7 @@ pub struct WinCertificate {
     pub data_offset: usize,
 }
 
-/// Read-only view of the certificate table overlay.
+/// Parsed view of the certificate table overlay.
 pub struct CertificateTable {
     /// File offset of the first `WIN_CERTIFICATE` (`IMAGE_DIRECTORY_ENTRY_SECURITY` uses a file offset, not an RVA).
     pub offset: usize,
@@ -65,6 +70,17 @@ impl WinCertificate {
             .get(self.data_offset..end)
             .ok_or(FileParseError::BufferOverflow)
     }
+
+    /// Decodes the PKCS#7 `SignedData` payload of a `WIN_CERT_TYPE_PKCS_SIGNED_DATA` entry.
+    pub fn signed_data(&self, buffer: &[u8]) -> Result<SignedData, FileParseError> {
+        if self.certificate_type.value != WIN_CERT_TYPE_PKCS_SIGNED_DATA {
+            return Err(FileParseError::UnsupportedFeature(format!(
+                "WIN_CERTIFICATE type {:#06x}",
+                self.certificate_type.value
+            )));
+        }
+        SignedData::parse(self.data(buffer)?)
+    }
 }
 
 impl CertificateTable {
@@ -111,6 +127,81 @@ impl CertificateTable {
     }
 }
 
+impl PE {
+    /// Removes the certificate table from the overlay and zeroes `IMAGE_DIRECTORY_ENTRY_SECURITY`.
+    ///
+    /// Does nothing when the image is unsigned.
+    pub fn strip_signatures(&mut self) -> Result<(), FileParseError> {
+        let Some(table) = self.certificates()? else {
+            return Ok(());
+        };
+        self.check_certificate_placement(table.offset)?;
+        self.buffer.drain(table.offset..table.offset + table.size);
+        self.sync_data_directory_rva(header::SECURITY, 0)?;
+        self.sync_data_directory_size(header::SECURITY, 0)?;
+        self.sync_layout()
+    }
+
+    /// Replaces every existing signature with a single `WIN_CERT_TYPE_PKCS_SIGNED_DATA` entry
+    /// holding `pkcs7`.
+    pub fn set_signature(&mut self, pkcs7: &[u8]) -> Result<(), FileParseError> {
+        self.strip_signatures()?;
+        self.append_signature(pkcs7)
+    }
+
+    /// Appends a `WIN_CERT_TYPE_PKCS_SIGNED_DATA` entry holding `pkcs7` to the certificate table.
+    ///
+    /// Unsigned images get a new table at the end of the file; the file is first zero-padded to an
+    /// 8-byte boundary, and that padding is covered by [`PE::authenticode_digest`].
+    pub fn append_signature(&mut self, pkcs7: &[u8]) -> Result<(), FileParseError> {
+        if !self.optional_header.has_data_directory(header::SECURITY) {
+            return Err(FileParseError::UnsupportedFeature(
+                "image without a security data directory".to_string(),
+            ));
+        }
+        let length = u32::try_from(WinCertificate::HEADER_SIZE + pkcs7.len())
+            .map_err(|_| FileParseError::ValueTooLarge)?;
+
+        let (offset, insert_at) = match self.certificates()? {
+            Some(table) => {
+                self.check_certificate_placement(table.offset)?;
+                (table.offset, table.offset + align_up(table.size, 8))
+            }
+            None => {
+                let offset = align_up(self.buffer.len(), 8);
+                self.buffer.resize(offset, 0);
+                (offset, offset)
+            }
+        };
+        if insert_at > self.buffer.len() {
+            return Err(FileParseError::BufferOverflow);
+        }
+
+        let mut entry = Vec::with_capacity(align_up(length as usize, 8));
+        entry.extend_from_slice(&length.to_le_bytes());
+        entry.extend_from_slice(&WIN_CERT_REVISION_2_0.to_le_bytes());
+        entry.extend_from_slice(&WIN_CERT_TYPE_PKCS_SIGNED_DATA.to_le_bytes());
+        entry.extend_from_slice(pkcs7);
+        entry.resize(align_up(entry.len(), 8), 0);
+
+        let size = u32::try_from(insert_at - offset + entry.len())
+            .map_err(|_| FileParseError::ValueTooLarge)?;
+        let offset = u32::try_from(offset).map_err(|_| FileParseError::ValueTooLarge)?;
+        self.buffer.splice(insert_at..insert_at, entry);
+        self.sync_data_directory_rva(header::SECURITY, offset)?;
+        self.sync_data_directory_size(header::SECURITY, size)?;
+        self.sync_layout()
+    }
+
+    /// Rejects tables that overlap headers or section data, which cannot be resized safely.
+    fn check_certificate_placement(&self, offset: usize) -> Result<(), FileParseError> {
+        if offset < self.image_end_offset() {
+            return Err(FileParseError::InvalidFileFormat);
+        }
+        Ok(())
+    }
+}
+
 fn align_up(value: usize, alignment: usize) -> usize {
     (value + alignment - 1) & !(alignment - 1)
 }
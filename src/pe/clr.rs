@@ -1,8 +1,13 @@
 //! CLR / .NET metadata directory (`IMAGE_COR20_HEADER`).
+//!
+//! Also covers the directories the header points at besides metadata: embedded managed resources,
+//! the strong-name signature range and the VTableFixups table.
 
 use crate::errors::FileParseError;
 use crate::field::Field;
+use crate::pe::clr_metadata::Metadata;
 use crate::pe::header::DataDirectoryEntry;
+use crate::pe::PE;
 use crate::utils::{extract_u16, extract_u32};
 
 /// Minimum size of `IMAGE_COR20_HEADER`.
@@ -10,6 +15,19 @@ pub const COR20_HEADER_SIZE: usize = 72;
 
 /// `COMIMAGE_FLAGS_ILONLY`.
 pub const COMIMAGE_FLAGS_ILONLY: u32 = 0x0000_0001;
+/// `COMIMAGE_FLAGS_STRONGNAMESIGNED`.
+pub const COMIMAGE_FLAGS_STRONGNAMESIGNED: u32 = 0x0000_0008;
+
+/// `COR_VTABLE_32BIT`: slots are 32 bits wide.
+pub const COR_VTABLE_32BIT: u16 = 0x01;
+/// `COR_VTABLE_64BIT`: slots are 64 bits wide.
+pub const COR_VTABLE_64BIT: u16 = 0x02;
+/// `COR_VTABLE_FROM_UNMANAGED`: transition from unmanaged to managed code.
+pub const COR_VTABLE_FROM_UNMANAGED: u16 = 0x04;
+/// `COR_VTABLE_FROM_UNMANAGED_RETAIN_APPDOMAIN`: unmanaged transition keeping the caller's domain.
+pub const COR_VTABLE_FROM_UNMANAGED_RETAIN_APPDOMAIN: u16 = 0x08;
+/// `COR_VTABLE_CALL_MOST_DERIVED`: call the most derived method of the token.
+pub const COR_VTABLE_CALL_MOST_DERIVED: u16 = 0x10;
 
 /// `IMAGE_COR20_HEADER` base fields.
 pub struct Cor20Header {
@@ -71,3 +89,206 @@ impl Cor20Header {
         self.flags.value & COMIMAGE_FLAGS_ILONLY != 0
     }
 }
+
+/// One embedded managed resource: a `u32` length prefix followed by the resource bytes.
+pub struct ManagedResource {
+    /// Length prefix; its offset is the start of the entry.
+    pub length: Field<u32>,
+    /// Absolute file offset of the resource bytes.
+    pub data_offset: usize,
+    /// `ManifestResource` name when metadata is available.
+    pub name: Option<String>,
+    /// `ManifestResource` flags (`ManifestResourceAttributes`) when metadata is available.
+    pub flags: Option<u32>,
+}
+
+impl ManagedResource {
+    /// Parses the entry whose length prefix is at `offset`; it must end before `end`.
+    pub fn parse(buffer: &[u8], offset: usize, end: usize) -> Result<Self, FileParseError> {
+        let length = extract_u32(buffer, offset)?;
+        let data_end = (offset + 4)
+            .checked_add(length as usize)
+            .ok_or(FileParseError::BufferOverflow)?;
+        if data_end > end || data_end > buffer.len() {
+            return Err(FileParseError::BufferOverflow);
+        }
+        Ok(ManagedResource {
+            length: Field::new(length, offset, 4),
+            data_offset: offset + 4,
+            name: None,
+            flags: None,
+        })
+    }
+
+    /// Resource bytes.
+    pub fn data<'a>(&self, buffer: &'a [u8]) -> Result<&'a [u8], FileParseError> {
+        buffer
+            .get(self.data_offset..self.data_offset + self.length.value as usize)
+            .ok_or(FileParseError::BufferOverflow)
+    }
+}
+
+/// Strong-name signature region from `StrongNameSignature`.
+pub struct StrongNameSignature {
+    /// Absolute file offset of the signature.
+    pub offset: usize,
+    /// Size in bytes.
+    pub size: usize,
+}
+
+impl StrongNameSignature {
+    /// Signature bytes.
+    pub fn data<'a>(&self, buffer: &'a [u8]) -> Result<&'a [u8], FileParseError> {
+        buffer
+            .get(self.offset..self.offset + self.size)
+            .ok_or(FileParseError::BufferOverflow)
+    }
+}
+
+/// `IMAGE_COR_VTABLEFIXUP` entry.
+pub struct VTableFixup {
+    /// RVA of the vtable slots (`RVA`).
+    pub rva: Field<u32>,
+    /// Number of slots (`Count`).
+    pub count: Field<u16>,
+    /// `COR_VTABLE_*` flags (`Type`).
+    pub kind: Field<u16>,
+}
+
+impl VTableFixup {
+    /// Size of one `IMAGE_COR_VTABLEFIXUP` entry.
+    pub const SIZE: usize = 8;
+
+    /// Parses one entry at `offset`.
+    pub fn parse(buffer: &[u8], offset: usize) -> Result<Self, FileParseError> {
+        Ok(VTableFixup {
+            rva: Field::new(extract_u32(buffer, offset)?, offset, 4),
+            count: Field::new(extract_u16(buffer, offset + 4)?, offset + 4, 2),
+            kind: Field::new(extract_u16(buffer, offset + 6)?, offset + 6, 2),
+        })
+    }
+
+    /// Bytes per slot (4 or 8).
+    pub fn slot_size(&self) -> usize {
+        if self.kind.value & COR_VTABLE_64BIT != 0 {
+            8
+        } else {
+            4
+        }
+    }
+}
+
+impl PE {
+    /// Lists embedded managed resources from the CLR `Resources` directory.
+    ///
+    /// With metadata, entries come from `ManifestResource` rows implemented in this image and
+    /// carry their names. Without it the blob is walked sequentially, each entry starting on an
+    /// 8-byte boundary relative to the directory as compilers emit it.
+    pub fn managed_resources(&self) -> Result<Vec<ManagedResource>, FileParseError> {
+        let Some(cor20) = self.clr()? else {
+            return Ok(Vec::new());
+        };
+        let Some((start, end)) = self.clr_directory_range(&cor20.resources)? else {
+            return Ok(Vec::new());
+        };
+
+        let metadata = self.clr_metadata()?;
+        if let Some(tables) = metadata.as_ref().and_then(|m: &Metadata| m.tables.as_ref()) {
+            return tables
+                .manifest_resources
+                .iter()
+                .filter(|row| row.implementation.row == 0)
+                .map(|row| {
+                    let offset = start
+                        .checked_add(row.offset.value as usize)
+                        .ok_or(FileParseError::BufferOverflow)?;
+                    let mut resource = ManagedResource::parse(&self.buffer, offset, end)?;
+                    resource.name = Some(row.name.value.clone());
+                    resource.flags = Some(row.flags.value);
+                    Ok(resource)
+                })
+                .collect();
+        }
+
+        let mut resources = Vec::new();
+        let mut cursor = start;
+        while cursor + 4 <= end {
+            let resource = ManagedResource::parse(&self.buffer, cursor, end)?;
+            let next = resource.data_offset + resource.length.value as usize;
+            resources.push(resource);
+            cursor = start + (next - start).next_multiple_of(8);
+        }
+        Ok(resources)
+    }
+
+    /// Returns the strong-name signature region when the image carries one.
+    pub fn strong_name_signature(&self) -> Result<Option<StrongNameSignature>, FileParseError> {
+        let Some(cor20) = self.clr()? else {
+            return Ok(None);
+        };
+        Ok(self
+            .clr_directory_range(&cor20.strong_name_signature)?
+            .map(|(start, end)| StrongNameSignature {
+                offset: start,
+                size: end - start,
+            }))
+    }
+
+    /// Overwrites the strong-name signature with `signature`, zero-padding shorter input.
+    ///
+    /// The region size is fixed by the header; longer input returns `ValueTooLarge`. The
+    /// optional header checksum is recomputed afterwards.
+    pub fn set_strong_name_signature(&mut self, signature: &[u8]) -> Result<(), FileParseError> {
+        let region = self
+            .strong_name_signature()?
+            .ok_or(FileParseError::InvalidFileFormat)?;
+        if signature.len() > region.size {
+            return Err(FileParseError::ValueTooLarge);
+        }
+        let target = &mut self.buffer[region.offset..region.offset + region.size];
+        target[..signature.len()].copy_from_slice(signature);
+        target[signature.len()..].fill(0);
+        let checksum = self.calc_checksum();
+        self.optional_header
+            .checksum
+            .update(&mut self.buffer, checksum)
+    }
+
+    /// Zeroes the strong-name signature, e.g. before re-signing a patched assembly.
+    pub fn clear_strong_name_signature(&mut self) -> Result<(), FileParseError> {
+        self.set_strong_name_signature(&[])
+    }
+
+    /// Parses the `VTableFixups` table.
+    pub fn vtable_fixups(&self) -> Result<Vec<VTableFixup>, FileParseError> {
+        let Some(cor20) = self.clr()? else {
+            return Ok(Vec::new());
+        };
+        let Some((start, end)) = self.clr_directory_range(&cor20.vtable_fixups)? else {
+            return Ok(Vec::new());
+        };
+        (start..end)
+            .step_by(VTableFixup::SIZE)
+            .take((end - start) / VTableFixup::SIZE)
+            .map(|offset| VTableFixup::parse(&self.buffer, offset))
+            .collect()
+    }
+
+    /// Maps a COR20 directory to an absolute `[start, end)` file range, `None` when empty.
+    fn clr_directory_range(
+        &self,
+        directory: &DataDirectoryEntry,
+    ) -> Result<Option<(usize, usize)>, FileParseError> {
+        if directory.virtual_address.value == 0 || directory.size.value == 0 {
+            return Ok(None);
+        }
+        let start = self.rva_to_offset(directory.virtual_address.value)?;
+        let end = start
+            .checked_add(directory.size.value as usize)
+            .ok_or(FileParseError::BufferOverflow)?;
+        if end > self.buffer.len() {
+            return Err(FileParseError::BufferOverflow);
+        }
+        Ok(Some((start, end)))
+    }
+}
@@ -0,0 +1,1120 @@
+//! ECMA-335 metadata referenced by `IMAGE_COR20_HEADER::MetaData`.
+//!
+//! Covers the metadata root and stream headers, lookups into the `#Strings`, `#US`, `#Blob` and
+//! `#GUID` heaps, and the `#~` (or uncompressed `#-`) table stream. Row sizes are computed for
+//! every table so any table can be located; Module, TypeRef, TypeDef, MethodDef, MemberRef,
+//! Assembly, AssemblyRef and ManifestResource rows are decoded with their names resolved.
+
+use crate::errors::FileParseError;
+use crate::field::Field;
+use crate::pe::PE;
+use crate::utils::{extract_u16, extract_u32, extract_u64};
+
+/// Metadata root signature (`BSJB`).
+pub const METADATA_SIGNATURE: u32 = 0x424A_5342;
+
+/// `Module` table.
+pub const TABLE_MODULE: u8 = 0x00;
+/// `TypeRef` table.
+pub const TABLE_TYPE_REF: u8 = 0x01;
+/// `TypeDef` table.
+pub const TABLE_TYPE_DEF: u8 = 0x02;
+/// `FieldPtr` table (`#-` streams only).
+pub const TABLE_FIELD_PTR: u8 = 0x03;
+/// `Field` table.
+pub const TABLE_FIELD: u8 = 0x04;
+/// `MethodPtr` table (`#-` streams only).
+pub const TABLE_METHOD_PTR: u8 = 0x05;
+/// `MethodDef` table.
+pub const TABLE_METHOD_DEF: u8 = 0x06;
+/// `ParamPtr` table (`#-` streams only).
+pub const TABLE_PARAM_PTR: u8 = 0x07;
+/// `Param` table.
+pub const TABLE_PARAM: u8 = 0x08;
+/// `InterfaceImpl` table.
+pub const TABLE_INTERFACE_IMPL: u8 = 0x09;
+/// `MemberRef` table.
+pub const TABLE_MEMBER_REF: u8 = 0x0A;
+/// `Constant` table.
+pub const TABLE_CONSTANT: u8 = 0x0B;
+/// `CustomAttribute` table.
+pub const TABLE_CUSTOM_ATTRIBUTE: u8 = 0x0C;
+/// `FieldMarshal` table.
+pub const TABLE_FIELD_MARSHAL: u8 = 0x0D;
+/// `DeclSecurity` table.
+pub const TABLE_DECL_SECURITY: u8 = 0x0E;
+/// `ClassLayout` table.
+pub const TABLE_CLASS_LAYOUT: u8 = 0x0F;
+/// `FieldLayout` table.
+pub const TABLE_FIELD_LAYOUT: u8 = 0x10;
+/// `StandAloneSig` table.
+pub const TABLE_STAND_ALONE_SIG: u8 = 0x11;
+/// `EventMap` table.
+pub const TABLE_EVENT_MAP: u8 = 0x12;
+/// `EventPtr` table (`#-` streams only).
+pub const TABLE_EVENT_PTR: u8 = 0x13;
+/// `Event` table.
+pub const TABLE_EVENT: u8 = 0x14;
+/// `PropertyMap` table.
+pub const TABLE_PROPERTY_MAP: u8 = 0x15;
+/// `PropertyPtr` table (`#-` streams only).
+pub const TABLE_PROPERTY_PTR: u8 = 0x16;
+/// `Property` table.
+pub const TABLE_PROPERTY: u8 = 0x17;
+/// `MethodSemantics` table.
+pub const TABLE_METHOD_SEMANTICS: u8 = 0x18;
+/// `MethodImpl` table.
+pub const TABLE_METHOD_IMPL: u8 = 0x19;
+/// `ModuleRef` table.
+pub const TABLE_MODULE_REF: u8 = 0x1A;
+/// `TypeSpec` table.
+pub const TABLE_TYPE_SPEC: u8 = 0x1B;
+/// `ImplMap` table.
+pub const TABLE_IMPL_MAP: u8 = 0x1C;
+/// `FieldRVA` table.
+pub const TABLE_FIELD_RVA: u8 = 0x1D;
+/// `EncLog` table.
+pub const TABLE_ENC_LOG: u8 = 0x1E;
+/// `EncMap` table.
+pub const TABLE_ENC_MAP: u8 = 0x1F;
+/// `Assembly` table.
+pub const TABLE_ASSEMBLY: u8 = 0x20;
+/// `AssemblyProcessor` table.
+pub const TABLE_ASSEMBLY_PROCESSOR: u8 = 0x21;
+/// `AssemblyOS` table.
+pub const TABLE_ASSEMBLY_OS: u8 = 0x22;
+/// `AssemblyRef` table.
+pub const TABLE_ASSEMBLY_REF: u8 = 0x23;
+/// `AssemblyRefProcessor` table.
+pub const TABLE_ASSEMBLY_REF_PROCESSOR: u8 = 0x24;
+/// `AssemblyRefOS` table.
+pub const TABLE_ASSEMBLY_REF_OS: u8 = 0x25;
+/// `File` table.
+pub const TABLE_FILE: u8 = 0x26;
+/// `ExportedType` table.
+pub const TABLE_EXPORTED_TYPE: u8 = 0x27;
+/// `ManifestResource` table.
+pub const TABLE_MANIFEST_RESOURCE: u8 = 0x28;
+/// `NestedClass` table.
+pub const TABLE_NESTED_CLASS: u8 = 0x29;
+/// `GenericParam` table.
+pub const TABLE_GENERIC_PARAM: u8 = 0x2A;
+/// `MethodSpec` table.
+pub const TABLE_METHOD_SPEC: u8 = 0x2B;
+/// `GenericParamConstraint` table.
+pub const TABLE_GENERIC_PARAM_CONSTRAINT: u8 = 0x2C;
+
+/// Coded index layout: tag width and the table selected by each tag (`None` for unused tags).
+#[derive(Debug, Clone, Copy)]
+pub struct CodedIndexKind {
+    /// Number of low tag bits.
+    pub tag_bits: u32,
+    /// Table per tag value.
+    pub tables: &'static [Option<u8>],
+}
+
+/// `TypeDefOrRef` coded index.
+pub const TYPE_DEF_OR_REF: CodedIndexKind = CodedIndexKind {
+    tag_bits: 2,
+    tables: &[
+        Some(TABLE_TYPE_DEF),
+        Some(TABLE_TYPE_REF),
+        Some(TABLE_TYPE_SPEC),
+    ],
+};
+/// `HasConstant` coded index.
+pub const HAS_CONSTANT: CodedIndexKind = CodedIndexKind {
+    tag_bits: 2,
+    tables: &[Some(TABLE_FIELD), Some(TABLE_PARAM), Some(TABLE_PROPERTY)],
+};
+/// `HasCustomAttribute` coded index.
+pub const HAS_CUSTOM_ATTRIBUTE: CodedIndexKind = CodedIndexKind {
+    tag_bits: 5,
+    tables: &[
+        Some(TABLE_METHOD_DEF),
+        Some(TABLE_FIELD),
+        Some(TABLE_TYPE_REF),
+        Some(TABLE_TYPE_DEF),
+        Some(TABLE_PARAM),
+        Some(TABLE_INTERFACE_IMPL),
+        Some(TABLE_MEMBER_REF),
+        Some(TABLE_MODULE),
+        Some(TABLE_DECL_SECURITY),
+        Some(TABLE_PROPERTY),
+        Some(TABLE_EVENT),
+        Some(TABLE_STAND_ALONE_SIG),
+        Some(TABLE_MODULE_REF),
+        Some(TABLE_TYPE_SPEC),
+        Some(TABLE_ASSEMBLY),
+        Some(TABLE_ASSEMBLY_REF),
+        Some(TABLE_FILE),
+        Some(TABLE_EXPORTED_TYPE),
+        Some(TABLE_MANIFEST_RESOURCE),
+        Some(TABLE_GENERIC_PARAM),
+        Some(TABLE_GENERIC_PARAM_CONSTRAINT),
+        Some(TABLE_METHOD_SPEC),
+    ],
+};
+/// `HasFieldMarshal` coded index.
+pub const HAS_FIELD_MARSHAL: CodedIndexKind = CodedIndexKind {
+    tag_bits: 1,
+    tables: &[Some(TABLE_FIELD), Some(TABLE_PARAM)],
+};
+/// `HasDeclSecurity` coded index.
+pub const HAS_DECL_SECURITY: CodedIndexKind = CodedIndexKind {
+    tag_bits: 2,
+    tables: &[
+        Some(TABLE_TYPE_DEF),
+        Some(TABLE_METHOD_DEF),
+        Some(TABLE_ASSEMBLY),
+    ],
+};
+/// `MemberRefParent` coded index.
+pub const MEMBER_REF_PARENT: CodedIndexKind = CodedIndexKind {
+    tag_bits: 3,
+    tables: &[
+        Some(TABLE_TYPE_DEF),
+        Some(TABLE_TYPE_REF),
+        Some(TABLE_MODULE_REF),
+        Some(TABLE_METHOD_DEF),
+        Some(TABLE_TYPE_SPEC),
+    ],
+};
+/// `HasSemantics` coded index.
+pub const HAS_SEMANTICS: CodedIndexKind = CodedIndexKind {
+    tag_bits: 1,
+    tables: &[Some(TABLE_EVENT), Some(TABLE_PROPERTY)],
+};
+/// `MethodDefOrRef` coded index.
+pub const METHOD_DEF_OR_REF: CodedIndexKind = CodedIndexKind {
+    tag_bits: 1,
+    tables: &[Some(TABLE_METHOD_DEF), Some(TABLE_MEMBER_REF)],
+};
+/// `MemberForwarded` coded index.
+pub const MEMBER_FORWARDED: CodedIndexKind = CodedIndexKind {
+    tag_bits: 1,
+    tables: &[Some(TABLE_FIELD), Some(TABLE_METHOD_DEF)],
+};
+/// `Implementation` coded index.
+pub const IMPLEMENTATION: CodedIndexKind = CodedIndexKind {
+    tag_bits: 2,
+    tables: &[
+        Some(TABLE_FILE),
+        Some(TABLE_ASSEMBLY_REF),
+        Some(TABLE_EXPORTED_TYPE),
+    ],
+};
+/// `CustomAttributeType` coded index.
+pub const CUSTOM_ATTRIBUTE_TYPE: CodedIndexKind = CodedIndexKind {
+    tag_bits: 3,
+    tables: &[
+        None,
+        None,
+        Some(TABLE_METHOD_DEF),
+        Some(TABLE_MEMBER_REF),
+        None,
+    ],
+};
+/// `ResolutionScope` coded index.
+pub const RESOLUTION_SCOPE: CodedIndexKind = CodedIndexKind {
+    tag_bits: 2,
+    tables: &[
+        Some(TABLE_MODULE),
+        Some(TABLE_MODULE_REF),
+        Some(TABLE_ASSEMBLY_REF),
+        Some(TABLE_TYPE_REF),
+    ],
+};
+/// `TypeOrMethodDef` coded index.
+pub const TYPE_OR_METHOD_DEF: CodedIndexKind = CodedIndexKind {
+    tag_bits: 1,
+    tables: &[Some(TABLE_TYPE_DEF), Some(TABLE_METHOD_DEF)],
+};
+
+/// One stream header following the metadata root.
+pub struct StreamHeader {
+    /// Stream offset relative to the metadata root (`Offset`).
+    pub offset: Field<u32>,
+    /// Stream size in bytes (`Size`).
+    pub size: Field<u32>,
+    /// Stream name, e.g. `#~` or `#Strings`.
+    pub name: String,
+    /// Absolute file offset of the stream data.
+    pub data_offset: usize,
+}
+
+/// Metadata root (`STORAGESIGNATURE` + `STORAGEHEADER`) and its stream headers.
+pub struct MetadataRoot {
+    /// Absolute file offset of the root.
+    pub offset: usize,
+    /// `BSJB` signature.
+    pub signature: Field<u32>,
+    /// Major version.
+    pub major_version: Field<u16>,
+    /// Minor version.
+    pub minor_version: Field<u16>,
+    /// Reserved.
+    pub reserved: Field<u32>,
+    /// Length of the padded version string.
+    pub length: Field<u32>,
+    /// Runtime version string, e.g. `v4.0.30319` (padding trimmed).
+    pub version: Field<String>,
+    /// Flags.
+    pub flags: Field<u16>,
+    /// Number of stream headers.
+    pub streams: Field<u16>,
+    /// Stream headers in file order.
+    pub stream_headers: Vec<StreamHeader>,
+}
+
+/// `#Strings` index with the resolved UTF-8 value.
+#[derive(Debug, Clone)]
+pub struct HeapString {
+    /// Heap index column (2 or 4 bytes on disk).
+    pub index: Field<u32>,
+    /// Resolved string.
+    pub value: String,
+}
+
+/// Coded index column split into table and row.
+#[derive(Debug, Clone)]
+pub struct CodedIndex {
+    /// Raw column value (2 or 4 bytes on disk).
+    pub raw: Field<u32>,
+    /// Referenced table, `None` for unused tags.
+    pub table: Option<u8>,
+    /// One-based row in [`CodedIndex::table`], `0` for a null reference.
+    pub row: u32,
+}
+
+/// `Module` row.
+pub struct ModuleRow {
+    /// `Generation`.
+    pub generation: Field<u16>,
+    /// `Name`.
+    pub name: HeapString,
+    /// `Mvid` (`#GUID` index).
+    pub mvid: Field<u32>,
+    /// `EncId` (`#GUID` index).
+    pub enc_id: Field<u32>,
+    /// `EncBaseId` (`#GUID` index).
+    pub enc_base_id: Field<u32>,
+}
+
+/// `TypeRef` row.
+pub struct TypeRefRow {
+    /// `ResolutionScope`.
+    pub resolution_scope: CodedIndex,
+    /// `TypeName`.
+    pub type_name: HeapString,
+    /// `TypeNamespace`.
+    pub type_namespace: HeapString,
+}
+
+/// `TypeDef` row.
+pub struct TypeDefRow {
+    /// `Flags` (`TypeAttributes`).
+    pub flags: Field<u32>,
+    /// `TypeName`.
+    pub type_name: HeapString,
+    /// `TypeNamespace`.
+    pub type_namespace: HeapString,
+    /// `Extends` (`TypeDefOrRef`).
+    pub extends: CodedIndex,
+    /// `FieldList` (first `Field` row).
+    pub field_list: Field<u32>,
+    /// `MethodList` (first `MethodDef` row).
+    pub method_list: Field<u32>,
+}
+
+/// `MethodDef` row.
+pub struct MethodDefRow {
+    /// `RVA` of the method body, `0` for abstract / extern methods.
+    pub rva: Field<u32>,
+    /// `ImplFlags` (`MethodImplAttributes`).
+    pub impl_flags: Field<u16>,
+    /// `Flags` (`MethodAttributes`).
+    pub flags: Field<u16>,
+    /// `Name`.
+    pub name: HeapString,
+    /// `Signature` (`#Blob` index).
+    pub signature: Field<u32>,
+    /// `ParamList` (first `Param` row).
+    pub param_list: Field<u32>,
+}
+
+/// `MemberRef` row.
+pub struct MemberRefRow {
+    /// `Class` (`MemberRefParent`).
+    pub class: CodedIndex,
+    /// `Name`.
+    pub name: HeapString,
+    /// `Signature` (`#Blob` index).
+    pub signature: Field<u32>,
+}
+
+/// `Assembly` row.
+pub struct AssemblyRow {
+    /// `HashAlgId`.
+    pub hash_alg_id: Field<u32>,
+    /// `MajorVersion`.
+    pub major_version: Field<u16>,
+    /// `MinorVersion`.
+    pub minor_version: Field<u16>,
+    /// `BuildNumber`.
+    pub build_number: Field<u16>,
+    /// `RevisionNumber`.
+    pub revision_number: Field<u16>,
+    /// `Flags` (`AssemblyFlags`).
+    pub flags: Field<u32>,
+    /// `PublicKey` (`#Blob` index).
+    pub public_key: Field<u32>,
+    /// `Name`.
+    pub name: HeapString,
+    /// `Culture`.
+    pub culture: HeapString,
+}
+
+/// `AssemblyRef` row.
+pub struct AssemblyRefRow {
+    /// `MajorVersion`.
+    pub major_version: Field<u16>,
+    /// `MinorVersion`.
+    pub minor_version: Field<u16>,
+    /// `BuildNumber`.
+    pub build_number: Field<u16>,
+    /// `RevisionNumber`.
+    pub revision_number: Field<u16>,
+    /// `Flags` (`AssemblyFlags`).
+    pub flags: Field<u32>,
+    /// `PublicKeyOrToken` (`#Blob` index).
+    pub public_key_or_token: Field<u32>,
+    /// `Name`.
+    pub name: HeapString,
+    /// `Culture`.
+    pub culture: HeapString,
+    /// `HashValue` (`#Blob` index).
+    pub hash_value: Field<u32>,
+}
+
+/// `ManifestResource` row.
+pub struct ManifestResourceRow {
+    /// `Offset` into the CLR resources directory (embedded resources only).
+    pub offset: Field<u32>,
+    /// `Flags` (`ManifestResourceAttributes`).
+    pub flags: Field<u32>,
+    /// `Name`.
+    pub name: HeapString,
+    /// `Implementation`; a null reference means the resource is embedded in this image.
+    pub implementation: CodedIndex,
+}
+
+/// Location and size of one present table.
+pub struct TableInfo {
+    /// Table number (`TABLE_*`).
+    pub id: u8,
+    /// Row count.
+    pub rows: Field<u32>,
+    /// Absolute file offset of the first row.
+    pub offset: usize,
+    /// Bytes per row.
+    pub row_size: usize,
+}
+
+/// `#~` / `#-` table stream.
+pub struct TablesStream {
+    /// Absolute file offset of the stream header.
+    pub offset: usize,
+    /// Major version.
+    pub major_version: Field<u8>,
+    /// Minor version.
+    pub minor_version: Field<u8>,
+    /// Heap index widths (`0x01` strings, `0x02` GUID, `0x04` blob are 4 bytes).
+    pub heap_sizes: Field<u8>,
+    /// Bit vector of present tables.
+    pub valid: Field<u64>,
+    /// Bit vector of sorted tables.
+    pub sorted: Field<u64>,
+    /// Present tables in table-number order.
+    pub tables: Vec<TableInfo>,
+    /// `Module` rows.
+    pub modules: Vec<ModuleRow>,
+    /// `TypeRef` rows.
+    pub type_refs: Vec<TypeRefRow>,
+    /// `TypeDef` rows.
+    pub type_defs: Vec<TypeDefRow>,
+    /// `MethodDef` rows.
+    pub method_defs: Vec<MethodDefRow>,
+    /// `MemberRef` rows.
+    pub member_refs: Vec<MemberRefRow>,
+    /// `Assembly` rows.
+    pub assemblies: Vec<AssemblyRow>,
+    /// `AssemblyRef` rows.
+    pub assembly_refs: Vec<AssemblyRefRow>,
+    /// `ManifestResource` rows.
+    pub manifest_resources: Vec<ManifestResourceRow>,
+}
+
+/// Parsed metadata: root, heaps and table stream.
+pub struct Metadata {
+    /// Metadata root and stream headers.
+    pub root: MetadataRoot,
+    /// Table stream when a `#~` or `#-` stream is present.
+    pub tables: Option<TablesStream>,
+}
+
+#[derive(Clone, Copy)]
+enum Column {
+    U16,
+    U32,
+    Str,
+    Guid,
+    Blob,
+    Table(u8),
+    Coded(CodedIndexKind),
+}
+
+fn schema(table: u8) -> Option<&'static [Column]> {
+    use Column::*;
+    let columns: &'static [Column] = match table {
+        TABLE_MODULE => &[U16, Str, Guid, Guid, Guid],
+        TABLE_TYPE_REF => &[Coded(RESOLUTION_SCOPE), Str, Str],
+        TABLE_TYPE_DEF => &[
+            U32,
+            Str,
+            Str,
+            Coded(TYPE_DEF_OR_REF),
+            Table(TABLE_FIELD),
+            Table(TABLE_METHOD_DEF),
+        ],
+        TABLE_FIELD_PTR => &[Table(TABLE_FIELD)],
+        TABLE_FIELD => &[U16, Str, Blob],
+        TABLE_METHOD_PTR => &[Table(TABLE_METHOD_DEF)],
+        TABLE_METHOD_DEF => &[U32, U16, U16, Str, Blob, Table(TABLE_PARAM)],
+        TABLE_PARAM_PTR => &[Table(TABLE_PARAM)],
+        TABLE_PARAM => &[U16, U16, Str],
+        TABLE_INTERFACE_IMPL => &[Table(TABLE_TYPE_DEF), Coded(TYPE_DEF_OR_REF)],
+        TABLE_MEMBER_REF => &[Coded(MEMBER_REF_PARENT), Str, Blob],
+        TABLE_CONSTANT => &[U16, Coded(HAS_CONSTANT), Blob],
+        TABLE_CUSTOM_ATTRIBUTE => &[
+            Coded(HAS_CUSTOM_ATTRIBUTE),
+            Coded(CUSTOM_ATTRIBUTE_TYPE),
+            Blob,
+        ],
+        TABLE_FIELD_MARSHAL => &[Coded(HAS_FIELD_MARSHAL), Blob],
+        TABLE_DECL_SECURITY => &[U16, Coded(HAS_DECL_SECURITY), Blob],
+        TABLE_CLASS_LAYOUT => &[U16, U32, Table(TABLE_TYPE_DEF)],
+        TABLE_FIELD_LAYOUT => &[U32, Table(TABLE_FIELD)],
+        TABLE_STAND_ALONE_SIG => &[Blob],
+        TABLE_EVENT_MAP => &[Table(TABLE_TYPE_DEF), Table(TABLE_EVENT)],
+        TABLE_EVENT_PTR => &[Table(TABLE_EVENT)],
+        TABLE_EVENT => &[U16, Str, Coded(TYPE_DEF_OR_REF)],
+        TABLE_PROPERTY_MAP => &[Table(TABLE_TYPE_DEF), Table(TABLE_PROPERTY)],
+        TABLE_PROPERTY_PTR => &[Table(TABLE_PROPERTY)],
+        TABLE_PROPERTY => &[U16, Str, Blob],
+        TABLE_METHOD_SEMANTICS => &[U16, Table(TABLE_METHOD_DEF), Coded(HAS_SEMANTICS)],
+        TABLE_METHOD_IMPL => &[
+            Table(TABLE_TYPE_DEF),
+            Coded(METHOD_DEF_OR_REF),
+            Coded(METHOD_DEF_OR_REF),
+        ],
+        TABLE_MODULE_REF => &[Str],
+        TABLE_TYPE_SPEC => &[Blob],
+        TABLE_IMPL_MAP => &[U16, Coded(MEMBER_FORWARDED), Str, Table(TABLE_MODULE_REF)],
+        TABLE_FIELD_RVA => &[U32, Table(TABLE_FIELD)],
+        TABLE_ENC_LOG => &[U32, U32],
+        TABLE_ENC_MAP => &[U32],
+        TABLE_ASSEMBLY => &[U32, U16, U16, U16, U16, U32, Blob, Str, Str],
+        TABLE_ASSEMBLY_PROCESSOR => &[U32],
+        TABLE_ASSEMBLY_OS => &[U32, U32, U32],
+        TABLE_ASSEMBLY_REF => &[U16, U16, U16, U16, U32, Blob, Str, Str, Blob],
+        TABLE_ASSEMBLY_REF_PROCESSOR => &[U32, Table(TABLE_ASSEMBLY_REF)],
+        TABLE_ASSEMBLY_REF_OS => &[U32, U32, U32, Table(TABLE_ASSEMBLY_REF)],
+        TABLE_FILE => &[U32, Str, Blob],
+        TABLE_EXPORTED_TYPE => &[U32, U32, Str, Str, Coded(IMPLEMENTATION)],
+        TABLE_MANIFEST_RESOURCE => &[U32, U32, Str, Coded(IMPLEMENTATION)],
+        TABLE_NESTED_CLASS => &[Table(TABLE_TYPE_DEF), Table(TABLE_TYPE_DEF)],
+        TABLE_GENERIC_PARAM => &[U16, U16, Coded(TYPE_OR_METHOD_DEF), Str],
+        TABLE_METHOD_SPEC => &[Coded(METHOD_DEF_OR_REF), Blob],
+        TABLE_GENERIC_PARAM_CONSTRAINT => &[Table(TABLE_GENERIC_PARAM), Coded(TYPE_DEF_OR_REF)],
+        _ => return None,
+    };
+    Some(columns)
+}
+
+/// Index widths derived from `HeapSizes` and the row counts.
+struct IndexSizes {
+    string: usize,
+    guid: usize,
+    blob: usize,
+    rows: [u32; 64],
+}
+
+impl IndexSizes {
+    fn table(&self, table: u8) -> usize {
+        if self.rows[table as usize] < 0x1_0000 {
+            2
+        } else {
+            4
+        }
+    }
+
+    fn coded(&self, kind: CodedIndexKind) -> usize {
+        let max_rows = kind
+            .tables
+            .iter()
+            .flatten()
+            .map(|table| self.rows[*table as usize])
+            .max()
+            .unwrap_or(0);
+        if max_rows < 1 << (16 - kind.tag_bits) {
+            2
+        } else {
+            4
+        }
+    }
+
+    fn column(&self, column: Column) -> usize {
+        match column {
+            Column::U16 => 2,
+            Column::U32 => 4,
+            Column::Str => self.string,
+            Column::Guid => self.guid,
+            Column::Blob => self.blob,
+            Column::Table(table) => self.table(table),
+            Column::Coded(kind) => self.coded(kind),
+        }
+    }
+
+    fn row_size(&self, table: u8) -> Option<usize> {
+        schema(table).map(|columns| columns.iter().map(|c| self.column(*c)).sum())
+    }
+}
+
+/// Sequential column reader over one row.
+struct RowReader<'a> {
+    buffer: &'a [u8],
+    cursor: usize,
+    sizes: &'a IndexSizes,
+    strings: Option<&'a StreamHeader>,
+}
+
+impl RowReader<'_> {
+    fn u16(&mut self) -> Result<Field<u16>, FileParseError> {
+        let offset = self.cursor;
+        self.cursor += 2;
+        Ok(Field::new(extract_u16(self.buffer, offset)?, offset, 2))
+    }
+
+    fn u32(&mut self) -> Result<Field<u32>, FileParseError> {
+        let offset = self.cursor;
+        self.cursor += 4;
+        Ok(Field::new(extract_u32(self.buffer, offset)?, offset, 4))
+    }
+
+    fn index(&mut self, width: usize) -> Result<Field<u32>, FileParseError> {
+        let offset = self.cursor;
+        self.cursor += width;
+        let value = match width {
+            2 => extract_u16(self.buffer, offset)? as u32,
+            _ => extract_u32(self.buffer, offset)?,
+        };
+        Ok(Field::new(value, offset, width))
+    }
+
+    fn string(&mut self) -> Result<HeapString, FileParseError> {
+        let index = self.index(self.sizes.string)?;
+        let value = heap_string(self.buffer, self.strings, index.value)?;
+        Ok(HeapString { index, value })
+    }
+
+    fn guid(&mut self) -> Result<Field<u32>, FileParseError> {
+        self.index(self.sizes.guid)
+    }
+
+    fn blob(&mut self) -> Result<Field<u32>, FileParseError> {
+        self.index(self.sizes.blob)
+    }
+
+    fn table(&mut self, table: u8) -> Result<Field<u32>, FileParseError> {
+        self.index(self.sizes.table(table))
+    }
+
+    fn coded(&mut self, kind: CodedIndexKind) -> Result<CodedIndex, FileParseError> {
+        let raw = self.index(self.sizes.coded(kind))?;
+        let tag = raw.value & ((1 << kind.tag_bits) - 1);
+        Ok(CodedIndex {
+            table: kind.tables.get(tag as usize).copied().flatten(),
+            row: raw.value >> kind.tag_bits,
+            raw,
+        })
+    }
+}
+
+impl MetadataRoot {
+    /// Parses the metadata root at `offset`; stream headers must fit in `size` bytes.
+    pub fn parse(buffer: &[u8], offset: usize, size: usize) -> Result<Self, FileParseError> {
+        let end = offset
+            .checked_add(size)
+            .ok_or(FileParseError::BufferOverflow)?;
+        if buffer.len() < end {
+            return Err(FileParseError::BufferOverflow);
+        }
+        let signature = extract_u32(buffer, offset)?;
+        if signature != METADATA_SIGNATURE {
+            return Err(FileParseError::InvalidFileFormat);
+        }
+        let length = extract_u32(buffer, offset + 12)? as usize;
+        let version_offset = offset + 16;
+        let version_bytes = buffer
+            .get(version_offset..version_offset + length)
+            .ok_or(FileParseError::BufferOverflow)?;
+        let version_len = version_bytes
+            .iter()
+            .position(|byte| *byte == 0)
+            .unwrap_or(length);
+        let version = String::from_utf8_lossy(&version_bytes[..version_len]).into_owned();
+
+        let flags_offset = version_offset + length;
+        let streams = extract_u16(buffer, flags_offset + 2)?;
+        let mut stream_headers = Vec::with_capacity(streams as usize);
+        let mut cursor = flags_offset + 4;
+        for _ in 0..streams {
+            let stream_offset = extract_u32(buffer, cursor)?;
+            let stream_size = extract_u32(buffer, cursor + 4)?;
+            let name_bytes = buffer
+                .get(cursor + 8..end.min(cursor + 8 + 32))
+                .ok_or(FileParseError::BufferOverflow)?;
+            let name_len = name_bytes
+                .iter()
+                .position(|byte| *byte == 0)
+                .ok_or(FileParseError::InvalidFileFormat)?;
+            let stream_end = (stream_offset as usize)
+                .checked_add(stream_size as usize)
+                .ok_or(FileParseError::BufferOverflow)?;
+            if stream_end > size {
+                return Err(FileParseError::BufferOverflow);
+            }
+            stream_headers.push(StreamHeader {
+                offset: Field::new(stream_offset, cursor, 4),
+                size: Field::new(stream_size, cursor + 4, 4),
+                name: String::from_utf8_lossy(&name_bytes[..name_len]).into_owned(),
+                data_offset: offset + stream_offset as usize,
+            });
+            cursor += 8 + (name_len + 1).next_multiple_of(4);
+        }
+
+        Ok(MetadataRoot {
+            offset,
+            signature: Field::new(signature, offset, 4),
+            major_version: Field::new(extract_u16(buffer, offset + 4)?, offset + 4, 2),
+            minor_version: Field::new(extract_u16(buffer, offset + 6)?, offset + 6, 2),
+            reserved: Field::new(extract_u32(buffer, offset + 8)?, offset + 8, 4),
+            length: Field::new(length as u32, offset + 12, 4),
+            version: Field::new(version, version_offset, length),
+            flags: Field::new(extract_u16(buffer, flags_offset)?, flags_offset, 2),
+            streams: Field::new(streams, flags_offset + 2, 2),
+            stream_headers,
+        })
+    }
+
+    /// Returns the stream header named `name`.
+    pub fn stream(&self, name: &str) -> Option<&StreamHeader> {
+        self.stream_headers
+            .iter()
+            .find(|stream| stream.name == name)
+    }
+}
+
+impl TablesStream {
+    /// Parses the table stream described by `stream`, resolving names through `strings`.
+    pub fn parse(
+        buffer: &[u8],
+        stream: &StreamHeader,
+        strings: Option<&StreamHeader>,
+    ) -> Result<Self, FileParseError> {
+        let offset = stream.data_offset;
+        let end = offset + stream.size.value as usize;
+        let heap_sizes = buffer
+            .get(offset + 6)
+            .copied()
+            .ok_or(FileParseError::BufferOverflow)?;
+        let valid = extract_u64(buffer, offset + 8)?;
+        let sorted = extract_u64(buffer, offset + 16)?;
+
+        let mut sizes = IndexSizes {
+            string: if heap_sizes & 0x01 != 0 { 4 } else { 2 },
+            guid: if heap_sizes & 0x02 != 0 { 4 } else { 2 },
+            blob: if heap_sizes & 0x04 != 0 { 4 } else { 2 },
+            rows: [0; 64],
+        };
+        let mut counts = Vec::new();
+        let mut cursor = offset + 24;
+        for table in 0..64u8 {
+            if valid & (1 << table) != 0 {
+                let rows = extract_u32(buffer, cursor)?;
+                sizes.rows[table as usize] = rows;
+                counts.push((table, Field::new(rows, cursor, 4)));
+                cursor += 4;
+            }
+        }
+        // `HeapSizes` bit 0x40 (extra data) adds four bytes after the row counts.
+        if heap_sizes & 0x40 != 0 {
+            cursor += 4;
+        }
+
+        let mut tables = Vec::with_capacity(counts.len());
+        for (id, rows) in counts {
+            let row_size = sizes.row_size(id).ok_or_else(|| {
+                FileParseError::UnsupportedFeature(format!("metadata table {id:#04x}"))
+            })?;
+            let length = (rows.value as usize)
+                .checked_mul(row_size)
+                .ok_or(FileParseError::BufferOverflow)?;
+            if cursor + length > end {
+                return Err(FileParseError::BufferOverflow);
+            }
+            tables.push(TableInfo {
+                id,
+                rows,
+                offset: cursor,
+                row_size,
+            });
+            cursor += length;
+        }
+
+        let mut stream = TablesStream {
+            offset,
+            major_version: Field::new(buffer[offset + 4], offset + 4, 1),
+            minor_version: Field::new(buffer[offset + 5], offset + 5, 1),
+            heap_sizes: Field::new(heap_sizes, offset + 6, 1),
+            valid: Field::new(valid, offset + 8, 8),
+            sorted: Field::new(sorted, offset + 16, 8),
+            tables,
+            modules: Vec::new(),
+            type_refs: Vec::new(),
+            type_defs: Vec::new(),
+            method_defs: Vec::new(),
+            member_refs: Vec::new(),
+            assemblies: Vec::new(),
+            assembly_refs: Vec::new(),
+            manifest_resources: Vec::new(),
+        };
+
+        let rows = |table: u8| {
+            stream
+                .table(table)
+                .map(|info| (info.offset, info.row_size, info.rows.value))
+        };
+        let reader = |offset: usize| RowReader {
+            buffer,
+            cursor: offset,
+            sizes: &sizes,
+            strings,
+        };
+        let modules = read_rows(rows(TABLE_MODULE), |offset| {
+            let mut r = reader(offset);
+            Ok(ModuleRow {
+                generation: r.u16()?,
+                name: r.string()?,
+                mvid: r.guid()?,
+                enc_id: r.guid()?,
+                enc_base_id: r.guid()?,
+            })
+        })?;
+        let type_refs = read_rows(rows(TABLE_TYPE_REF), |offset| {
+            let mut r = reader(offset);
+            Ok(TypeRefRow {
+                resolution_scope: r.coded(RESOLUTION_SCOPE)?,
+                type_name: r.string()?,
+                type_namespace: r.string()?,
+            })
+        })?;
+        let type_defs = read_rows(rows(TABLE_TYPE_DEF), |offset| {
+            let mut r = reader(offset);
+            Ok(TypeDefRow {
+                flags: r.u32()?,
+                type_name: r.string()?,
+                type_namespace: r.string()?,
+                extends: r.coded(TYPE_DEF_OR_REF)?,
+                field_list: r.table(TABLE_FIELD)?,
+                method_list: r.table(TABLE_METHOD_DEF)?,
+            })
+        })?;
+        let method_defs = read_rows(rows(TABLE_METHOD_DEF), |offset| {
+            let mut r = reader(offset);
+            Ok(MethodDefRow {
+                rva: r.u32()?,
+                impl_flags: r.u16()?,
+                flags: r.u16()?,
+                name: r.string()?,
+                signature: r.blob()?,
+                param_list: r.table(TABLE_PARAM)?,
+            })
+        })?;
+        let member_refs = read_rows(rows(TABLE_MEMBER_REF), |offset| {
+            let mut r = reader(offset);
+            Ok(MemberRefRow {
+                class: r.coded(MEMBER_REF_PARENT)?,
+                name: r.string()?,
+                signature: r.blob()?,
+            })
+        })?;
+        let assemblies = read_rows(rows(TABLE_ASSEMBLY), |offset| {
+            let mut r = reader(offset);
+            Ok(AssemblyRow {
+                hash_alg_id: r.u32()?,
+                major_version: r.u16()?,
+                minor_version: r.u16()?,
+                build_number: r.u16()?,
+                revision_number: r.u16()?,
+                flags: r.u32()?,
+                public_key: r.blob()?,
+                name: r.string()?,
+                culture: r.string()?,
+            })
+        })?;
+        let assembly_refs = read_rows(rows(TABLE_ASSEMBLY_REF), |offset| {
+            let mut r = reader(offset);
+            Ok(AssemblyRefRow {
+                major_version: r.u16()?,
+                minor_version: r.u16()?,
+                build_number: r.u16()?,
+                revision_number: r.u16()?,
+                flags: r.u32()?,
+                public_key_or_token: r.blob()?,
+                name: r.string()?,
+                culture: r.string()?,
+                hash_value: r.blob()?,
+            })
+        })?;
+        let manifest_resources = read_rows(rows(TABLE_MANIFEST_RESOURCE), |offset| {
+            let mut r = reader(offset);
+            Ok(ManifestResourceRow {
+                offset: r.u32()?,
+                flags: r.u32()?,
+                name: r.string()?,
+                implementation: r.coded(IMPLEMENTATION)?,
+            })
+        })?;
+
+        stream.modules = modules;
+        stream.type_refs = type_refs;
+        stream.type_defs = type_defs;
+        stream.method_defs = method_defs;
+        stream.member_refs = member_refs;
+        stream.assemblies = assemblies;
+        stream.assembly_refs = assembly_refs;
+        stream.manifest_resources = manifest_resources;
+        Ok(stream)
+    }
+
+    /// Returns the location of `table` when present.
+    pub fn table(&self, table: u8) -> Option<&TableInfo> {
+        self.tables.iter().find(|info| info.id == table)
+    }
+
+    /// Row count of `table`, `0` when absent.
+    pub fn row_count(&self, table: u8) -> u32 {
+        self.table(table).map_or(0, |info| info.rows.value)
+    }
+
+    /// Absolute file offset of one-based row `row` in `table`.
+    pub fn row_offset(&self, table: u8, row: u32) -> Option<usize> {
+        let info = self.table(table)?;
+        if row == 0 || row > info.rows.value {
+            return None;
+        }
+        Some(info.offset + (row as usize - 1) * info.row_size)
+    }
+}
+
+fn read_rows<T>(
+    table: Option<(usize, usize, u32)>,
+    mut read: impl FnMut(usize) -> Result<T, FileParseError>,
+) -> Result<Vec<T>, FileParseError> {
+    let Some((offset, row_size, rows)) = table else {
+        return Ok(Vec::new());
+    };
+    (0..rows as usize)
+        .map(|row| read(offset + row * row_size))
+        .collect()
+}
+
+fn heap_string(
+    buffer: &[u8],
+    heap: Option<&StreamHeader>,
+    index: u32,
+) -> Result<String, FileParseError> {
+    let heap = heap.ok_or(FileParseError::InvalidFileFormat)?;
+    let data = heap_data(buffer, heap)?;
+    let bytes = data
+        .get(index as usize..)
+        .ok_or(FileParseError::BufferOverflow)?;
+    let len = bytes
+        .iter()
+        .position(|byte| *byte == 0)
+        .ok_or(FileParseError::InvalidFileFormat)?;
+    Ok(String::from_utf8_lossy(&bytes[..len]).into_owned())
+}
+
+fn heap_data<'a>(buffer: &'a [u8], heap: &StreamHeader) -> Result<&'a [u8], FileParseError> {
+    buffer
+        .get(heap.data_offset..heap.data_offset + heap.size.value as usize)
+        .ok_or(FileParseError::BufferOverflow)
+}
+
+/// Decodes an ECMA-335 compressed unsigned integer, returning the value and its encoded length.
+pub fn read_compressed_u32(data: &[u8]) -> Result<(u32, usize), FileParseError> {
+    let first = *data.first().ok_or(FileParseError::BufferOverflow)?;
+    let (length, value) = match first {
+        0x00..=0x7F => (1, first as u32),
+        0x80..=0xBF => (2, (first & 0x3F) as u32),
+        0xC0..=0xDF => (4, (first & 0x1F) as u32),
+        _ => return Err(FileParseError::InvalidFileFormat),
+    };
+    let rest = data.get(1..length).ok_or(FileParseError::BufferOverflow)?;
+    let value = rest
+        .iter()
+        .fold(value, |acc, byte| (acc << 8) | *byte as u32);
+    Ok((value, length))
+}
+
+impl Metadata {
+    /// Parses the metadata root at `offset` (`size` from the COR20 `MetaData` directory) and the
+    /// table stream.
+    pub fn parse(buffer: &[u8], offset: usize, size: usize) -> Result<Self, FileParseError> {
+        let root = MetadataRoot::parse(buffer, offset, size)?;
+        let tables = match root.stream("#~").or_else(|| root.stream("#-")) {
+            Some(stream) => Some(TablesStream::parse(
+                buffer,
+                stream,
+                root.stream("#Strings"),
+            )?),
+            None => None,
+        };
+        Ok(Metadata { root, tables })
+    }
+
+    /// Reads a `#Strings` entry.
+    pub fn string(&self, buffer: &[u8], index: u32) -> Result<String, FileParseError> {
+        heap_string(buffer, self.root.stream("#Strings"), index)
+    }
+
+    /// Reads a `#US` (user string) entry, dropping the trailing flag byte.
+    pub fn user_string(&self, buffer: &[u8], index: u32) -> Result<String, FileParseError> {
+        let bytes = self.heap_blob(buffer, "#US", index)?;
+        let units: Vec<u16> = bytes
+            .as_chunks::<2>()
+            .0
+            .iter()
+            .map(|chunk| u16::from_le_bytes(*chunk))
+            .collect();
+        Ok(String::from_utf16_lossy(&units))
+    }
+
+    /// Reads a `#Blob` entry.
+    pub fn blob<'a>(&self, buffer: &'a [u8], index: u32) -> Result<&'a [u8], FileParseError> {
+        self.heap_blob(buffer, "#Blob", index)
+    }
+
+    /// Reads a `#GUID` entry; index `0` is the null GUID and yields `None`.
+    pub fn guid(&self, buffer: &[u8], index: u32) -> Result<Option<[u8; 16]>, FileParseError> {
+        if index == 0 {
+            return Ok(None);
+        }
+        let heap = self
+            .root
+            .stream("#GUID")
+            .ok_or(FileParseError::InvalidFileFormat)?;
+        let start = (index as usize - 1) * 16;
+        let bytes = heap_data(buffer, heap)?
+            .get(start..start + 16)
+            .ok_or(FileParseError::BufferOverflow)?;
+        let mut guid = [0u8; 16];
+        guid.copy_from_slice(bytes);
+        Ok(Some(guid))
+    }
+
+    fn heap_blob<'a>(
+        &self,
+        buffer: &'a [u8],
+        name: &str,
+        index: u32,
+    ) -> Result<&'a [u8], FileParseError> {
+        let heap = self
+            .root
+            .stream(name)
+            .ok_or(FileParseError::InvalidFileFormat)?;
+        let data = heap_data(buffer, heap)?
+            .get(index as usize..)
+            .ok_or(FileParseError::BufferOverflow)?;
+        let (length, header) = read_compressed_u32(data)?;
+        data.get(header..header + length as usize)
+            .ok_or(FileParseError::BufferOverflow)
+    }
+}
+
+impl TypeRefRow {
+    /// `Namespace.Name`, or just the name for types without a namespace.
+    pub fn full_name(&self) -> String {
+        full_name(&self.type_namespace.value, &self.type_name.value)
+    }
+}
+
+impl TypeDefRow {
+    /// `Namespace.Name`, or just the name for types without a namespace.
+    pub fn full_name(&self) -> String {
+        full_name(&self.type_namespace.value, &self.type_name.value)
+    }
+}
+
+impl AssemblyRow {
+    /// `(major, minor, build, revision)`.
+    pub fn version(&self) -> (u16, u16, u16, u16) {
+        (
+            self.major_version.value,
+            self.minor_version.value,
+            self.build_number.value,
+            self.revision_number.value,
+        )
+    }
+}
+
+impl AssemblyRefRow {
+    /// `(major, minor, build, revision)`.
+    pub fn version(&self) -> (u16, u16, u16, u16) {
+        (
+            self.major_version.value,
+            self.minor_version.value,
+            self.build_number.value,
+            self.revision_number.value,
+        )
+    }
+}
+
+fn full_name(namespace: &str, name: &str) -> String {
+    if namespace.is_empty() {
+        name.to_string()
+    } else {
+        format!("{namespace}.{name}")
+    }
+}
+
+impl PE {
+    /// Parses the ECMA-335 metadata referenced by the CLR header, when the image is managed.
+    pub fn clr_metadata(&self) -> Result<Option<Metadata>, FileParseError> {
+        let Some(cor20) = self.clr()? else {
+            return Ok(None);
+        };
+        if cor20.metadata.virtual_address.value == 0 || cor20.metadata.size.value == 0 {
+            return Ok(None);
+        }
+        let offset = self.rva_to_offset(cor20.metadata.virtual_address.value)?;
+        Ok(Some(Metadata::parse(
+            &self.buffer,
+            offset,
+            cor20.metadata.size.value as usize,
+        )?))
+    }
+}
@@ -5,6 +5,9 @@ use crate::errors::FileParseError;
 use crate::field::Field;
 use crate::utils::{extract_u16, extract_u32};
 
+/// `IMAGE_FILE_RELOCS_STRIPPED`: the image has no base relocations and must load at its base.
+pub const IMAGE_FILE_RELOCS_STRIPPED: u16 = 0x0001;
+
 /// COFF file header (`IMAGE_FILE_HEADER`) — 20 bytes after the `PE\0\0` signature.
 #[derive(Debug)]
 pub struct CoffFileHeader {
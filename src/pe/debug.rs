@@ -1,12 +1,21 @@
-//! Debug directory entries (`IMAGE_DEBUG_DIRECTORY`).
+//! Debug directory entries (`IMAGE_DEBUG_DIRECTORY`) and CodeView records.
+//!
+//! `IMAGE_DEBUG_TYPE_CODEVIEW` entries point at a `RSDS` (PDB 7.0) or legacy `NB10` (PDB 2.0)
+//! record. [`CodeViewRecord`] exposes the PDB signature, age, and path as [`Field`]s so they can
+//! be patched in place.
 
 use crate::errors::FileParseError;
-use crate::field::Field;
+use crate::field::{Field, FixedBytes};
 use crate::utils::{extract_u16, extract_u32};
 
 /// `IMAGE_DEBUG_TYPE_CODEVIEW`.
 pub const IMAGE_DEBUG_TYPE_CODEVIEW: u32 = 2;
 
+/// CodeView `RSDS` signature (PDB 7.0).
+pub const CODEVIEW_SIGNATURE_RSDS: u32 = 0x5344_5352;
+/// CodeView `NB10` signature (PDB 2.0).
+pub const CODEVIEW_SIGNATURE_NB10: u32 = 0x3031_424E;
+
 /// `IMAGE_DEBUG_DIRECTORY` — 28 bytes.
 pub struct DebugDirectoryEntry {
     /// Characteristics (reserved, must be zero).
@@ -27,6 +36,40 @@ pub struct DebugDirectoryEntry {
     pub address_of_raw_data: Field<u32>,
 }
 
+/// `CV_INFO_PDB70` — `RSDS` signature, GUID, age, and PDB path.
+pub struct CodeViewRsds {
+    /// `CvSignature` (`RSDS`).
+    pub signature: Field<u32>,
+    /// PDB GUID as stored on disk (`Data1`..`Data3` little-endian).
+    pub guid: Field<FixedBytes<16>>,
+    /// PDB age.
+    pub age: Field<u32>,
+    /// PDB path; [`Field::size`] excludes the final NUL byte of the record.
+    pub path: Field<String>,
+}
+
+/// `CV_INFO_PDB20` — `NB10` signature, offset, timestamp, age, and PDB path.
+pub struct CodeViewNb10 {
+    /// `CvSignature` (`NB10`).
+    pub signature: Field<u32>,
+    /// `Offset` (always zero for external PDBs).
+    pub offset: Field<u32>,
+    /// PDB signature timestamp.
+    pub time_date_stamp: Field<u32>,
+    /// PDB age.
+    pub age: Field<u32>,
+    /// PDB path; [`Field::size`] excludes the final NUL byte of the record.
+    pub path: Field<String>,
+}
+
+/// Decoded CodeView record behind an `IMAGE_DEBUG_TYPE_CODEVIEW` entry.
+pub enum CodeViewRecord {
+    /// `RSDS` record (PDB 7.0).
+    Rsds(CodeViewRsds),
+    /// `NB10` record (PDB 2.0).
+    Nb10(CodeViewNb10),
+}
+
 /// Parsed debug directory.
 pub struct DebugDirectory {
     /// Absolute file offset of the first entry.
@@ -65,6 +108,133 @@ impl DebugDirectoryEntry {
             .ok_or(FileParseError::BufferOverflow)?;
         buffer.get(start..end).ok_or(FileParseError::BufferOverflow)
     }
+
+    /// Decodes the CodeView record behind this entry.
+    ///
+    /// Returns `Ok(None)` when [`Self::debug_type`] is not `IMAGE_DEBUG_TYPE_CODEVIEW`.
+    pub fn codeview(&self, buffer: &[u8]) -> Result<Option<CodeViewRecord>, FileParseError> {
+        if self.debug_type.value != IMAGE_DEBUG_TYPE_CODEVIEW {
+            return Ok(None);
+        }
+        Ok(Some(CodeViewRecord::parse(
+            buffer,
+            self.pointer_to_raw_data.value as usize,
+            self.size_of_data.value as usize,
+        )?))
+    }
+}
+
+impl CodeViewRecord {
+    /// Parses a CodeView record of `size` bytes at file `offset`.
+    ///
+    /// Unknown signatures yield [`FileParseError::UnsupportedFeature`].
+    pub fn parse(buffer: &[u8], offset: usize, size: usize) -> Result<Self, FileParseError> {
+        let end = offset
+            .checked_add(size)
+            .ok_or(FileParseError::BufferOverflow)?;
+        if buffer.len() < end || size < 4 {
+            return Err(FileParseError::BufferOverflow);
+        }
+
+        let signature = extract_u32(buffer, offset)?;
+        match signature {
+            CODEVIEW_SIGNATURE_RSDS => {
+                if size < 24 {
+                    return Err(FileParseError::BufferOverflow);
+                }
+                Ok(CodeViewRecord::Rsds(CodeViewRsds {
+                    signature: Field::new(signature, offset, 4),
+                    guid: Field::new(
+                        FixedBytes::from_slice(&buffer[offset + 4..offset + 20]),
+                        offset + 4,
+                        16,
+                    ),
+                    age: Field::new(extract_u32(buffer, offset + 20)?, offset + 20, 4),
+                    path: parse_path(buffer, offset + 24, end)?,
+                }))
+            }
+            CODEVIEW_SIGNATURE_NB10 => {
+                if size < 16 {
+                    return Err(FileParseError::BufferOverflow);
+                }
+                Ok(CodeViewRecord::Nb10(CodeViewNb10 {
+                    signature: Field::new(signature, offset, 4),
+                    offset: Field::new(extract_u32(buffer, offset + 4)?, offset + 4, 4),
+                    time_date_stamp: Field::new(extract_u32(buffer, offset + 8)?, offset + 8, 4),
+                    age: Field::new(extract_u32(buffer, offset + 12)?, offset + 12, 4),
+                    path: parse_path(buffer, offset + 16, end)?,
+                }))
+            }
+            _ => Err(FileParseError::UnsupportedFeature(format!(
+                "CodeView signature {signature:#010x}"
+            ))),
+        }
+    }
+
+    /// PDB path stored in the record.
+    pub fn path(&self) -> &str {
+        match self {
+            CodeViewRecord::Rsds(record) => &record.path.value,
+            CodeViewRecord::Nb10(record) => &record.path.value,
+        }
+    }
+
+    /// PDB age stored in the record.
+    pub fn age(&self) -> u32 {
+        match self {
+            CodeViewRecord::Rsds(record) => record.age.value,
+            CodeViewRecord::Nb10(record) => record.age.value,
+        }
+    }
+
+    /// Symbol-server key (`GUID` + age for `RSDS`, timestamp + age for `NB10`), upper-case hex.
+    ///
+    /// This is the directory name used under `<pdb name>/` on a symbol server.
+    pub fn symbol_server_key(&self) -> String {
+        match self {
+            CodeViewRecord::Rsds(record) => {
+                let (d1, d2, d3, d4) = guid_parts(&record.guid.value.0);
+                let mut key = format!("{d1:08X}{d2:04X}{d3:04X}");
+                for byte in d4 {
+                    key.push_str(&format!("{byte:02X}"));
+                }
+                key.push_str(&format!("{:X}", record.age.value));
+                key
+            }
+            CodeViewRecord::Nb10(record) => {
+                format!("{:08X}{:X}", record.time_date_stamp.value, record.age.value)
+            }
+        }
+    }
+}
+
+impl CodeViewRsds {
+    /// Registry-style GUID text (`XXXXXXXX-XXXX-XXXX-XXXX-XXXXXXXXXXXX`).
+    pub fn guid_string(&self) -> String {
+        let (d1, d2, d3, d4) = guid_parts(&self.guid.value.0);
+        format!(
+            "{d1:08X}-{d2:04X}-{d3:04X}-{:02X}{:02X}-{:02X}{:02X}{:02X}{:02X}{:02X}{:02X}",
+            d4[0], d4[1], d4[2], d4[3], d4[4], d4[5], d4[6], d4[7]
+        )
+    }
+}
+
+fn guid_parts(bytes: &[u8; 16]) -> (u32, u16, u16, [u8; 8]) {
+    let d1 = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    let d2 = u16::from_le_bytes([bytes[4], bytes[5]]);
+    let d3 = u16::from_le_bytes([bytes[6], bytes[7]]);
+    let mut d4 = [0u8; 8];
+    d4.copy_from_slice(&bytes[8..16]);
+    (d1, d2, d3, d4)
+}
+
+fn parse_path(buffer: &[u8], start: usize, end: usize) -> Result<Field<String>, FileParseError> {
+    let bytes = buffer
+        .get(start..end)
+        .ok_or(FileParseError::BufferOverflow)?;
+    let len = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    let value = String::from_utf8_lossy(&bytes[..len]).into_owned();
+    Ok(Field::new(value, start, bytes.len().saturating_sub(1)))
 }
 
 impl DebugDirectory {
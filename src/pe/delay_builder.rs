@@ -0,0 +1,523 @@
+//! Delay-load import directory rebuilding.
+//!
+//! [`DelayImportBuilder`] holds one [`DelayImportedDll`] per descriptor. Descriptors copied from an
+//! image keep every table RVA, so the delay IAT, module handle and the helper thunks that point at
+//! them stay valid. [`DelayImportBuilder::build`] writes the descriptor array, then the INT, IAT
+//! and module handle slot of each new DLL, and finally the DLL names and 2-byte aligned
+//! `IMAGE_IMPORT_BY_NAME` entries.
+//!
+//! Helper thunks pass the address of their descriptor to `__delayLoadHelper2`, so the directory
+//! is only rewritten in place when every copied descriptor keeps its slot; otherwise the previous
+//! descriptor array is left in place and a new one is written elsewhere.
+
+use crate::errors::FileParseError;
+use crate::pe::delay::{DelayLoadDescriptor, DelayLoadDirectory};
+use crate::pe::header::{self, ImageBase, PEType};
+use crate::pe::import_builder::{ImportBuilder, ImportSymbol, ImportedDll};
+use crate::pe::{section, PE};
+
+/// `dlattrRva`: descriptor fields are RVAs rather than VAs.
+pub const DLATTR_RVA: u32 = 1;
+
+/// Table RVAs of a descriptor copied from an image, written back unchanged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DelayLoadTables {
+    /// `Attributes`.
+    pub attributes: u32,
+    /// RVA of the DLL name string.
+    pub dll_name: u32,
+    /// RVA of the module handle storage.
+    pub module_handle: u32,
+    /// RVA of the delay-load IAT.
+    pub address_table: u32,
+    /// RVA of the delay-load INT.
+    pub name_table: u32,
+    /// RVA of the bound delay-load IAT.
+    pub bound_address_table: u32,
+    /// RVA of the unload delay-load IAT.
+    pub unload_address_table: u32,
+    /// `TimeDateStamp`.
+    pub time_date_stamp: u32,
+}
+
+/// Delay-loaded imports from one DLL as written to a single descriptor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DelayImportedDll {
+    /// DLL name (e.g. `USER32.dll`).
+    pub name: String,
+    /// Symbols in IAT order.
+    pub symbols: Vec<ImportSymbol>,
+    /// Existing tables to reference; `None` allocates name, INT, IAT and module handle.
+    pub tables: Option<DelayLoadTables>,
+    /// Helper thunk RVAs stored as VAs in the new IAT, one per symbol; missing slots stay zero.
+    pub thunks: Vec<u32>,
+}
+
+/// Editable delay-load import directory model.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DelayImportBuilder {
+    /// Descriptors in on-disk order.
+    pub dlls: Vec<DelayImportedDll>,
+}
+
+/// Serialized delay-load directory and where its parts landed.
+pub struct BuiltDelayImports {
+    /// Blob to place at the RVA passed to [`DelayImportBuilder::build`].
+    pub data: Vec<u8>,
+    /// Size of the descriptor array including the null terminator.
+    pub directory_size: u32,
+    /// Descriptor RVA per DLL, in [`DelayImportBuilder::dlls`] order.
+    pub descriptors: Vec<u32>,
+    /// Delay IAT RVA per DLL, in [`DelayImportBuilder::dlls`] order.
+    pub address_tables: Vec<u32>,
+    /// RVAs of the thunk VAs written to new IATs, which need base relocations.
+    pub pointers: Vec<u32>,
+}
+
+impl DelayImportedDll {
+    /// Creates a descriptor with new tables importing `symbols` by name.
+    pub fn new(name: &str, symbols: &[&str]) -> Self {
+        DelayImportedDll {
+            name: name.to_string(),
+            symbols: symbols
+                .iter()
+                .map(|name| ImportSymbol::Name {
+                    hint: 0,
+                    name: name.to_string(),
+                })
+                .collect(),
+            tables: None,
+            thunks: Vec::new(),
+        }
+    }
+}
+
+impl DelayImportBuilder {
+    /// Copies every descriptor of a parsed delay-load directory, keeping its table RVAs.
+    pub fn from_directory(delay: &DelayLoadDirectory) -> Self {
+        let dlls = delay
+            .dlls
+            .iter()
+            .map(|dll| {
+                let descriptor = &dll.descriptor;
+                DelayImportedDll {
+                    name: dll.dll_name.clone(),
+                    symbols: dll.entries.iter().map(ImportSymbol::from).collect(),
+                    tables: Some(DelayLoadTables {
+                        attributes: descriptor.attributes.value,
+                        dll_name: descriptor.dll_name_rva.value,
+                        module_handle: descriptor.module_handle_rva.value,
+                        address_table: descriptor.delay_import_address_table_rva.value,
+                        name_table: descriptor.delay_import_name_table_rva.value,
+                        bound_address_table: descriptor.bound_delay_import_table_rva.value,
+                        unload_address_table: descriptor.unload_delay_import_table_rva.value,
+                        time_date_stamp: descriptor.time_date_stamp.value,
+                    }),
+                    thunks: Vec::new(),
+                }
+            })
+            .collect();
+        DelayImportBuilder { dlls }
+    }
+
+    /// Removes the descriptor for `name` (ASCII case-insensitive); `false` when absent.
+    pub fn remove(&mut self, name: &str) -> bool {
+        let before = self.dlls.len();
+        self.dlls.retain(|dll| !dll.name.eq_ignore_ascii_case(name));
+        self.dlls.len() != before
+    }
+
+    /// Serializes the directory for placement at `rva` in an image loaded at `image_base`.
+    pub fn build(
+        &self,
+        rva: u32,
+        pe_type: PEType,
+        image_base: u64,
+    ) -> Result<BuiltDelayImports, FileParseError> {
+        let thunk_size = match pe_type {
+            PEType::PE32 => 4,
+            PEType::PE32Plus => 8,
+        };
+        let ordinal_flag = match pe_type {
+            PEType::PE32 => 1u64 << 31,
+            PEType::PE32Plus => 1u64 << 63,
+        };
+        let table_len = |dll: &DelayImportedDll| match dll.tables {
+            Some(_) => 0,
+            None => (dll.symbols.len() + 1) * thunk_size,
+        };
+
+        let directory_size = (self.dlls.len() + 1) * DelayLoadDescriptor::SIZE;
+        let int_start = directory_size.next_multiple_of(thunk_size);
+        let iat_start = int_start + self.dlls.iter().map(table_len).sum::<usize>();
+        let handle_start = iat_start + self.dlls.iter().map(table_len).sum::<usize>();
+        let new_dlls = self.dlls.iter().filter(|dll| dll.tables.is_none()).count();
+        let strings_start = handle_start + new_dlls * thunk_size;
+
+        let mut data = vec![0u8; strings_start];
+        let to_rva = |offset: usize| -> Result<u32, FileParseError> {
+            u32::try_from(offset)
+                .ok()
+                .and_then(|offset| rva.checked_add(offset))
+                .ok_or(FileParseError::ValueTooLarge)
+        };
+
+        let mut descriptors = Vec::with_capacity(self.dlls.len());
+        let mut address_tables = Vec::with_capacity(self.dlls.len());
+        let mut pointers = Vec::new();
+        let (mut int, mut iat, mut handle) = (int_start, iat_start, handle_start);
+        for (index, dll) in self.dlls.iter().enumerate() {
+            let tables = match dll.tables {
+                Some(tables) => tables,
+                None => {
+                    if dll.thunks.len() > dll.symbols.len() {
+                        return Err(FileParseError::UnsupportedFeature(format!(
+                            "more helper thunks than symbols for {}",
+                            dll.name
+                        )));
+                    }
+                    let name_offset = data.len();
+                    data.extend_from_slice(dll.name.as_bytes());
+                    data.push(0);
+
+                    for (slot, symbol) in dll.symbols.iter().enumerate() {
+                        let value = match symbol {
+                            ImportSymbol::Ordinal(ordinal) => ordinal_flag | *ordinal as u64,
+                            ImportSymbol::Name { hint, name } => {
+                                if data.len() % 2 != 0 {
+                                    data.push(0);
+                                }
+                                let by_name = to_rva(data.len())? as u64;
+                                data.extend_from_slice(&hint.to_le_bytes());
+                                data.extend_from_slice(name.as_bytes());
+                                data.push(0);
+                                by_name
+                            }
+                        };
+                        let at = int + slot * thunk_size;
+                        data[at..at + thunk_size]
+                            .copy_from_slice(&value.to_le_bytes()[..thunk_size]);
+                    }
+                    for (slot, &thunk) in dll.thunks.iter().enumerate() {
+                        let va = image_base + thunk as u64;
+                        if thunk_size == 4 && va > u32::MAX as u64 {
+                            return Err(FileParseError::ValueTooLarge);
+                        }
+                        let at = iat + slot * thunk_size;
+                        data[at..at + thunk_size].copy_from_slice(&va.to_le_bytes()[..thunk_size]);
+                        pointers.push(to_rva(at)?);
+                    }
+
+                    let tables = DelayLoadTables {
+                        attributes: DLATTR_RVA,
+                        dll_name: to_rva(name_offset)?,
+                        module_handle: to_rva(handle)?,
+                        address_table: to_rva(iat)?,
+                        name_table: to_rva(int)?,
+                        bound_address_table: 0,
+                        unload_address_table: 0,
+                        time_date_stamp: 0,
+                    };
+                    int += table_len(dll);
+                    iat += table_len(dll);
+                    handle += thunk_size;
+                    tables
+                }
+            };
+
+            let descriptor = index * DelayLoadDescriptor::SIZE;
+            let fields = [
+                tables.attributes,
+                tables.dll_name,
+                tables.module_handle,
+                tables.address_table,
+                tables.name_table,
+                tables.bound_address_table,
+                tables.unload_address_table,
+                tables.time_date_stamp,
+            ];
+            for (i, value) in fields.iter().enumerate() {
+                data[descriptor + i * 4..descriptor + i * 4 + 4]
+                    .copy_from_slice(&value.to_le_bytes());
+            }
+            descriptors.push(to_rva(descriptor)?);
+            address_tables.push(tables.address_table);
+        }
+
+        Ok(BuiltDelayImports {
+            data,
+            directory_size: directory_size as u32,
+            descriptors,
+            address_tables,
+            pointers,
+        })
+    }
+}
+
+impl PE {
+    /// Loads the delay-load directory into an editable [`DelayImportBuilder`].
+    pub fn delay_import_builder(&self) -> Result<DelayImportBuilder, FileParseError> {
+        Ok(match self.delay_imports()? {
+            Some(delay) => DelayImportBuilder::from_directory(&delay),
+            None => DelayImportBuilder::default(),
+        })
+    }
+
+    /// Writes `builder` as the delay-load directory, in place when it fits the current one and
+    /// otherwise in a new `.didat2` section.
+    ///
+    /// An empty builder clears the `DELAY_IMPORT` directory instead. Thunk pointers written to new
+    /// IATs get base relocations unless relocations were stripped.
+    pub fn set_delay_imports(
+        &mut self,
+        builder: &DelayImportBuilder,
+    ) -> Result<BuiltDelayImports, FileParseError> {
+        if !self
+            .optional_header
+            .has_data_directory(header::DELAY_IMPORT)
+        {
+            return Err(FileParseError::UnsupportedFeature(
+                "image has no delay import data directory slot".to_string(),
+            ));
+        }
+        let pe_type = self.optional_header.pe_type()?;
+        let image_base = self.optional_header.image_base.value.value();
+        if builder.dlls.is_empty() {
+            self.sync_data_directory_rva(header::DELAY_IMPORT, 0)?;
+            self.sync_data_directory_size(header::DELAY_IMPORT, 0)?;
+            self.sync_layout()?;
+            return builder.build(0, pe_type, image_base);
+        }
+
+        let built = match self.set_delay_imports_in_place(builder, pe_type, image_base)? {
+            Some(built) => built,
+            None => {
+                let characteristics = section::INITIALIZED_DATA | section::READ | section::WRITE;
+                let probe = builder.build(0, pe_type, image_base)?;
+                let header =
+                    self.build_section_header(".didat2", probe.data.len() as u32, characteristics)?;
+                let rva = header.virtual_address.value;
+                let built = builder.build(rva, pe_type, image_base)?;
+                self.append_section_raw(header, built.data.clone())?;
+                self.sync_data_directory_rva(header::DELAY_IMPORT, rva)?;
+                built
+            }
+        };
+        self.sync_data_directory_size(header::DELAY_IMPORT, built.directory_size)?;
+        self.add_pointer_relocations(&built.pointers)?;
+        self.sync_layout()?;
+        Ok(built)
+    }
+
+    /// Rewrites the delay-load directory at its current RVA; `None` when it does not fit.
+    ///
+    /// The new blob may spill past the descriptor array only into zero bytes of the same section
+    /// that no kept descriptor references, and every copied descriptor must keep its slot so the
+    /// helper thunks still pass the right descriptor address.
+    fn set_delay_imports_in_place(
+        &mut self,
+        builder: &DelayImportBuilder,
+        pe_type: PEType,
+        image_base: u64,
+    ) -> Result<Option<BuiltDelayImports>, FileParseError> {
+        let current = &self.optional_header.data_directories[header::DELAY_IMPORT];
+        let (rva, size) = (current.virtual_address.value, current.size.value as usize);
+        if rva == 0 {
+            return Ok(None);
+        }
+        let section_align = self.optional_header.section_alignment.value.max(1);
+        let Some(index) = self.sections.iter().position(|section| {
+            let va = section.virtual_address.value;
+            rva >= va && rva - va < section.size_of_raw_data.value
+        }) else {
+            return Ok(None);
+        };
+        let section = &self.sections[index];
+        let capacity = section.size_of_raw_data.value.min(
+            section
+                .virtual_size
+                .value
+                .max(1)
+                .next_multiple_of(section_align),
+        );
+        let room = capacity.saturating_sub(rva - section.virtual_address.value) as usize;
+
+        let built = builder.build(rva, pe_type, image_base)?;
+        let len = built.data.len();
+        if size.max(len) > room {
+            return Ok(None);
+        }
+        let start = self.rva_to_offset(rva)?;
+        let Some(current) = self.buffer.get(start..start + size.max(len)) else {
+            return Ok(None);
+        };
+        if len > size && current[size..].iter().any(|&byte| byte != 0) {
+            return Ok(None);
+        }
+        let spill = rva + size as u32..rva + len as u32;
+        for (slot, dll) in builder.dlls.iter().enumerate() {
+            let Some(tables) = dll.tables else {
+                continue;
+            };
+            let at = slot * DelayLoadDescriptor::SIZE..(slot + 1) * DelayLoadDescriptor::SIZE;
+            let referenced = [
+                tables.dll_name,
+                tables.module_handle,
+                tables.address_table,
+                tables.name_table,
+                tables.bound_address_table,
+                tables.unload_address_table,
+            ];
+            if at.end > size
+                || current.get(at.clone()) != built.data.get(at)
+                || referenced.iter().any(|table| spill.contains(table))
+            {
+                return Ok(None);
+            }
+        }
+
+        let end = start + size.max(len);
+        self.buffer[start..end].fill(0);
+        self.buffer[start..start + len].copy_from_slice(&built.data);
+        let section = &mut self.sections[index];
+        let used = rva - section.virtual_address.value + len as u32;
+        if section.virtual_size.value < used {
+            section.virtual_size.update(&mut self.buffer, used)?;
+        }
+        Ok(Some(built))
+    }
+
+    /// Adds a delay-load descriptor importing `symbols` by name from `dll` and returns the delay
+    /// IAT slot RVA of each symbol.
+    ///
+    /// Slots start zeroed: generate the helper thunks (which need the slot and descriptor
+    /// addresses) and store them with [`PE::set_delay_import_thunk`] before the image runs.
+    pub fn add_delay_import(
+        &mut self,
+        dll: &str,
+        symbols: &[&str],
+    ) -> Result<Vec<u32>, FileParseError> {
+        let mut builder = self.delay_import_builder()?;
+        if builder
+            .dlls
+            .iter()
+            .any(|entry| entry.name.eq_ignore_ascii_case(dll))
+        {
+            return Err(FileParseError::UnsupportedFeature(format!(
+                "{dll} is already delay-loaded"
+            )));
+        }
+        builder.dlls.push(DelayImportedDll::new(dll, symbols));
+        let built = self.set_delay_imports(&builder)?;
+        let thunk_size = match self.optional_header.pe_type()? {
+            PEType::PE32 => 4,
+            PEType::PE32Plus => 8,
+        };
+        let iat = *built.address_tables.last().expect("descriptor just pushed");
+        Ok((0..symbols.len() as u32)
+            .map(|slot| iat + slot * thunk_size)
+            .collect())
+    }
+
+    /// Stores the VA of the helper thunk at `thunk_rva` in the delay IAT slot at `slot_rva` and
+    /// adds its base relocation.
+    pub fn set_delay_import_thunk(
+        &mut self,
+        slot_rva: u32,
+        thunk_rva: u32,
+    ) -> Result<(), FileParseError> {
+        let offset = self.rva_to_offset(slot_rva)?;
+        let va = match self.optional_header.image_base.value {
+            ImageBase::Base32(base) => base
+                .checked_add(thunk_rva)
+                .ok_or(FileParseError::ValueTooLarge)?
+                .to_le_bytes()
+                .to_vec(),
+            ImageBase::Base64(base) => (base + thunk_rva as u64).to_le_bytes().to_vec(),
+        };
+        self.buffer
+            .get_mut(offset..offset + va.len())
+            .ok_or(FileParseError::BufferOverflow)?
+            .copy_from_slice(&va);
+        self.add_pointer_relocations(&[slot_rva])?;
+        self.sync_layout()
+    }
+
+    /// Removes the delay-load descriptor for `dll`; `false` when it is not delay-loaded.
+    ///
+    /// The remaining descriptors are rewritten by [`PE::set_delay_imports`]; the tables of the
+    /// removed DLL are left unreferenced.
+    pub fn remove_delay_import(&mut self, dll: &str) -> Result<bool, FileParseError> {
+        let mut builder = self.delay_import_builder()?;
+        if !builder.remove(dll) {
+            return Ok(false);
+        }
+        self.set_delay_imports(&builder)?;
+        Ok(true)
+    }
+
+    /// Turns the delay-loaded `dll` into a regular import; `false` when it is not delay-loaded.
+    ///
+    /// The new import descriptor uses the delay IAT as its `FirstThunk`, so code calling through
+    /// it is bound by the loader and never reaches the helper thunks. Each slot is reset to its
+    /// new ILT value as a linker would emit it and its base relocation is dropped. The import
+    /// directory is rebuilt by [`PE::set_imports`] and the delay descriptor removed; on error the
+    /// image is left unchanged.
+    pub fn convert_delay_import(&mut self, dll: &str) -> Result<bool, FileParseError> {
+        let Some(delay) = self.delay_imports()? else {
+            return Ok(false);
+        };
+        let Some(delayed) = delay
+            .dlls
+            .iter()
+            .find(|entry| entry.dll_name.eq_ignore_ascii_case(dll))
+        else {
+            return Ok(false);
+        };
+        let descriptor = &delayed.descriptor;
+        if descriptor.attributes.value & DLATTR_RVA == 0 {
+            return Err(FileParseError::UnsupportedFeature(
+                "VA-based (pre-VC7) delay-load descriptors".to_string(),
+            ));
+        }
+        if descriptor.delay_import_name_table_rva.value == 0 {
+            return Err(FileParseError::UnsupportedFeature(format!(
+                "delay-load descriptor for {dll} has no INT"
+            )));
+        }
+
+        let thunk_size = match self.optional_header.pe_type()? {
+            PEType::PE32 => 4,
+            PEType::PE32Plus => 8,
+        };
+        let iat = descriptor.delay_import_address_table_rva.value;
+        let mut imports = ImportBuilder::from_directory(&self.imports()?);
+        imports.dlls.push(ImportedDll {
+            name: delayed.dll_name.clone(),
+            symbols: delayed.entries.iter().map(ImportSymbol::from).collect(),
+            first_thunk: Some(iat),
+            time_date_stamp: 0,
+            forwarder_chain: 0,
+        });
+        let mut builder = DelayImportBuilder::from_directory(&delay);
+        builder.remove(&delayed.dll_name);
+
+        // Each step commits to the buffer, so work on a copy and keep `self` intact on failure.
+        let mut pe = PE::from_buffer(self.buffer.clone())?;
+        pe.set_imports(&imports)?;
+        let converted = pe
+            .imports()?
+            .dlls
+            .pop()
+            .ok_or(FileParseError::InvalidFileFormat)?;
+        pe.restore_iat_from_lookup(&converted)?;
+        let mut relocations = pe.base_relocation_builder()?;
+        let end = iat + (converted.entries.len() * thunk_size) as u32;
+        if relocations.remove_range(iat, end) > 0 {
+            pe.set_base_relocations(&relocations)?;
+        }
+        pe.set_delay_imports(&builder)?;
+        *self = pe;
+        Ok(true)
+    }
+}
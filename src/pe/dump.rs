@@ -0,0 +1,168 @@
+//! Reconstruction of PE files from memory dumps of loaded modules.
+//!
+//! A dump is in virtual layout: every section sits at its RVA. Instead of moving data back, the
+//! section table is rewritten so that `PointerToRawData` equals `VirtualAddress`, which makes the
+//! dump a valid file as is. Relocations applied by the loader can be reverted, and an import
+//! directory can be rebuilt from the resolved addresses left in the IAT.
+
+use std::collections::HashMap;
+
+use crate::errors::FileParseError;
+use crate::pe::header::{self, ImageBase, PEType};
+use crate::pe::import_builder::{ImportBuilder, ImportSymbol, ImportedDll};
+use crate::pe::PE;
+use crate::utils::{extract_u32, extract_u64};
+
+/// One export of a module loaded next to the dumped image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleExport {
+    /// Absolute address the export resolved to in the dumped process.
+    pub address: u64,
+    /// Name or ordinal to import it by.
+    pub symbol: ImportSymbol,
+}
+
+/// Exports of a DLL referenced by a dumped IAT.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoadedModule {
+    /// DLL name written to the rebuilt descriptor (e.g. `KERNEL32.dll`).
+    pub name: String,
+    /// Resolved exports; forwarded exports may be listed with their target address.
+    pub exports: Vec<ModuleExport>,
+}
+
+impl PE {
+    /// Parses a module dumped from memory at `loaded_base`.
+    ///
+    /// Each section's `PointerToRawData` becomes its `VirtualAddress` and `SizeOfRawData` spans
+    /// up to the next section (the last one up to its aligned virtual end), clamped to the
+    /// buffer. `ImageBase` is set to `loaded_base` since that is what the dumped pointers are
+    /// relocated for. With `preferred_base`, base relocations move them back to it; the dumped
+    /// header cannot supply that value because the loader rewrites `ImageBase` on load.
+    pub fn from_mapped_image(
+        buffer: Vec<u8>,
+        loaded_base: u64,
+        preferred_base: Option<u64>,
+    ) -> Result<PE, FileParseError> {
+        let mut pe = PE::parse_headers(buffer)?;
+        let section_alignment = pe.optional_header.section_alignment.value.max(1);
+        let length = u32::try_from(pe.buffer.len()).map_err(|_| FileParseError::ValueTooLarge)?;
+        let starts: Vec<u32> = pe
+            .sections
+            .iter()
+            .map(|section| section.virtual_address.value)
+            .collect();
+        for (index, section) in pe.sections.iter_mut().enumerate() {
+            let start = section.virtual_address.value;
+            let end = match starts.get(index + 1) {
+                Some(&next) => next,
+                None => start
+                    .saturating_add(section.virtual_size.value)
+                    .next_multiple_of(section_alignment),
+            };
+            let size = end.min(length).saturating_sub(start.min(length));
+            let pointer = if size == 0 { 0 } else { start };
+            section
+                .pointer_to_raw_data
+                .update(&mut pe.buffer, pointer)?;
+            section.size_of_raw_data.update(&mut pe.buffer, size)?;
+        }
+
+        let mut pe = PE::from_buffer(pe.buffer)?;
+        let pe_type = pe.optional_header.pe_type()?;
+        let image_base = |base: u64| match pe_type {
+            PEType::PE32 => u32::try_from(base)
+                .map(ImageBase::Base32)
+                .map_err(|_| FileParseError::ValueTooLarge),
+            PEType::PE32Plus => Ok(ImageBase::Base64(base)),
+        };
+        pe.optional_header
+            .image_base
+            .update(&mut pe.buffer, image_base(loaded_base)?)?;
+        if let Some(preferred) = preferred_base {
+            pe.apply_image_base(image_base(preferred)?)?;
+        }
+        pe.sync_layout()?;
+        Ok(pe)
+    }
+
+    /// Rebuilds the import directory from the resolved addresses in the IAT at `iat_rva`.
+    ///
+    /// Slots are grouped into descriptors at zero terminators. Each group takes the first module
+    /// in `modules` that exports every address in it, so shared addresses (forwarded exports)
+    /// do not pin the group to the wrong DLL.
+    /// The descriptors keep the IAT at its RVA, the directory is written to a new `.idata2`
+    /// section, the `IAT` directory is set to the scanned range, and each slot is reset to its
+    /// lookup table value as a linker would emit it.
+    pub fn rebuild_imports_from_iat(
+        &mut self,
+        iat_rva: u32,
+        iat_size: u32,
+        modules: &[LoadedModule],
+    ) -> Result<(), FileParseError> {
+        let pe_type = self.optional_header.pe_type()?;
+        let thunk_size = match pe_type {
+            PEType::PE32 => 4,
+            PEType::PE32Plus => 8,
+        };
+        let lookups: Vec<HashMap<u64, &ImportSymbol>> = modules
+            .iter()
+            .map(|module| {
+                module
+                    .exports
+                    .iter()
+                    .map(|export| (export.address, &export.symbol))
+                    .collect()
+            })
+            .collect();
+
+        // Non-zero slots between terminators, keyed by the RVA of the first one.
+        let mut groups: Vec<(u32, Vec<u64>)> = Vec::new();
+        let mut open = false;
+        for slot in 0..iat_size / thunk_size {
+            let rva = iat_rva + slot * thunk_size;
+            let offset = self.rva_to_offset(rva)?;
+            let address = match pe_type {
+                PEType::PE32 => extract_u32(&self.buffer, offset)? as u64,
+                PEType::PE32Plus => extract_u64(&self.buffer, offset)?,
+            };
+            match groups.last_mut() {
+                Some((_, addresses)) if open && address != 0 => addresses.push(address),
+                _ if address != 0 => groups.push((rva, vec![address])),
+                _ => {}
+            }
+            open = address != 0;
+        }
+
+        let mut builder = ImportBuilder::default();
+        for (rva, addresses) in groups {
+            let module = lookups
+                .iter()
+                .position(|lookup| addresses.iter().all(|address| lookup.contains_key(address)))
+                .ok_or_else(|| {
+                    FileParseError::UnsupportedFeature(format!(
+                        "no module exports every address of the IAT group at RVA {rva:#x}"
+                    ))
+                })?;
+            builder.dlls.push(ImportedDll {
+                name: modules[module].name.clone(),
+                symbols: addresses
+                    .iter()
+                    .map(|address| lookups[module][address].clone())
+                    .collect(),
+                first_thunk: Some(rva),
+                time_date_stamp: 0,
+                forwarder_chain: 0,
+            });
+        }
+
+        self.set_imports(&builder)?;
+        self.sync_data_directory_rva(header::IAT, iat_rva)?;
+        self.sync_data_directory_size(header::IAT, iat_size)?;
+
+        for dll in self.imports()?.dlls {
+            self.restore_iat_from_lookup(&dll)?;
+        }
+        self.sync_layout()
+    }
+}
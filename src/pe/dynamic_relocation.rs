@@ -0,0 +1,452 @@
+//! Dynamic value relocation table (DVRT).
+//!
+//! The table is referenced from the load config (`DynamicValueRelocTableOffset` /
+//! `DynamicValueRelocTableSection`, or the older `DynamicValueRelocTable` VA). It starts with an
+//! `IMAGE_DYNAMIC_RELOCATION_TABLE` header followed by one `IMAGE_DYNAMIC_RELOCATION` per symbol.
+//! Each symbol owns `IMAGE_BASE_RELOCATION`-style page blocks whose record layout depends on the
+//! symbol: retpoline import / indirect / switch-table fixups and ARM64X fixups are decoded, other
+//! symbols are kept as a raw fixup range.
+
+use crate::errors::FileParseError;
+use crate::field::Field;
+use crate::pe::header::PEType;
+use crate::pe::PE;
+use crate::utils::{extract_u16, extract_u32, extract_u64};
+
+/// `IMAGE_DYNAMIC_RELOCATION_GUARD_RF_PROLOGUE`.
+pub const IMAGE_DYNAMIC_RELOCATION_GUARD_RF_PROLOGUE: u64 = 1;
+/// `IMAGE_DYNAMIC_RELOCATION_GUARD_RF_EPILOGUE`.
+pub const IMAGE_DYNAMIC_RELOCATION_GUARD_RF_EPILOGUE: u64 = 2;
+/// `IMAGE_DYNAMIC_RELOCATION_GUARD_IMPORT_CONTROL_TRANSFER` (retpoline imports).
+pub const IMAGE_DYNAMIC_RELOCATION_GUARD_IMPORT_CONTROL_TRANSFER: u64 = 3;
+/// `IMAGE_DYNAMIC_RELOCATION_GUARD_INDIR_CONTROL_TRANSFER` (retpoline indirect calls).
+pub const IMAGE_DYNAMIC_RELOCATION_GUARD_INDIR_CONTROL_TRANSFER: u64 = 4;
+/// `IMAGE_DYNAMIC_RELOCATION_GUARD_SWITCHTABLE_BRANCH` (retpoline switch tables).
+pub const IMAGE_DYNAMIC_RELOCATION_GUARD_SWITCHTABLE_BRANCH: u64 = 5;
+/// `IMAGE_DYNAMIC_RELOCATION_ARM64X`.
+pub const IMAGE_DYNAMIC_RELOCATION_ARM64X: u64 = 6;
+/// `IMAGE_DYNAMIC_RELOCATION_FUNCTION_OVERRIDE`.
+pub const IMAGE_DYNAMIC_RELOCATION_FUNCTION_OVERRIDE: u64 = 7;
+
+/// `IMAGE_DVRT_ARM64X_FIXUP_TYPE_ZEROFILL`.
+pub const IMAGE_DVRT_ARM64X_FIXUP_TYPE_ZEROFILL: u16 = 0;
+/// `IMAGE_DVRT_ARM64X_FIXUP_TYPE_VALUE`.
+pub const IMAGE_DVRT_ARM64X_FIXUP_TYPE_VALUE: u16 = 1;
+/// `IMAGE_DVRT_ARM64X_FIXUP_TYPE_DELTA`.
+pub const IMAGE_DVRT_ARM64X_FIXUP_TYPE_DELTA: u16 = 2;
+
+/// Decoded meaning of one DVRT record.
+#[derive(Debug, Clone)]
+pub enum DynamicRelocationKind {
+    /// `IMAGE_IMPORT_CONTROL_TRANSFER_DYNAMIC_RELOCATION`.
+    ImportControlTransfer {
+        /// Call goes through the IAT (`call [rip+x]`) rather than a direct `call`.
+        indirect_call: bool,
+        /// Index of the IAT slot.
+        iat_index: u32,
+    },
+    /// `IMAGE_INDIR_CONTROL_TRANSFER_DYNAMIC_RELOCATION`.
+    IndirectControlTransfer {
+        /// Site is a call rather than a jump.
+        indirect_call: bool,
+        /// Instruction carries a `REX.W` prefix.
+        rex_w_prefix: bool,
+        /// Site is a CFG check.
+        cfg_check: bool,
+    },
+    /// `IMAGE_SWITCHTABLE_BRANCH_DYNAMIC_RELOCATION`.
+    SwitchTableBranch {
+        /// Register holding the branch target.
+        register_number: u8,
+    },
+    /// ARM64X zero fill of `size` bytes.
+    Arm64xZeroFill {
+        /// Bytes to clear (1, 2, 4 or 8).
+        size: usize,
+    },
+    /// ARM64X value copy; the value width is [`Field::size`].
+    Arm64xValue {
+        /// Replacement bytes following the record header.
+        value: Field<u64>,
+    },
+    /// ARM64X delta added to the value at the target.
+    Arm64xDelta {
+        /// Encoded delta word following the record header.
+        value: Field<u16>,
+        /// Signed delta after scaling (`value * 4` or `value * 8`).
+        delta: i64,
+    },
+    /// Record of a symbol without a dedicated decoder (`type << 12 | offset`).
+    Other {
+        /// High nibble of the record word.
+        relocation_type: u8,
+    },
+}
+
+/// One record inside a DVRT page block.
+#[derive(Debug, Clone)]
+pub struct DynamicRelocationRecord {
+    /// Record header word (2 or 4 bytes on disk, see [`Field::size`]).
+    pub raw: Field<u32>,
+    /// Offset of the patched item within the block page.
+    pub page_offset: u16,
+    /// Decoded record.
+    pub kind: DynamicRelocationKind,
+}
+
+impl DynamicRelocationRecord {
+    /// RVA of the item patched by this record.
+    pub fn rva(&self, page_rva: u32) -> u32 {
+        page_rva + self.page_offset as u32
+    }
+}
+
+/// `IMAGE_BASE_RELOCATION`-style page block inside a DVRT symbol.
+pub struct DynamicRelocationBlock {
+    /// Page RVA covered by this block.
+    pub page_rva: Field<u32>,
+    /// Total block size in bytes, including this header.
+    pub block_size: Field<u32>,
+    /// Records following the 8-byte block header.
+    pub records: Vec<DynamicRelocationRecord>,
+}
+
+/// `IMAGE_DYNAMIC_RELOCATION` (v1) or `IMAGE_DYNAMIC_RELOCATION_V2` entry.
+pub struct DynamicRelocation {
+    /// Absolute file offset of the entry header.
+    pub offset: usize,
+    /// `HeaderSize` (v2 only).
+    pub header_size: Option<Field<u32>>,
+    /// `BaseRelocSize` (v1) or `FixupInfoSize` (v2).
+    pub fixup_info_size: Field<u32>,
+    /// `Symbol` (4 or 8 bytes on disk), one of `IMAGE_DYNAMIC_RELOCATION_*`.
+    pub symbol: Field<u64>,
+    /// `SymbolGroup` (v2 only).
+    pub symbol_group: Option<Field<u32>>,
+    /// `Flags` (v2 only).
+    pub flags: Option<Field<u32>>,
+    /// Absolute file offset of the fixup data.
+    pub fixup_offset: usize,
+    /// Decoded page blocks; empty for symbols without a block decoder.
+    pub blocks: Vec<DynamicRelocationBlock>,
+}
+
+/// `IMAGE_DYNAMIC_RELOCATION_TABLE` plus its entries.
+pub struct DynamicRelocationTable {
+    /// Absolute file offset of the table header.
+    pub offset: usize,
+    /// `Version` (1 or 2).
+    pub version: Field<u32>,
+    /// `Size` of the entries following the 8-byte header.
+    pub size: Field<u32>,
+    /// Entries in file order.
+    pub relocations: Vec<DynamicRelocation>,
+}
+
+impl DynamicRelocationTable {
+    /// Parses the table at `offset`.
+    pub fn parse(buffer: &[u8], offset: usize, pe_type: PEType) -> Result<Self, FileParseError> {
+        let version = extract_u32(buffer, offset)?;
+        let size = extract_u32(buffer, offset + 4)?;
+        let start = offset + 8;
+        let end = start
+            .checked_add(size as usize)
+            .ok_or(FileParseError::BufferOverflow)?;
+        if buffer.len() < end {
+            return Err(FileParseError::BufferOverflow);
+        }
+        let pointer_size = match pe_type {
+            PEType::PE32 => 4,
+            PEType::PE32Plus => 8,
+        };
+        let read_symbol = |off: usize| -> Result<Field<u64>, FileParseError> {
+            let value = match pe_type {
+                PEType::PE32 => extract_u32(buffer, off)? as u64,
+                PEType::PE32Plus => extract_u64(buffer, off)?,
+            };
+            Ok(Field::new(value, off, pointer_size))
+        };
+
+        let mut relocations = Vec::new();
+        let mut cursor = start;
+        while cursor < end {
+            let mut relocation = match version {
+                1 => {
+                    let symbol = read_symbol(cursor)?;
+                    let size_offset = cursor + pointer_size;
+                    DynamicRelocation {
+                        offset: cursor,
+                        header_size: None,
+                        fixup_info_size: Field::new(
+                            extract_u32(buffer, size_offset)?,
+                            size_offset,
+                            4,
+                        ),
+                        symbol,
+                        symbol_group: None,
+                        flags: None,
+                        fixup_offset: size_offset + 4,
+                        blocks: Vec::new(),
+                    }
+                }
+                2 => {
+                    let header_size = extract_u32(buffer, cursor)?;
+                    if (header_size as usize) < 8 + pointer_size + 8 {
+                        return Err(FileParseError::InvalidFileFormat);
+                    }
+                    let group_offset = cursor + 8 + pointer_size;
+                    DynamicRelocation {
+                        offset: cursor,
+                        header_size: Some(Field::new(header_size, cursor, 4)),
+                        fixup_info_size: Field::new(
+                            extract_u32(buffer, cursor + 4)?,
+                            cursor + 4,
+                            4,
+                        ),
+                        symbol: read_symbol(cursor + 8)?,
+                        symbol_group: Some(Field::new(
+                            extract_u32(buffer, group_offset)?,
+                            group_offset,
+                            4,
+                        )),
+                        flags: Some(Field::new(
+                            extract_u32(buffer, group_offset + 4)?,
+                            group_offset + 4,
+                            4,
+                        )),
+                        fixup_offset: cursor + header_size as usize,
+                        blocks: Vec::new(),
+                    }
+                }
+                other => {
+                    return Err(FileParseError::UnsupportedFeature(format!(
+                        "dynamic relocation table version {other}"
+                    )))
+                }
+            };
+
+            let fixup_end = relocation
+                .fixup_offset
+                .checked_add(relocation.fixup_info_size.value as usize)
+                .ok_or(FileParseError::BufferOverflow)?;
+            if fixup_end > end {
+                return Err(FileParseError::BufferOverflow);
+            }
+            if has_block_decoder(relocation.symbol.value) {
+                relocation.blocks = parse_blocks(
+                    buffer,
+                    relocation.fixup_offset,
+                    fixup_end,
+                    relocation.symbol.value,
+                )?;
+            }
+            relocations.push(relocation);
+            cursor = fixup_end;
+        }
+
+        Ok(DynamicRelocationTable {
+            offset,
+            version: Field::new(version, offset, 4),
+            size: Field::new(size, offset + 4, 4),
+            relocations,
+        })
+    }
+}
+
+fn has_block_decoder(symbol: u64) -> bool {
+    matches!(
+        symbol,
+        IMAGE_DYNAMIC_RELOCATION_GUARD_IMPORT_CONTROL_TRANSFER
+            | IMAGE_DYNAMIC_RELOCATION_GUARD_INDIR_CONTROL_TRANSFER
+            | IMAGE_DYNAMIC_RELOCATION_GUARD_SWITCHTABLE_BRANCH
+            | IMAGE_DYNAMIC_RELOCATION_ARM64X
+    )
+}
+
+fn parse_blocks(
+    buffer: &[u8],
+    offset: usize,
+    end: usize,
+    symbol: u64,
+) -> Result<Vec<DynamicRelocationBlock>, FileParseError> {
+    let mut blocks = Vec::new();
+    let mut current = offset;
+    while current < end {
+        if end - current < 8 {
+            return Err(FileParseError::InvalidFileFormat);
+        }
+        let page_rva = extract_u32(buffer, current)?;
+        let block_size = extract_u32(buffer, current + 4)?;
+        if block_size < 8 {
+            return Err(FileParseError::InvalidFileFormat);
+        }
+        let block_end = current
+            .checked_add(block_size as usize)
+            .ok_or(FileParseError::BufferOverflow)?;
+        if block_end > end {
+            return Err(FileParseError::BufferOverflow);
+        }
+
+        let mut records = Vec::new();
+        let mut cursor = current + 8;
+        while cursor < block_end {
+            // A zero word in the last two bytes keeps the next block 4-byte aligned.
+            if block_end - cursor == 2 && extract_u16(buffer, cursor)? == 0 {
+                break;
+            }
+            let (record, next) = parse_record(buffer, cursor, block_end, symbol)?;
+            records.push(record);
+            cursor = next;
+        }
+
+        blocks.push(DynamicRelocationBlock {
+            page_rva: Field::new(page_rva, current, 4),
+            block_size: Field::new(block_size, current + 4, 4),
+            records,
+        });
+        current = block_end;
+    }
+    Ok(blocks)
+}
+
+/// Decodes one record at `offset`, returning it with the offset of the next record.
+fn parse_record(
+    buffer: &[u8],
+    offset: usize,
+    end: usize,
+    symbol: u64,
+) -> Result<(DynamicRelocationRecord, usize), FileParseError> {
+    let need = |length: usize| -> Result<(), FileParseError> {
+        if offset + length > end {
+            Err(FileParseError::BufferOverflow)
+        } else {
+            Ok(())
+        }
+    };
+
+    if symbol == IMAGE_DYNAMIC_RELOCATION_GUARD_IMPORT_CONTROL_TRANSFER {
+        need(4)?;
+        let raw = extract_u32(buffer, offset)?;
+        let record = DynamicRelocationRecord {
+            raw: Field::new(raw, offset, 4),
+            page_offset: (raw & 0x0FFF) as u16,
+            kind: DynamicRelocationKind::ImportControlTransfer {
+                indirect_call: raw & (1 << 12) != 0,
+                iat_index: raw >> 13,
+            },
+        };
+        return Ok((record, offset + 4));
+    }
+
+    need(2)?;
+    let word = extract_u16(buffer, offset)?;
+    let page_offset = word & 0x0FFF;
+    let mut next = offset + 2;
+    let kind = match symbol {
+        IMAGE_DYNAMIC_RELOCATION_GUARD_INDIR_CONTROL_TRANSFER => {
+            DynamicRelocationKind::IndirectControlTransfer {
+                indirect_call: word & (1 << 12) != 0,
+                rex_w_prefix: word & (1 << 13) != 0,
+                cfg_check: word & (1 << 14) != 0,
+            }
+        }
+        IMAGE_DYNAMIC_RELOCATION_GUARD_SWITCHTABLE_BRANCH => {
+            DynamicRelocationKind::SwitchTableBranch {
+                register_number: (word >> 12) as u8,
+            }
+        }
+        IMAGE_DYNAMIC_RELOCATION_ARM64X => {
+            let size_bits = (word >> 14) & 0x3;
+            match (word >> 12) & 0x3 {
+                IMAGE_DVRT_ARM64X_FIXUP_TYPE_ZEROFILL => DynamicRelocationKind::Arm64xZeroFill {
+                    size: 1 << size_bits,
+                },
+                IMAGE_DVRT_ARM64X_FIXUP_TYPE_VALUE => {
+                    let size = 1usize << size_bits;
+                    need(2 + size)?;
+                    let value = buffer[next..next + size]
+                        .iter()
+                        .rev()
+                        .fold(0u64, |acc, byte| (acc << 8) | *byte as u64);
+                    let field = Field::new(value, next, size);
+                    next += size;
+                    DynamicRelocationKind::Arm64xValue { value: field }
+                }
+                IMAGE_DVRT_ARM64X_FIXUP_TYPE_DELTA => {
+                    need(4)?;
+                    let value = extract_u16(buffer, next)?;
+                    let scale = if size_bits & 0x2 != 0 { 8 } else { 4 };
+                    let magnitude = value as i64 * scale;
+                    let field = Field::new(value, next, 2);
+                    next += 2;
+                    DynamicRelocationKind::Arm64xDelta {
+                        value: field,
+                        delta: if size_bits & 0x1 != 0 {
+                            -magnitude
+                        } else {
+                            magnitude
+                        },
+                    }
+                }
+                _ => return Err(FileParseError::InvalidFileFormat),
+            }
+        }
+        _ => DynamicRelocationKind::Other {
+            relocation_type: (word >> 12) as u8,
+        },
+    };
+
+    let record = DynamicRelocationRecord {
+        raw: Field::new(word as u32, offset, 2),
+        page_offset,
+        kind,
+    };
+    Ok((record, next))
+}
+
+impl PE {
+    /// Parses the dynamic value relocation table referenced from the load config, when present.
+    pub fn dynamic_relocations(&self) -> Result<Option<DynamicRelocationTable>, FileParseError> {
+        let Some(config) = self.load_config()? else {
+            return Ok(None);
+        };
+        let section_index = config
+            .dynamic_value_reloc_table_section
+            .as_ref()
+            .map_or(0, |field| field.value as usize);
+        let table_offset = config
+            .dynamic_value_reloc_table_offset
+            .as_ref()
+            .map_or(0, |field| field.value as usize);
+
+        let offset = if section_index != 0 {
+            let section = self
+                .sections
+                .get(section_index - 1)
+                .ok_or(FileParseError::InvalidFileFormat)?;
+            if table_offset >= section.size_of_raw_data.value as usize {
+                return Err(FileParseError::BufferOverflow);
+            }
+            section.pointer_to_raw_data.value as usize + table_offset
+        } else {
+            match config.dynamic_value_reloc_table.as_ref() {
+                Some(table) if table.value != 0 => {
+                    let image_base = self.optional_header.image_base.value.value();
+                    let rva = table
+                        .value
+                        .checked_sub(image_base)
+                        .and_then(|rva| u32::try_from(rva).ok())
+                        .ok_or(FileParseError::InvalidFileFormat)?;
+                    self.rva_to_offset(rva)?
+                }
+                _ => return Ok(None),
+            }
+        };
+
+        Ok(Some(DynamicRelocationTable::parse(
+            &self.buffer,
+            offset,
+            self.optional_header.pe_type()?,
+        )?))
+    }
+}
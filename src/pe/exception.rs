@@ -1,8 +1,43 @@
-//! Exception directory (`RUNTIME_FUNCTION` entries on x64).
+//! Exception directory (`RUNTIME_FUNCTION` entries on x64) and `UNWIND_INFO` records.
+//!
+//! Each runtime function points at an `UNWIND_INFO` block in `.xdata`. [`UnwindInfo`] decodes the
+//! header, the `UNWIND_CODE` array (with multi-slot operations folded into one [`UnwindCode`]),
+//! the exception handler RVA, and the chained `RUNTIME_FUNCTION` when present.
 
 use crate::errors::FileParseError;
 use crate::field::Field;
-use crate::utils::extract_u32;
+use crate::pe::arm64_exception::Arm64ExceptionDirectory;
+use crate::utils::{extract_u16, extract_u32};
+
+/// `UNW_FLAG_EHANDLER` — the function has an exception handler.
+pub const UNW_FLAG_EHANDLER: u8 = 0x1;
+/// `UNW_FLAG_UHANDLER` — the function has a termination handler.
+pub const UNW_FLAG_UHANDLER: u8 = 0x2;
+/// `UNW_FLAG_CHAININFO` — the unwind info is chained to a previous `RUNTIME_FUNCTION`.
+pub const UNW_FLAG_CHAININFO: u8 = 0x4;
+
+/// `UWOP_PUSH_NONVOL`.
+pub const UWOP_PUSH_NONVOL: u8 = 0;
+/// `UWOP_ALLOC_LARGE`.
+pub const UWOP_ALLOC_LARGE: u8 = 1;
+/// `UWOP_ALLOC_SMALL`.
+pub const UWOP_ALLOC_SMALL: u8 = 2;
+/// `UWOP_SET_FPREG`.
+pub const UWOP_SET_FPREG: u8 = 3;
+/// `UWOP_SAVE_NONVOL`.
+pub const UWOP_SAVE_NONVOL: u8 = 4;
+/// `UWOP_SAVE_NONVOL_FAR`.
+pub const UWOP_SAVE_NONVOL_FAR: u8 = 5;
+/// `UWOP_EPILOG` (version 2; `UWOP_SAVE_XMM` in version 1).
+pub const UWOP_EPILOG: u8 = 6;
+/// `UWOP_SPARE_CODE` (version 2; `UWOP_SAVE_XMM_FAR` in version 1).
+pub const UWOP_SPARE_CODE: u8 = 7;
+/// `UWOP_SAVE_XMM128`.
+pub const UWOP_SAVE_XMM128: u8 = 8;
+/// `UWOP_SAVE_XMM128_FAR`.
+pub const UWOP_SAVE_XMM128_FAR: u8 = 9;
+/// `UWOP_PUSH_MACHFRAME`.
+pub const UWOP_PUSH_MACHFRAME: u8 = 10;
 
 /// `IMAGE_RUNTIME_FUNCTION_ENTRY` — 12 bytes (`.pdata` on x64).
 pub struct RuntimeFunction {
@@ -30,6 +65,268 @@ impl RuntimeFunction {
             unwind_data: Field::new(extract_u32(buffer, offset + 8)?, offset + 8, 4),
         })
     }
+
+    /// Decodes the `UNWIND_INFO` referenced by [`Self::unwind_data`].
+    ///
+    /// Entries whose unwind RVA has bit 0 set point at another `RUNTIME_FUNCTION` rather than
+    /// unwind data and yield [`FileParseError::UnsupportedFeature`].
+    pub fn unwind_info(
+        &self,
+        buffer: &[u8],
+        rva_to_offset: impl Fn(u32) -> Result<usize, FileParseError>,
+    ) -> Result<UnwindInfo, FileParseError> {
+        if self.unwind_data.value & 1 != 0 {
+            return Err(FileParseError::UnsupportedFeature(
+                "indirect RUNTIME_FUNCTION unwind reference".into(),
+            ));
+        }
+        UnwindInfo::parse(buffer, rva_to_offset(self.unwind_data.value)?)
+    }
+}
+
+/// Decoded `UNWIND_CODE` operation (register numbers use the x64 `UNWIND_REGISTER` encoding).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnwindOperation {
+    /// `UWOP_PUSH_NONVOL` — push a non-volatile integer register.
+    PushNonvol { register: u8 },
+    /// `UWOP_ALLOC_LARGE` — allocate `size` bytes (16- or 32-bit operand).
+    AllocLarge { size: u32 },
+    /// `UWOP_ALLOC_SMALL` — allocate 8..=128 bytes.
+    AllocSmall { size: u32 },
+    /// `UWOP_SET_FPREG` — establish the frame pointer register.
+    SetFpreg,
+    /// `UWOP_SAVE_NONVOL` / `UWOP_SAVE_NONVOL_FAR` — save a register at `offset` from RSP.
+    SaveNonvol { register: u8, offset: u32 },
+    /// `UWOP_SAVE_XMM128` / `UWOP_SAVE_XMM128_FAR` — save `XMM{register}` at `offset` from RSP.
+    SaveXmm128 { register: u8, offset: u32 },
+    /// `UWOP_EPILOG` (version 2) — epilog location descriptor.
+    Epilog { info: u8 },
+    /// `UWOP_PUSH_MACHFRAME` — push a machine frame (`error_code` when `OpInfo == 1`).
+    PushMachframe { error_code: bool },
+    /// Operation not decoded by this parser (`UWOP_SPARE_CODE`, version-1 XMM ops, …).
+    Unknown { op: u8, info: u8 },
+}
+
+/// One decoded `UNWIND_CODE` plus any extra operand slots it consumes.
+pub struct UnwindCode {
+    /// Prolog offset of the end of the instruction (`CodeOffset`).
+    pub code_offset: Field<u8>,
+    /// Packed `UnwindOp` (low nibble) and `OpInfo` (high nibble).
+    pub unwind_op_and_info: Field<u8>,
+    /// Number of 16-bit slots consumed in the code array (including this one).
+    pub slots: usize,
+    /// Decoded operation.
+    pub operation: UnwindOperation,
+}
+
+impl UnwindCode {
+    /// `UnwindOp` nibble.
+    pub fn unwind_op(&self) -> u8 {
+        self.unwind_op_and_info.value & 0x0F
+    }
+
+    /// `OpInfo` nibble.
+    pub fn op_info(&self) -> u8 {
+        self.unwind_op_and_info.value >> 4
+    }
+}
+
+/// `UNWIND_INFO` — header, unwind codes, and optional handler / chained entry.
+pub struct UnwindInfo {
+    /// Absolute file offset of this structure.
+    pub offset: usize,
+    /// Packed `Version` (low 3 bits) and `Flags` (high 5 bits).
+    pub version_and_flags: Field<u8>,
+    /// `SizeOfProlog`.
+    pub size_of_prolog: Field<u8>,
+    /// `CountOfCodes` (number of 16-bit slots, not operations).
+    pub count_of_codes: Field<u8>,
+    /// Packed `FrameRegister` (low nibble) and `FrameOffset` (high nibble).
+    pub frame_register_and_offset: Field<u8>,
+    /// Decoded unwind operations in array order.
+    pub codes: Vec<UnwindCode>,
+    /// Exception / termination handler RVA (`UNW_FLAG_EHANDLER` or `UNW_FLAG_UHANDLER`).
+    pub exception_handler: Option<Field<u32>>,
+    /// Absolute file offset of the language-specific handler data following the handler RVA.
+    pub handler_data_offset: Option<usize>,
+    /// Chained runtime function (`UNW_FLAG_CHAININFO`).
+    pub chained: Option<RuntimeFunction>,
+}
+
+impl UnwindInfo {
+    /// Parses `UNWIND_INFO` at `offset`.
+    pub fn parse(buffer: &[u8], offset: usize) -> Result<Self, FileParseError> {
+        if buffer.len() < offset + 4 {
+            return Err(FileParseError::BufferOverflow);
+        }
+
+        let version_and_flags = Field::new(buffer[offset], offset, 1);
+        let size_of_prolog = Field::new(buffer[offset + 1], offset + 1, 1);
+        let count_of_codes = Field::new(buffer[offset + 2], offset + 2, 1);
+        let frame_register_and_offset = Field::new(buffer[offset + 3], offset + 3, 1);
+
+        let version = version_and_flags.value & 0x07;
+        if version != 1 && version != 2 {
+            return Err(FileParseError::UnsupportedFeature(format!(
+                "UNWIND_INFO version {version}"
+            )));
+        }
+
+        let slot_count = count_of_codes.value as usize;
+        let codes_offset = offset + 4;
+        if buffer.len() < codes_offset + slot_count * 2 {
+            return Err(FileParseError::BufferOverflow);
+        }
+
+        let mut codes = Vec::new();
+        let mut slot = 0usize;
+        while slot < slot_count {
+            let slot_off = codes_offset + slot * 2;
+            let code = parse_unwind_code(buffer, slot_off, slot_count - slot, version)?;
+            slot += code.slots;
+            codes.push(code);
+        }
+
+        let flags = version_and_flags.value >> 3;
+        let trailer = codes_offset + slot_count.next_multiple_of(2) * 2;
+        let (exception_handler, handler_data_offset, chained) = if flags & UNW_FLAG_CHAININFO != 0 {
+            (None, None, Some(RuntimeFunction::parse(buffer, trailer)?))
+        } else if flags & (UNW_FLAG_EHANDLER | UNW_FLAG_UHANDLER) != 0 {
+            let handler = Field::new(extract_u32(buffer, trailer)?, trailer, 4);
+            (Some(handler), Some(trailer + 4), None)
+        } else {
+            (None, None, None)
+        };
+
+        Ok(UnwindInfo {
+            offset,
+            version_and_flags,
+            size_of_prolog,
+            count_of_codes,
+            frame_register_and_offset,
+            codes,
+            exception_handler,
+            handler_data_offset,
+            chained,
+        })
+    }
+
+    /// `Version` (1 or 2).
+    pub fn version(&self) -> u8 {
+        self.version_and_flags.value & 0x07
+    }
+
+    /// `Flags` (`UNW_FLAG_*`).
+    pub fn flags(&self) -> u8 {
+        self.version_and_flags.value >> 3
+    }
+
+    /// Frame register number (`0` when no frame pointer is used).
+    pub fn frame_register(&self) -> u8 {
+        self.frame_register_and_offset.value & 0x0F
+    }
+
+    /// Scaled frame register offset from RSP (`FrameOffset * 16`).
+    pub fn frame_offset(&self) -> u32 {
+        (self.frame_register_and_offset.value >> 4) as u32 * 16
+    }
+}
+
+fn parse_unwind_code(
+    buffer: &[u8],
+    offset: usize,
+    remaining_slots: usize,
+    version: u8,
+) -> Result<UnwindCode, FileParseError> {
+    let code_offset = Field::new(buffer[offset], offset, 1);
+    let unwind_op_and_info = Field::new(buffer[offset + 1], offset + 1, 1);
+    let op = unwind_op_and_info.value & 0x0F;
+    let info = unwind_op_and_info.value >> 4;
+
+    let operand_u16 = |index: usize| -> Result<u32, FileParseError> {
+        Ok(extract_u16(buffer, offset + index * 2)? as u32)
+    };
+    let operand_u32 = || -> Result<u32, FileParseError> { extract_u32(buffer, offset + 2) };
+
+    let (slots, operation) = match op {
+        UWOP_PUSH_NONVOL => (1, UnwindOperation::PushNonvol { register: info }),
+        UWOP_ALLOC_LARGE if info == 0 => (
+            2,
+            UnwindOperation::AllocLarge {
+                size: operand_u16(1)? * 8,
+            },
+        ),
+        UWOP_ALLOC_LARGE => (
+            3,
+            UnwindOperation::AllocLarge {
+                size: operand_u32()?,
+            },
+        ),
+        UWOP_ALLOC_SMALL => (
+            1,
+            UnwindOperation::AllocSmall {
+                size: info as u32 * 8 + 8,
+            },
+        ),
+        UWOP_SET_FPREG => (1, UnwindOperation::SetFpreg),
+        UWOP_SAVE_NONVOL => (
+            2,
+            UnwindOperation::SaveNonvol {
+                register: info,
+                offset: operand_u16(1)? * 8,
+            },
+        ),
+        UWOP_SAVE_NONVOL_FAR => (
+            3,
+            UnwindOperation::SaveNonvol {
+                register: info,
+                offset: operand_u32()?,
+            },
+        ),
+        UWOP_EPILOG if version == 2 => (2, UnwindOperation::Epilog { info }),
+        UWOP_EPILOG => (2, UnwindOperation::Unknown { op, info }),
+        UWOP_SPARE_CODE => (3, UnwindOperation::Unknown { op, info }),
+        UWOP_SAVE_XMM128 => (
+            2,
+            UnwindOperation::SaveXmm128 {
+                register: info,
+                offset: operand_u16(1)? * 16,
+            },
+        ),
+        UWOP_SAVE_XMM128_FAR => (
+            3,
+            UnwindOperation::SaveXmm128 {
+                register: info,
+                offset: operand_u32()?,
+            },
+        ),
+        UWOP_PUSH_MACHFRAME => (
+            1,
+            UnwindOperation::PushMachframe {
+                error_code: info == 1,
+            },
+        ),
+        _ => (1, UnwindOperation::Unknown { op, info }),
+    };
+
+    if slots > remaining_slots {
+        return Err(FileParseError::InvalidFileFormat);
+    }
+
+    Ok(UnwindCode {
+        code_offset,
+        unwind_op_and_info,
+        slots,
+        operation,
+    })
+}
+
+/// Exception directory decoded with the `.pdata` layout of the image architecture.
+pub enum ExceptionTable {
+    /// x64 three-dword `RUNTIME_FUNCTION` entries.
+    X64(ExceptionDirectory),
+    /// ARM64 two-dword entries with packed or `.xdata` unwind data.
+    Arm64(Arm64ExceptionDirectory),
 }
 
 /// Parsed exception / runtime function directory.
@@ -0,0 +1,328 @@
+//! Editable export model and export directory serializer.
+//!
+//! [`ExportBuilder`] keys exports by ordinal. [`ExportBuilder::build`] writes the 40-byte
+//! `IMAGE_EXPORT_DIRECTORY`, the address table (`Base` = lowest ordinal, gaps left zero), the
+//! lexically sorted name pointer table, the ordinal table, and finally the DLL name, export names
+//! and forwarder strings. Forwarders only resolve when the data directory size covers the whole
+//! blob, so [`PE::set_exports`] always records the full length.
+
+use std::collections::BTreeMap;
+
+use crate::errors::FileParseError;
+use crate::pe::export::{Exports, FunctionExport};
+use crate::pe::{header, section, PE};
+
+/// Size of `IMAGE_EXPORT_DIRECTORY` in bytes.
+const DIRECTORY_SIZE: usize = 40;
+
+/// What an export resolves to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExportTarget {
+    /// Code or data RVA inside the image.
+    Rva(u32),
+    /// Forwarder string such as `OTHER.Func` or `OTHER.#12`.
+    Forwarder(String),
+}
+
+/// One exported ordinal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportedSymbol {
+    /// Export names in name table order; several names are aliases of one ordinal and an empty
+    /// list makes the export ordinal-only.
+    pub names: Vec<String>,
+    /// Address or forwarder.
+    pub target: ExportTarget,
+}
+
+/// Editable export directory model.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExportBuilder {
+    /// DLL name written to `Name`.
+    pub dll_name: String,
+    /// `TimeDateStamp`.
+    pub time_date_stamp: u32,
+    /// `MajorVersion`.
+    pub major_version: u16,
+    /// `MinorVersion`.
+    pub minor_version: u16,
+    /// Exports keyed by ordinal.
+    pub exports: BTreeMap<u16, ExportedSymbol>,
+}
+
+impl ExportBuilder {
+    /// Creates an empty export table for `dll_name`.
+    pub fn new(dll_name: &str) -> Self {
+        ExportBuilder {
+            dll_name: dll_name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Copies a parsed export table; zero address table slots are dropped.
+    pub fn from_exports(exports: &Exports, dll_name: &str) -> Self {
+        let mut builder = ExportBuilder::new(dll_name);
+        builder.time_date_stamp = exports.directory.time_date_stamp.value;
+        builder.major_version = exports.directory.major_version.value;
+        builder.minor_version = exports.directory.minor_version.value;
+        for function in &exports.functions {
+            let (ordinal, index, target) = match function {
+                FunctionExport::Local {
+                    ordinal,
+                    index,
+                    function_rva,
+                } => {
+                    if function_rva.value == 0 {
+                        continue;
+                    }
+                    (*ordinal, *index, ExportTarget::Rva(function_rva.value))
+                }
+                FunctionExport::Forwarder {
+                    ordinal,
+                    index,
+                    forwarder,
+                    ..
+                } => (*ordinal, *index, ExportTarget::Forwarder(forwarder.clone())),
+            };
+            let names = exports
+                .named
+                .iter()
+                .filter(|named| named.name_ordinal_index.value as usize == index)
+                .map(|named| named.name.clone())
+                .collect();
+            builder
+                .exports
+                .insert(ordinal, ExportedSymbol { names, target });
+        }
+        builder
+    }
+
+    /// Adds a named export at the next free ordinal and returns that ordinal.
+    ///
+    /// An existing export with the same name is replaced in place.
+    pub fn add(&mut self, name: &str, target: ExportTarget) -> Result<u16, FileParseError> {
+        if let Some(ordinal) = self.ordinal_of(name) {
+            self.exports
+                .get_mut(&ordinal)
+                .expect("ordinal just found")
+                .target = target;
+            return Ok(ordinal);
+        }
+        let ordinal = match self.exports.last_key_value() {
+            Some((last, _)) => last.checked_add(1).ok_or(FileParseError::ValueTooLarge)?,
+            None => 1,
+        };
+        self.insert(ordinal, Some(name), target);
+        Ok(ordinal)
+    }
+
+    /// Inserts or replaces the export at `ordinal`; `name` `None` makes it ordinal-only.
+    ///
+    /// A `name` already used by another ordinal is taken from it as [`ExportBuilder::remove`]
+    /// would.
+    pub fn insert(&mut self, ordinal: u16, name: Option<&str>, target: ExportTarget) {
+        if let Some(name) = name {
+            self.remove(name);
+        }
+        self.exports.insert(
+            ordinal,
+            ExportedSymbol {
+                names: name.map(str::to_string).into_iter().collect(),
+                target,
+            },
+        );
+    }
+
+    /// Renames the export `old` to `new`; returns `false` when `old` is missing or `new` exists.
+    pub fn rename(&mut self, old: &str, new: &str) -> bool {
+        if self.ordinal_of(new).is_some() {
+            return false;
+        }
+        match self.ordinal_of(old) {
+            Some(ordinal) => {
+                let names = &mut self
+                    .exports
+                    .get_mut(&ordinal)
+                    .expect("ordinal just found")
+                    .names;
+                for name in names.iter_mut().filter(|name| *name == old) {
+                    *name = new.to_string();
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Removes the export named `name`; returns `false` when it is missing.
+    ///
+    /// When the ordinal has other names only the `name` alias is dropped.
+    pub fn remove(&mut self, name: &str) -> bool {
+        let Some(ordinal) = self.ordinal_of(name) else {
+            return false;
+        };
+        let symbol = self.exports.get_mut(&ordinal).expect("ordinal just found");
+        symbol.names.retain(|alias| alias != name);
+        if symbol.names.is_empty() {
+            self.exports.remove(&ordinal);
+        }
+        true
+    }
+
+    /// Removes the export at `ordinal`; returns `false` when it is missing.
+    pub fn remove_ordinal(&mut self, ordinal: u16) -> bool {
+        self.exports.remove(&ordinal).is_some()
+    }
+
+    /// Ordinal of the export named `name`.
+    pub fn ordinal_of(&self, name: &str) -> Option<u16> {
+        self.exports
+            .iter()
+            .find(|(_, symbol)| symbol.names.iter().any(|alias| alias == name))
+            .map(|(ordinal, _)| *ordinal)
+    }
+
+    /// Serializes the export directory for placement at `rva`.
+    pub fn build(&self, rva: u32) -> Result<Vec<u8>, FileParseError> {
+        let base = self.exports.keys().next().copied().unwrap_or(1);
+        let function_count = self
+            .exports
+            .keys()
+            .next_back()
+            .map_or(0, |last| (last - base) as usize + 1);
+        let mut names: Vec<(&str, u16)> = self
+            .exports
+            .iter()
+            .flat_map(|(ordinal, symbol)| {
+                symbol
+                    .names
+                    .iter()
+                    .map(move |name| (name.as_str(), *ordinal))
+            })
+            .collect();
+        names.sort_unstable_by(|a, b| a.0.as_bytes().cmp(b.0.as_bytes()));
+
+        let functions_at = DIRECTORY_SIZE;
+        let names_at = functions_at + function_count * 4;
+        let ordinals_at = names_at + names.len() * 4;
+        let strings_at = ordinals_at + names.len() * 2;
+
+        let mut out = vec![0u8; strings_at];
+        let to_rva = |offset: usize| -> Result<u32, FileParseError> {
+            u32::try_from(offset)
+                .ok()
+                .and_then(|offset| rva.checked_add(offset))
+                .ok_or(FileParseError::ValueTooLarge)
+        };
+        let push_string = |out: &mut Vec<u8>, text: &str| -> Result<u32, FileParseError> {
+            let at = to_rva(out.len())?;
+            out.extend_from_slice(text.as_bytes());
+            out.push(0);
+            Ok(at)
+        };
+
+        let dll_name_rva = push_string(&mut out, &self.dll_name)?;
+        for (index, (name, ordinal)) in names.iter().enumerate() {
+            if index > 0 && names[index - 1].0 == *name {
+                return Err(FileParseError::UnsupportedFeature(format!(
+                    "duplicate export name {name}"
+                )));
+            }
+            let name_rva = push_string(&mut out, name)?;
+            let slot = names_at + index * 4;
+            out[slot..slot + 4].copy_from_slice(&name_rva.to_le_bytes());
+            let slot = ordinals_at + index * 2;
+            out[slot..slot + 2].copy_from_slice(&(ordinal - base).to_le_bytes());
+        }
+        for (ordinal, symbol) in &self.exports {
+            let address = match &symbol.target {
+                ExportTarget::Rva(address) => *address,
+                ExportTarget::Forwarder(forwarder) => push_string(&mut out, forwarder)?,
+            };
+            let slot = functions_at + (ordinal - base) as usize * 4;
+            out[slot..slot + 4].copy_from_slice(&address.to_le_bytes());
+        }
+
+        let header: [u32; 10] = [
+            0,
+            self.time_date_stamp,
+            u32::from(self.major_version) | (u32::from(self.minor_version) << 16),
+            dll_name_rva,
+            u32::from(base),
+            function_count as u32,
+            names.len() as u32,
+            to_rva(functions_at)?,
+            to_rva(names_at)?,
+            to_rva(ordinals_at)?,
+        ];
+        for (index, value) in header.iter().enumerate() {
+            out[index * 4..index * 4 + 4].copy_from_slice(&value.to_le_bytes());
+        }
+        Ok(out)
+    }
+}
+
+impl PE {
+    /// Loads the current export table into an editable [`ExportBuilder`] (empty when absent).
+    pub fn export_builder(&self) -> Result<ExportBuilder, FileParseError> {
+        match self.exports()? {
+            Some(exports) => {
+                let dll_name = exports
+                    .directory
+                    .dll_name(&self.buffer, |rva| self.rva_to_offset(rva))?;
+                Ok(ExportBuilder::from_exports(&exports, &dll_name))
+            }
+            None => Ok(ExportBuilder::default()),
+        }
+    }
+
+    /// Serializes `builder` in place when it fits the current export directory, otherwise into
+    /// a new `.edata` section.
+    pub fn set_exports(&mut self, builder: &ExportBuilder) -> Result<(), FileParseError> {
+        if !self.optional_header.has_data_directory(header::EXPORT) {
+            return Err(FileParseError::UnsupportedFeature(
+                "image has no export data directory slot".to_string(),
+            ));
+        }
+        let current = &self.optional_header.data_directories[header::EXPORT];
+        let (current_rva, current_size) = (current.virtual_address.value, current.size.value);
+
+        let in_place = if current_rva != 0 {
+            let data = builder.build(current_rva)?;
+            let start = self.rva_to_offset(current_rva)?;
+            let end = start + current_size as usize;
+            match self.buffer.get_mut(start..end) {
+                Some(raw) if data.len() <= raw.len() => {
+                    raw.fill(0);
+                    raw[..data.len()].copy_from_slice(&data);
+                    Some(data.len())
+                }
+                _ => None,
+            }
+        } else {
+            None
+        };
+
+        let size = match in_place {
+            Some(size) => size,
+            None => {
+                let characteristics = section::INITIALIZED_DATA | section::READ;
+                let probe = builder.build(0)?;
+                let header =
+                    self.build_section_header(".edata", probe.len() as u32, characteristics)?;
+                let rva = header.virtual_address.value;
+                let data = builder.build(rva)?;
+                let size = data.len();
+                self.append_section_raw(header, data)?;
+                self.sync_data_directory_rva(header::EXPORT, rva)?;
+                size
+            }
+        };
+        self.sync_data_directory_size(header::EXPORT, size as u32)?;
+
+        let checksum = self.calc_checksum();
+        self.optional_header
+            .checksum
+            .update(&mut self.buffer, checksum)?;
+        Ok(())
+    }
+}
@@ -29,6 +29,16 @@ pub enum ImageBase {
     Base64(u64),
 }
 
+impl ImageBase {
+    /// Returns the address widened to `u64` regardless of its on-disk width.
+    pub fn value(self) -> u64 {
+        match self {
+            ImageBase::Base32(value) => value as u64,
+            ImageBase::Base64(value) => value,
+        }
+    }
+}
+
 /// Stack/heap reserve or commit size (4 bytes in PE32, 8 bytes in PE32+).
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum SizedU64 {
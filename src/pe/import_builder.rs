@@ -0,0 +1,324 @@
+//! Import directory rebuilding.
+//!
+//! [`ImportBuilder`] holds one [`ImportedDll`] per descriptor. [`ImportBuilder::build`] writes the
+//! descriptor array, then every ILT, then the IATs of DLLs without an existing `FirstThunk`, and
+//! finally the DLL names and 2-byte aligned `IMAGE_IMPORT_BY_NAME` entries. Descriptors copied
+//! from an image keep their original `FirstThunk` so code referencing the IAT stays valid.
+
+use crate::errors::FileParseError;
+use crate::pe::header::{self, PEType};
+use crate::pe::import::{DllImport, ImageImportDescriptor, ImportDirectory, ImportEntry};
+use crate::pe::{section, PE};
+
+/// One imported symbol.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImportSymbol {
+    /// Import by name with an export name table hint.
+    Name { hint: u16, name: String },
+    /// Import by ordinal.
+    Ordinal(u16),
+}
+
+impl From<&ImportEntry> for ImportSymbol {
+    fn from(entry: &ImportEntry) -> Self {
+        match entry {
+            ImportEntry::Ordinal { ordinal, .. } => ImportSymbol::Ordinal(*ordinal),
+            ImportEntry::ByName { by_name, .. } => ImportSymbol::Name {
+                hint: by_name.hint.value,
+                name: by_name.name.clone(),
+            },
+        }
+    }
+}
+
+/// Imports from one DLL as written to a single descriptor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportedDll {
+    /// DLL name (e.g. `KERNEL32.dll`).
+    pub name: String,
+    /// Symbols in IAT order.
+    pub symbols: Vec<ImportSymbol>,
+    /// Existing IAT RVA to keep; `None` allocates a new IAT in the built blob.
+    pub first_thunk: Option<u32>,
+    /// Descriptor `TimeDateStamp`.
+    pub time_date_stamp: u32,
+    /// Descriptor `ForwarderChain`.
+    pub forwarder_chain: u32,
+}
+
+/// Editable import directory model.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImportBuilder {
+    /// Descriptors in on-disk order.
+    pub dlls: Vec<ImportedDll>,
+}
+
+/// Serialized import directory and where its parts landed.
+pub struct BuiltImports {
+    /// Blob to place at the RVA passed to [`ImportBuilder::build`].
+    pub data: Vec<u8>,
+    /// Size of the descriptor array including the null terminator.
+    pub directory_size: u32,
+    /// `FirstThunk` RVA per DLL, in [`ImportBuilder::dlls`] order.
+    pub first_thunks: Vec<u32>,
+    /// RVA and size of the newly allocated IATs, `None` when every DLL kept its IAT.
+    pub new_iat: Option<(u32, u32)>,
+}
+
+impl ImportedDll {
+    /// Creates a descriptor with a new IAT importing `symbols` by name.
+    pub fn new(name: &str, symbols: &[&str]) -> Self {
+        ImportedDll {
+            name: name.to_string(),
+            symbols: symbols
+                .iter()
+                .map(|name| ImportSymbol::Name {
+                    hint: 0,
+                    name: name.to_string(),
+                })
+                .collect(),
+            first_thunk: None,
+            time_date_stamp: 0,
+            forwarder_chain: 0,
+        }
+    }
+}
+
+impl ImportBuilder {
+    /// Copies every descriptor of a parsed import directory, keeping its IAT RVA.
+    pub fn from_directory(imports: &ImportDirectory) -> Self {
+        let dlls = imports
+            .dlls
+            .iter()
+            .map(|dll| ImportedDll {
+                name: dll.dll_name.clone(),
+                symbols: dll.entries.iter().map(ImportSymbol::from).collect(),
+                first_thunk: Some(dll.descriptor.first_thunk.value),
+                time_date_stamp: dll.descriptor.time_date_stamp.value,
+                forwarder_chain: dll.descriptor.forwarder_chain.value,
+            })
+            .collect();
+        ImportBuilder { dlls }
+    }
+
+    /// Serializes the directory for placement at `rva`.
+    pub fn build(&self, rva: u32, pe_type: PEType) -> Result<BuiltImports, FileParseError> {
+        let thunk_size = match pe_type {
+            PEType::PE32 => 4,
+            PEType::PE32Plus => 8,
+        };
+        let ordinal_flag = match pe_type {
+            PEType::PE32 => 1u64 << 31,
+            PEType::PE32Plus => 1u64 << 63,
+        };
+        let table_len = |dll: &ImportedDll| (dll.symbols.len() + 1) * thunk_size;
+
+        let directory_size = (self.dlls.len() + 1) * ImageImportDescriptor::SIZE;
+        let ilt_start = directory_size.next_multiple_of(thunk_size);
+        let iat_start = ilt_start + self.dlls.iter().map(table_len).sum::<usize>();
+        let strings_start = iat_start
+            + self
+                .dlls
+                .iter()
+                .filter(|dll| dll.first_thunk.is_none())
+                .map(table_len)
+                .sum::<usize>();
+
+        let mut data = vec![0u8; strings_start];
+        let to_rva = |offset: usize| -> Result<u32, FileParseError> {
+            u32::try_from(offset)
+                .ok()
+                .and_then(|offset| rva.checked_add(offset))
+                .ok_or(FileParseError::ValueTooLarge)
+        };
+
+        let mut first_thunks = Vec::with_capacity(self.dlls.len());
+        let mut ilt = ilt_start;
+        let mut iat = iat_start;
+        for (index, dll) in self.dlls.iter().enumerate() {
+            let name_offset = data.len();
+            data.extend_from_slice(dll.name.as_bytes());
+            data.push(0);
+
+            let first_thunk = match dll.first_thunk {
+                Some(first_thunk) => first_thunk,
+                None => {
+                    let first_thunk = to_rva(iat)?;
+                    iat += table_len(dll);
+                    first_thunk
+                }
+            };
+            let new_iat = dll
+                .first_thunk
+                .is_none()
+                .then(|| (first_thunk - rva) as usize);
+
+            for (slot, symbol) in dll.symbols.iter().enumerate() {
+                let value = match symbol {
+                    ImportSymbol::Ordinal(ordinal) => ordinal_flag | *ordinal as u64,
+                    ImportSymbol::Name { hint, name } => {
+                        if data.len() % 2 != 0 {
+                            data.push(0);
+                        }
+                        let by_name = to_rva(data.len())? as u64;
+                        data.extend_from_slice(&hint.to_le_bytes());
+                        data.extend_from_slice(name.as_bytes());
+                        data.push(0);
+                        by_name
+                    }
+                };
+                let slots = std::iter::once(ilt).chain(new_iat);
+                for table in slots {
+                    let at = table + slot * thunk_size;
+                    data[at..at + thunk_size].copy_from_slice(&value.to_le_bytes()[..thunk_size]);
+                }
+            }
+
+            let descriptor = index * ImageImportDescriptor::SIZE;
+            let fields = [
+                to_rva(ilt)?,
+                dll.time_date_stamp,
+                dll.forwarder_chain,
+                to_rva(name_offset)?,
+                first_thunk,
+            ];
+            for (i, value) in fields.iter().enumerate() {
+                data[descriptor + i * 4..descriptor + i * 4 + 4]
+                    .copy_from_slice(&value.to_le_bytes());
+            }
+
+            first_thunks.push(first_thunk);
+            ilt += table_len(dll);
+        }
+
+        let new_iat = (iat > iat_start).then(|| (rva + iat_start as u32, (iat - iat_start) as u32));
+        Ok(BuiltImports {
+            data,
+            directory_size: directory_size as u32,
+            first_thunks,
+            new_iat,
+        })
+    }
+}
+
+impl PE {
+    /// Adds `symbols` imported by name from `dll`, rebuilding the import directory in a new
+    /// `.idata2` section.
+    ///
+    /// Existing descriptors, lookup tables and strings are copied; their IATs stay at the original
+    /// RVAs. Symbols already imported from `dll` are not duplicated, the rest go to a new
+    /// descriptor with its own IAT. The `IAT` directory is pointed at the new IAT only when it
+    /// was empty, since it must stay a single range. Returns the IAT slot RVA of each symbol.
+    pub fn add_import(&mut self, dll: &str, symbols: &[&str]) -> Result<Vec<u32>, FileParseError> {
+        if !self.optional_header.has_data_directory(header::IAT) {
+            return Err(FileParseError::UnsupportedFeature(
+                "image has no IAT data directory slot".to_string(),
+            ));
+        }
+        let pe_type = self.optional_header.pe_type()?;
+        let thunk_size = match pe_type {
+            PEType::PE32 => 4,
+            PEType::PE32Plus => 8,
+        };
+        let mut builder = ImportBuilder::from_directory(&self.imports()?);
+
+        let existing_slot = |builder: &ImportBuilder, symbol: &str| {
+            builder.dlls.iter().find_map(|entry| {
+                let first_thunk = entry.first_thunk?;
+                if !entry.name.eq_ignore_ascii_case(dll) {
+                    return None;
+                }
+                entry.symbols.iter().position(|candidate| {
+                    matches!(candidate, ImportSymbol::Name { name, .. } if name == symbol)
+                })
+                .map(|slot| first_thunk + (slot * thunk_size) as u32)
+            })
+        };
+        let mut missing: Vec<&str> = Vec::new();
+        for &symbol in symbols {
+            if existing_slot(&builder, symbol).is_none() && !missing.contains(&symbol) {
+                missing.push(symbol);
+            }
+        }
+        if missing.is_empty() {
+            return symbols
+                .iter()
+                .map(|symbol| {
+                    existing_slot(&builder, symbol).ok_or(FileParseError::InvalidFileFormat)
+                })
+                .collect();
+        }
+        builder.dlls.push(ImportedDll::new(dll, &missing));
+        let built = self.set_imports(&builder)?;
+
+        let new_first_thunk = *built.first_thunks.last().expect("descriptor just pushed");
+        symbols
+            .iter()
+            .map(
+                |symbol| match missing.iter().position(|name| name == symbol) {
+                    Some(slot) => Ok(new_first_thunk + (slot * thunk_size) as u32),
+                    None => {
+                        existing_slot(&builder, symbol).ok_or(FileParseError::InvalidFileFormat)
+                    }
+                },
+            )
+            .collect()
+    }
+
+    /// Writes `builder` as the import directory in a new `.idata2` section.
+    ///
+    /// The `IAT` directory is pointed at the newly allocated IATs only when it was empty, since it
+    /// must stay a single range.
+    pub fn set_imports(&mut self, builder: &ImportBuilder) -> Result<BuiltImports, FileParseError> {
+        let pe_type = self.optional_header.pe_type()?;
+        let characteristics = section::INITIALIZED_DATA | section::READ | section::WRITE;
+        let probe = builder.build(0, pe_type)?;
+        let header =
+            self.build_section_header(".idata2", probe.data.len() as u32, characteristics)?;
+        let rva = header.virtual_address.value;
+        let built = builder.build(rva, pe_type)?;
+        self.append_section_raw(header, built.data.clone())?;
+        self.sync_data_directory_rva(header::IMPORT, rva)?;
+        self.sync_data_directory_size(header::IMPORT, built.directory_size)?;
+        if self.optional_header.data_directories[header::IAT]
+            .virtual_address
+            .value
+            == 0
+        {
+            if let Some((iat_rva, iat_size)) = built.new_iat {
+                self.sync_data_directory_rva(header::IAT, iat_rva)?;
+                self.sync_data_directory_size(header::IAT, iat_size)?;
+            }
+        }
+
+        let checksum = self.calc_checksum();
+        self.optional_header
+            .checksum
+            .update(&mut self.buffer, checksum)?;
+        Ok(built)
+    }
+
+    /// Resets each IAT slot of `dll` to its lookup table thunk, as a linker emits an unbound
+    /// image.
+    pub(crate) fn restore_iat_from_lookup(
+        &mut self,
+        dll: &DllImport,
+    ) -> Result<(), FileParseError> {
+        let thunk_size = match self.optional_header.pe_type()? {
+            PEType::PE32 => 4,
+            PEType::PE32Plus => 8,
+        };
+        let first_thunk = dll.descriptor.first_thunk.value;
+        for (index, entry) in dll.entries.iter().enumerate() {
+            let thunk = match entry {
+                ImportEntry::Ordinal { thunk, .. } | ImportEntry::ByName { thunk, .. } => thunk,
+            };
+            let offset = self.rva_to_offset(first_thunk + (index * thunk_size) as u32)?;
+            self.buffer
+                .get_mut(offset..offset + thunk_size)
+                .ok_or(FileParseError::BufferOverflow)?
+                .copy_from_slice(&thunk.raw_value().to_le_bytes()[..thunk_size]);
+        }
+        Ok(())
+    }
+}
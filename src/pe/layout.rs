@@ -1,9 +1,19 @@
-//! Layout helpers for PE structural edits (section removal, header growth, sync).
+//! Layout helpers for PE structural edits (section removal, resizing, header growth, sync).
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::Range;
 
 use crate::errors::FileParseError;
+use crate::pe::exception::ExceptionTable;
 use crate::pe::header;
-use crate::pe::section::PeSection;
+use crate::pe::import::{self, ImportEntry};
+use crate::pe::relocation::{
+    BaseRelocationBuilder, IMAGE_REL_BASED_ABSOLUTE, IMAGE_REL_BASED_DIR64, IMAGE_REL_BASED_HIGHLOW,
+};
+use crate::pe::resource::{ResourceDirectoryNode, ResourceEntry};
+use crate::pe::section::{self, PeSection};
 use crate::pe::PE;
+use crate::utils::{extract_u32, extract_u64};
 
 fn align_up(value: u32, alignment: u32) -> u32 {
     if alignment == 0 {
@@ -150,6 +160,500 @@ impl PE {
         Ok(())
     }
 
+    /// Resizes section `index` in place, shifting later raw data and, when the aligned virtual
+    /// span changes, the RVAs of every later section.
+    ///
+    /// `new_raw_size` is rounded up to `FileAlignment`; a `new_virtual_size` of zero falls back
+    /// to the raw size. File offsets past the section (raw data, certificate table, COFF symbol
+    /// table, debug data) are always adjusted. When RVAs move, data directories, the entry
+    /// point, base relocations and the pointers they patch, imports, exports, delay-load
+    /// descriptors, debug entries, resource data entries, `.pdata` / unwind chains and load
+    /// config guard / SafeSEH tables are rewritten; the TLS and load config VAs are patched
+    /// directly as well, so images without base relocations keep them. Structures that cannot
+    /// be rewritten (CLR metadata, DVRT, CHPE metadata, language-specific exception handler data,
+    /// VA-based delay-load descriptors, unusual base relocation types) and, when shrinking, any
+    /// of the above that lives in or points into the dropped bytes or virtual span are listed in
+    /// an `UnsupportedFeature` error before anything is changed. Position-dependent code
+    /// operands such as x64 RIP-relative displacements are not rewritten.
+    pub fn resize_section(
+        &mut self,
+        index: usize,
+        new_raw_size: u32,
+        new_virtual_size: u32,
+    ) -> Result<(), FileParseError> {
+        let section = self
+            .sections
+            .get(index)
+            .ok_or(FileParseError::BufferOverflow)?;
+        let file_align = self.optional_header.file_alignment.value;
+        let section_align = self.optional_header.section_alignment.value;
+
+        let new_raw = align_up(new_raw_size, file_align);
+        let old_raw = section.size_of_raw_data.value;
+        let raw_start = section.pointer_to_raw_data.value;
+        if raw_start == 0 && new_raw > 0 {
+            return Err(FileParseError::UnsupportedFeature(
+                "growing a section without raw data".to_string(),
+            ));
+        }
+        let raw_end = raw_start
+            .checked_add(old_raw)
+            .ok_or(FileParseError::BufferOverflow)?;
+        if raw_end as usize > self.buffer.len() {
+            return Err(FileParseError::BufferOverflow);
+        }
+        let effective =
+            |virtual_size: u32, raw: u32| if virtual_size == 0 { raw } else { virtual_size };
+        let new_virtual = effective(new_virtual_size, new_raw);
+        let va = section.virtual_address.value;
+        let old_end = va
+            + align_up(
+                effective(section.virtual_size.value, old_raw),
+                section_align,
+            );
+        let new_end = va
+            .checked_add(align_up(new_virtual, section_align))
+            .ok_or(FileParseError::ValueTooLarge)?;
+        let rva_delta = new_end as i64 - old_end as i64;
+        let raw_delta = new_raw as i64 - old_raw as i64;
+
+        let mut later = false;
+        for (i, other) in self.sections.iter().enumerate() {
+            let other_va = other.virtual_address.value;
+            if i != index && other_va >= va {
+                if other_va < old_end {
+                    return Err(FileParseError::InvalidFileFormat);
+                }
+                later = true;
+            }
+        }
+        let shift_rvas = later && rva_delta != 0;
+        let new_raw_end = (raw_start + new_raw) as usize;
+        let removed = RemovedSpan {
+            file: new_raw_end..raw_end as usize,
+            rvas: [va + new_raw..(va + old_raw).min(old_end), new_end..old_end],
+            owner: section.pointer_to_raw_data.offset,
+        };
+
+        let fixups = self.collect_layout_fixups(shift_rvas, &removed)?;
+        if !fixups.blockers.is_empty() {
+            return Err(FileParseError::UnsupportedFeature(format!(
+                "resize_section cannot relocate: {}",
+                fixups.blockers.join(", ")
+            )));
+        }
+        let relocations = if shift_rvas && !self.base_relocations.is_empty() {
+            let mut builder = BaseRelocationBuilder::new();
+            for (rva, kind) in self.base_relocation_builder()?.entries {
+                builder.add(shift_rva(rva, old_end, u32::MAX, rva_delta), kind)?;
+            }
+            Some(builder)
+        } else {
+            None
+        };
+        let image_end = self.optional_header.size_of_image.value;
+        let image_base = self.optional_header.image_base.value.value();
+
+        if raw_delta > 0 {
+            self.buffer.splice(
+                raw_end as usize..raw_end as usize,
+                std::iter::repeat_n(0u8, raw_delta as usize),
+            );
+        } else {
+            self.buffer.drain(new_raw_end..raw_end as usize);
+        }
+        let moved = |offset: usize| -> Option<usize> {
+            if offset >= raw_end as usize {
+                Some((offset as i64 + raw_delta) as usize)
+            } else if offset >= new_raw_end && raw_delta < 0 {
+                None
+            } else {
+                Some(offset)
+            }
+        };
+
+        for (&offset, &width) in &fixups.pointers {
+            let Some(offset) = moved(offset) else {
+                continue;
+            };
+            let value = match width {
+                4 => extract_u32(&self.buffer, offset)? as u64,
+                _ => extract_u64(&self.buffer, offset)?,
+            };
+            let Some(rva) = value
+                .checked_sub(image_base)
+                .and_then(|rva| u32::try_from(rva).ok())
+            else {
+                continue;
+            };
+            if rva >= old_end && rva < image_end {
+                let patched = image_base + shift_rva(rva, old_end, image_end, rva_delta) as u64;
+                match width {
+                    4 => write_u32(&mut self.buffer, offset, patched as u32)?,
+                    _ => write_u64(&mut self.buffer, offset, patched)?,
+                }
+            }
+        }
+        if shift_rvas {
+            for &offset in &fixups.rvas {
+                let Some(offset) = moved(offset) else {
+                    continue;
+                };
+                let value = extract_u32(&self.buffer, offset)?;
+                write_u32(
+                    &mut self.buffer,
+                    offset,
+                    shift_rva(value, old_end, image_end, rva_delta),
+                )?;
+            }
+        }
+        for &offset in &fixups.file_offsets {
+            let Some(offset) = moved(offset) else {
+                continue;
+            };
+            let value = extract_u32(&self.buffer, offset)?;
+            if value != 0 && value >= raw_end {
+                write_u32(&mut self.buffer, offset, (value as i64 + raw_delta) as u32)?;
+            }
+        }
+
+        let section = &self.sections[index];
+        write_u32(&mut self.buffer, section.size_of_raw_data.offset, new_raw)?;
+        write_u32(&mut self.buffer, section.virtual_size.offset, new_virtual)?;
+        let size_field = if section.characteristics.value & section::CODE != 0 {
+            Some(&self.optional_header.size_of_code)
+        } else if section.characteristics.value & section::INITIALIZED_DATA != 0 {
+            Some(&self.optional_header.size_of_initialized_data)
+        } else {
+            None
+        };
+        if let Some(size) = size_field {
+            let value = (size.value as i64 + raw_delta).max(0) as u32;
+            write_u32(&mut self.buffer, size.offset, value)?;
+        }
+
+        let buffer = std::mem::take(&mut self.buffer);
+        *self = PE::from_buffer(buffer)?;
+        if let Some(builder) = relocations {
+            self.set_base_relocations(&builder)?;
+        }
+        self.sync_layout()
+    }
+
+    /// Collects every on-disk slot holding an RVA, a relocated pointer or a file offset, and
+    /// blocks on any of them that `removed` would drop or leave dangling.
+    fn collect_layout_fixups(
+        &self,
+        shift_rvas: bool,
+        removed: &RemovedSpan,
+    ) -> Result<LayoutFixups, FileParseError> {
+        let mut fixups = LayoutFixups::default();
+
+        let security = &self.optional_header.data_directories[header::SECURITY];
+        if self.optional_header.has_data_directory(header::SECURITY) {
+            fixups.file_offsets.insert(security.virtual_address.offset);
+        }
+        fixups
+            .file_offsets
+            .insert(self.coff_header.pointer_to_symbol_table.offset);
+        for section in &self.sections {
+            fixups
+                .file_offsets
+                .insert(section.pointer_to_raw_data.offset);
+            fixups
+                .file_offsets
+                .insert(section.pointer_to_relocations.offset);
+            fixups
+                .file_offsets
+                .insert(section.pointer_to_linenumbers.offset);
+        }
+        if let Some(debug) = self.debug_directory()? {
+            for entry in &debug.entries {
+                fixups.file_offsets.insert(entry.pointer_to_raw_data.offset);
+                fixups.rvas.insert(entry.address_of_raw_data.offset);
+            }
+        }
+        if !shift_rvas && removed.is_empty() {
+            return Ok(fixups);
+        }
+
+        for (dir_index, entry) in self.optional_header.data_directories
+            [..self.optional_header.active_data_directory_count()]
+            .iter()
+            .enumerate()
+        {
+            if dir_index != header::SECURITY {
+                fixups.rvas.insert(entry.virtual_address.offset);
+            }
+        }
+        fixups.rvas.insert(self.optional_header.entry_point.offset);
+        fixups.rvas.insert(self.optional_header.base_of_code.offset);
+        if let Some(base_of_data) = &self.optional_header.base_of_data {
+            fixups.rvas.insert(base_of_data.offset);
+        }
+        for section in &self.sections {
+            fixups.rvas.insert(section.virtual_address.offset);
+        }
+
+        for block in &self.base_relocations {
+            for entry in &block.entries {
+                let slot = self.rva_to_offset(entry.rva(block.page_rva.value));
+                match entry.relocation_type() {
+                    IMAGE_REL_BASED_ABSOLUTE => {}
+                    IMAGE_REL_BASED_HIGHLOW => {
+                        fixups.pointers.insert(slot?, 4);
+                    }
+                    IMAGE_REL_BASED_DIR64 => {
+                        fixups.pointers.insert(slot?, 8);
+                    }
+                    kind => fixups.block(format!("base relocations of type {kind}")),
+                }
+            }
+        }
+        if let Some(tls) = self.tls()? {
+            for field in [
+                &tls.start_address_of_raw_data,
+                &tls.end_address_of_raw_data,
+                &tls.address_of_index,
+                &tls.address_of_callbacks,
+            ] {
+                fixups.pointers.insert(field.offset, field.size);
+            }
+            for callback in self.tls_callbacks()? {
+                fixups.pointers.insert(callback.offset, callback.size);
+            }
+        }
+
+        let pe_type = self.optional_header.pe_type()?;
+        let imports = self.imports()?;
+        for dll in &imports.dlls {
+            let descriptor = &dll.descriptor;
+            fixups.rvas.insert(descriptor.original_first_thunk.offset);
+            fixups.rvas.insert(descriptor.name.offset);
+            fixups.rvas.insert(descriptor.first_thunk.offset);
+            fixups.add_thunks(&dll.entries);
+            if descriptor.original_first_thunk.value != 0 && descriptor.time_date_stamp.value == 0 {
+                let iat = import::parse_thunk_table(
+                    &self.buffer,
+                    &self.sections,
+                    descriptor.first_thunk.value,
+                    pe_type,
+                )?;
+                fixups.add_thunks(&iat);
+            }
+        }
+
+        if let Some(delay) = self.delay_imports()? {
+            for dll in &delay.dlls {
+                let descriptor = &dll.descriptor;
+                if descriptor.attributes.value & 1 == 0 {
+                    fixups.block("VA-based delay-load descriptors".to_string());
+                    continue;
+                }
+                for field in [
+                    &descriptor.dll_name_rva,
+                    &descriptor.module_handle_rva,
+                    &descriptor.delay_import_address_table_rva,
+                    &descriptor.delay_import_name_table_rva,
+                    &descriptor.bound_delay_import_table_rva,
+                    &descriptor.unload_delay_import_table_rva,
+                ] {
+                    fixups.rvas.insert(field.offset);
+                }
+                fixups.add_thunks(&dll.entries);
+            }
+        }
+
+        if let Some(exports) = self.exports()? {
+            let directory = &exports.directory;
+            for field in [
+                &directory.name,
+                &directory.address_of_functions,
+                &directory.address_of_names,
+                &directory.address_of_name_ordinals,
+            ] {
+                fixups.rvas.insert(field.offset);
+            }
+            for function in &exports.functions {
+                let (crate::pe::export::FunctionExport::Local { function_rva, .. }
+                | crate::pe::export::FunctionExport::Forwarder { function_rva, .. }) = function;
+                fixups.rvas.insert(function_rva.offset);
+            }
+            for named in &exports.named {
+                fixups.rvas.insert(named.name_rva.offset);
+            }
+        }
+
+        if let Some(tree) = self.resources()? {
+            fixups.add_resource_node(&tree.root);
+        }
+
+        match self.exception_table()? {
+            Some(ExceptionTable::X64(directory)) => {
+                for function in &directory.entries {
+                    fixups.rvas.insert(function.begin_address.offset);
+                    fixups.rvas.insert(function.end_address.offset);
+                    fixups.rvas.insert(function.unwind_data.offset);
+                    if function.unwind_data.value & 1 != 0 {
+                        continue;
+                    }
+                    let info = self.unwind_info(function)?;
+                    if info.exception_handler.is_some() {
+                        fixups.block("language-specific exception handler data".to_string());
+                    }
+                    if let Some(chained) = &info.chained {
+                        fixups.rvas.insert(chained.begin_address.offset);
+                        fixups.rvas.insert(chained.end_address.offset);
+                        fixups.rvas.insert(chained.unwind_data.offset);
+                    }
+                }
+            }
+            Some(ExceptionTable::Arm64(directory)) => {
+                for function in &directory.entries {
+                    fixups.rvas.insert(function.begin_address.offset);
+                    if let Some(xdata) = self.arm64_xdata(function)? {
+                        fixups.rvas.insert(function.unwind_data.offset);
+                        if xdata.exception_handler.is_some() {
+                            fixups.block("language-specific exception handler data".to_string());
+                        }
+                    }
+                }
+            }
+            None => {}
+        }
+
+        if let Some(config) = self.load_config()? {
+            let present = |field: &Option<crate::field::Field<u64>>| {
+                field.as_ref().is_some_and(|field| field.value != 0)
+            };
+            if present(&config.dynamic_value_reloc_table)
+                || config
+                    .dynamic_value_reloc_table_offset
+                    .as_ref()
+                    .is_some_and(|field| field.value != 0)
+            {
+                fixups.block("dynamic value relocation table".to_string());
+            }
+            if present(&config.chpe_metadata_pointer) {
+                fixups.block("CHPE metadata".to_string());
+            }
+            for table in [
+                self.guard_cf_functions()?,
+                self.guard_address_taken_iat_entries()?,
+                self.guard_long_jump_targets()?,
+                self.guard_eh_continuation_targets()?,
+            ] {
+                fixups
+                    .rvas
+                    .extend(table.iter().map(|entry| entry.rva.offset));
+            }
+            fixups
+                .rvas
+                .extend(self.se_handlers()?.iter().map(|handler| handler.offset));
+            let vas = [
+                &config.lock_prefix_table,
+                &config.reserved2,
+                &config.security_cookie,
+            ]
+            .into_iter()
+            .chain(
+                [
+                    &config.se_handler_table,
+                    &config.guard_cf_check_function_pointer,
+                    &config.guard_cf_dispatch_function_pointer,
+                    &config.guard_cf_function_table,
+                    &config.guard_address_taken_iat_entry_table,
+                    &config.guard_long_jump_target_table,
+                    &config.guard_rf_failure_routine,
+                    &config.guard_rf_failure_routine_function_pointer,
+                    &config.guard_rf_verify_stack_pointer_function_pointer,
+                    &config.enclave_configuration_pointer,
+                    &config.volatile_metadata_pointer,
+                    &config.guard_eh_continuation_table,
+                    &config.guard_xfg_check_function_pointer,
+                    &config.guard_xfg_dispatch_function_pointer,
+                    &config.guard_xfg_table_dispatch_function_pointer,
+                    &config.cast_guard_os_determined_failure_mode,
+                    &config.guard_memcpy_function_pointer,
+                ]
+                .into_iter()
+                .flatten(),
+            );
+            for field in vas {
+                fixups.pointers.insert(field.offset, field.size);
+            }
+        }
+
+        if self
+            .optional_header
+            .has_data_directory(header::COM_DESCRIPTOR)
+            && self.optional_header.data_directories[header::COM_DESCRIPTOR]
+                .virtual_address
+                .value
+                != 0
+        {
+            fixups.block("CLR header and metadata".to_string());
+        }
+
+        if !removed.is_empty() {
+            self.block_removed(&mut fixups, removed)?;
+        }
+        Ok(fixups)
+    }
+
+    /// Blocks on collected slots that lie in `removed` bytes or point into the removed span.
+    fn block_removed(
+        &self,
+        fixups: &mut LayoutFixups,
+        removed: &RemovedSpan,
+    ) -> Result<(), FileParseError> {
+        let image_base = self.optional_header.image_base.value.value();
+        let mut lost = Vec::new();
+        for (dir_index, entry) in self.optional_header.data_directories
+            [..self.optional_header.active_data_directory_count()]
+            .iter()
+            .enumerate()
+        {
+            let (rva, size) = (entry.virtual_address.value, entry.size.value);
+            if dir_index != header::SECURITY
+                && rva != 0
+                && removed.overlaps(rva..rva.saturating_add(size.max(1)))
+            {
+                lost.push(format!("data directory {dir_index}"));
+            }
+        }
+        for &offset in &fixups.rvas {
+            let value = extract_u32(&self.buffer, offset)?;
+            if value != 0 && (removed.file.contains(&offset) || removed.holds(value)) {
+                lost.push(format!("RVA {value:#x} at file offset {offset:#x}"));
+            }
+        }
+        for (&offset, &width) in &fixups.pointers {
+            let value = match width {
+                4 => extract_u32(&self.buffer, offset)? as u64,
+                _ => extract_u64(&self.buffer, offset)?,
+            };
+            let target = value
+                .checked_sub(image_base)
+                .and_then(|rva| u32::try_from(rva).ok());
+            if value != 0
+                && (removed.file.contains(&offset) || target.is_some_and(|rva| removed.holds(rva)))
+            {
+                lost.push(format!("pointer {value:#x} at file offset {offset:#x}"));
+            }
+        }
+        for &offset in &fixups.file_offsets {
+            let value = extract_u32(&self.buffer, offset)? as usize;
+            if offset != removed.owner && value != 0 && removed.file.contains(&value) {
+                lost.push(format!("file offset {value:#x} at file offset {offset:#x}"));
+            }
+        }
+        for what in lost {
+            fixups.block(format!("{what} refers to the removed range"));
+        }
+        Ok(())
+    }
+
     /// Grows the optional header by `extra_bytes`, shifting the section table and raw data.
     pub fn grow_optional_header(&mut self, extra_bytes: u16) -> Result<(), FileParseError> {
         if extra_bytes == 0 {
@@ -246,6 +750,96 @@ impl PE {
     }
 }
 
+/// On-disk slots rewritten by [`PE::resize_section`], keyed by absolute file offset.
+#[derive(Default)]
+struct LayoutFixups {
+    /// `u32` RVAs.
+    rvas: BTreeSet<usize>,
+    /// Base-relocated pointers (VAs) and their width.
+    pointers: BTreeMap<usize, usize>,
+    /// `u32` file offsets.
+    file_offsets: BTreeSet<usize>,
+    /// Structures holding RVAs that cannot be rewritten.
+    blockers: Vec<String>,
+}
+
+impl LayoutFixups {
+    fn block(&mut self, what: String) {
+        if !self.blockers.contains(&what) {
+            self.blockers.push(what);
+        }
+    }
+
+    /// Adds by-name thunk slots (their values are `IMAGE_IMPORT_BY_NAME` RVAs).
+    fn add_thunks(&mut self, entries: &[ImportEntry]) {
+        for entry in entries {
+            if let ImportEntry::ByName { thunk, .. } = entry {
+                if thunk.raw_value() >> 32 == 0 {
+                    self.rvas.insert(thunk.offset());
+                }
+            }
+        }
+    }
+
+    fn add_resource_node(&mut self, node: &ResourceDirectoryNode) {
+        for entry in &node.entries {
+            match entry {
+                ResourceEntry::Directory { directory, .. } => self.add_resource_node(directory),
+                ResourceEntry::Data { data, .. } => {
+                    self.rvas.insert(data.offset_to_data.offset);
+                }
+            }
+        }
+    }
+}
+
+/// Bytes and RVAs a shrinking [`PE::resize_section`] drops.
+struct RemovedSpan {
+    /// Dropped raw data (absolute file offsets).
+    file: Range<usize>,
+    /// RVAs of the dropped raw data and of the dropped virtual span.
+    rvas: [Range<u32>; 2],
+    /// File offset of the resized section's `PointerToRawData`, which may equal `file.start`.
+    owner: usize,
+}
+
+impl RemovedSpan {
+    fn is_empty(&self) -> bool {
+        self.file.is_empty() && self.rvas.iter().all(Range::is_empty)
+    }
+
+    fn holds(&self, rva: u32) -> bool {
+        self.rvas.iter().any(|range| range.contains(&rva))
+    }
+
+    fn overlaps(&self, span: Range<u32>) -> bool {
+        self.rvas
+            .iter()
+            .any(|range| range.start < span.end && span.start < range.end)
+    }
+}
+
+/// Moves `rva` by `delta` when it lies in `old_end..image_end`.
+fn shift_rva(rva: u32, old_end: u32, image_end: u32, delta: i64) -> u32 {
+    if rva >= old_end && rva < image_end {
+        (rva as i64 + delta) as u32
+    } else {
+        rva
+    }
+}
+
+/// Updates a `u64` field in `buffer` at `offset`.
+fn write_u64(buffer: &mut [u8], offset: usize, value: u64) -> Result<(), FileParseError> {
+    let end = offset
+        .checked_add(8)
+        .ok_or(FileParseError::BufferOverflow)?;
+    buffer
+        .get_mut(offset..end)
+        .ok_or(FileParseError::BufferOverflow)?
+        .copy_from_slice(&value.to_le_bytes());
+    Ok(())
+}
+
 fn shift_section_fields(section: &mut PeSection, delta: i32) {
     let shift = |offset: &mut usize| {
         if delta >= 0 {
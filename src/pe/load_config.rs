@@ -1,11 +1,70 @@
 //! Load configuration directory (`IMAGE_LOAD_CONFIG_DIRECTORY`).
+//!
+//! The structure grew with every Windows release; fields past `SecurityCookie` are only present
+//! when the on-disk `Size` covers them. Pointer-sized fields are 4 bytes in PE32 and 8 bytes in
+//! PE32+ and are exposed as `Field<u64>` with the on-disk width in [`Field::size`].
 
 use crate::errors::FileParseError;
 use crate::field::Field;
 use crate::pe::header::PEType;
+use crate::pe::PE;
 use crate::utils::{extract_u16, extract_u32, extract_u64};
 
-/// Base fields of `IMAGE_LOAD_CONFIG_DIRECTORY32` / `IMAGE_LOAD_CONFIG_DIRECTORY64`.
+/// `IMAGE_GUARD_CF_INSTRUMENTED`: module performs control flow integrity checks.
+pub const IMAGE_GUARD_CF_INSTRUMENTED: u32 = 0x0000_0100;
+/// `IMAGE_GUARD_CFW_INSTRUMENTED`: module performs control flow and write integrity checks.
+pub const IMAGE_GUARD_CFW_INSTRUMENTED: u32 = 0x0000_0200;
+/// `IMAGE_GUARD_CF_FUNCTION_TABLE_PRESENT`: module contains valid control flow target metadata.
+pub const IMAGE_GUARD_CF_FUNCTION_TABLE_PRESENT: u32 = 0x0000_0400;
+/// `IMAGE_GUARD_SECURITY_COOKIE_UNUSED`: module does not use the `/GS` security cookie.
+pub const IMAGE_GUARD_SECURITY_COOKIE_UNUSED: u32 = 0x0000_0800;
+/// `IMAGE_GUARD_PROTECT_DELAYLOAD_IAT`: module supports read-only delay load IAT.
+pub const IMAGE_GUARD_PROTECT_DELAYLOAD_IAT: u32 = 0x0000_1000;
+/// `IMAGE_GUARD_DELAYLOAD_IAT_IN_ITS_OWN_SECTION`: delay load IAT in its own `.didat` section.
+pub const IMAGE_GUARD_DELAYLOAD_IAT_IN_ITS_OWN_SECTION: u32 = 0x0000_2000;
+/// `IMAGE_GUARD_CF_EXPORT_SUPPRESSION_INFO_PRESENT`: module contains suppressed export information.
+pub const IMAGE_GUARD_CF_EXPORT_SUPPRESSION_INFO_PRESENT: u32 = 0x0000_4000;
+/// `IMAGE_GUARD_CF_ENABLE_EXPORT_SUPPRESSION`: module enables suppression of exports.
+pub const IMAGE_GUARD_CF_ENABLE_EXPORT_SUPPRESSION: u32 = 0x0000_8000;
+/// `IMAGE_GUARD_CF_LONGJUMP_TABLE_PRESENT`: module contains `longjmp` target information.
+pub const IMAGE_GUARD_CF_LONGJUMP_TABLE_PRESENT: u32 = 0x0001_0000;
+/// `IMAGE_GUARD_RETPOLINE_PRESENT`: module was built with retpoline support.
+pub const IMAGE_GUARD_RETPOLINE_PRESENT: u32 = 0x0010_0000;
+/// `IMAGE_GUARD_EH_CONTINUATION_TABLE_PRESENT`: module contains EH continuation target information.
+pub const IMAGE_GUARD_EH_CONTINUATION_TABLE_PRESENT: u32 = 0x0040_0000;
+/// `IMAGE_GUARD_XFG_ENABLED`: module was built with eXtended Flow Guard.
+pub const IMAGE_GUARD_XFG_ENABLED: u32 = 0x0080_0000;
+/// `IMAGE_GUARD_CASTGUARD_PRESENT`: module has CastGuard instrumentation.
+pub const IMAGE_GUARD_CASTGUARD_PRESENT: u32 = 0x0100_0000;
+/// `IMAGE_GUARD_MEMCPY_PRESENT`: module has guarded `memcpy` instrumentation.
+pub const IMAGE_GUARD_MEMCPY_PRESENT: u32 = 0x0200_0000;
+/// `IMAGE_GUARD_CF_FUNCTION_TABLE_SIZE_MASK`: metadata bytes per guard table entry.
+pub const IMAGE_GUARD_CF_FUNCTION_TABLE_SIZE_MASK: u32 = 0xF000_0000;
+/// Shift for [`IMAGE_GUARD_CF_FUNCTION_TABLE_SIZE_MASK`].
+pub const IMAGE_GUARD_CF_FUNCTION_TABLE_SIZE_SHIFT: u32 = 28;
+
+/// `IMAGE_GUARD_FLAG_FID_SUPPRESSED`: call target is explicitly suppressed.
+pub const IMAGE_GUARD_FLAG_FID_SUPPRESSED: u8 = 0x01;
+/// `IMAGE_GUARD_FLAG_EXPORT_SUPPRESSED`: call target is export suppressed.
+pub const IMAGE_GUARD_FLAG_EXPORT_SUPPRESSED: u8 = 0x02;
+/// `IMAGE_GUARD_FLAG_FID_LANGEXCPTHANDLER`: call target is a language exception handler.
+pub const IMAGE_GUARD_FLAG_FID_LANGEXCPTHANDLER: u8 = 0x04;
+/// `IMAGE_GUARD_FLAG_FID_XFG`: call target supports XFG.
+pub const IMAGE_GUARD_FLAG_FID_XFG: u8 = 0x08;
+
+/// `IMAGE_LOAD_CONFIG_CODE_INTEGRITY`.
+pub struct CodeIntegrity {
+    /// Flags (`Flags`).
+    pub flags: Field<u16>,
+    /// Catalog index, `0xFFFF` when absent (`Catalog`).
+    pub catalog: Field<u16>,
+    /// Catalog offset (`CatalogOffset`).
+    pub catalog_offset: Field<u32>,
+    /// Reserved (`Reserved`).
+    pub reserved: Field<u32>,
+}
+
+/// `IMAGE_LOAD_CONFIG_DIRECTORY32` / `IMAGE_LOAD_CONFIG_DIRECTORY64`.
 pub struct LoadConfigDirectory {
     /// Size of this structure (`Size`).
     pub size: Field<u32>,
@@ -31,18 +90,142 @@ pub struct LoadConfigDirectory {
     pub maximum_allocation_size: Field<u64>,
     /// Virtual memory threshold (4 or 8 bytes on disk).
     pub virtual_memory_threshold: Field<u64>,
-    /// Process affinity mask (PE32+ only).
+    /// Process affinity mask (4 or 8 bytes on disk; follows `ProcessHeapFlags` in PE32).
     pub process_affinity_mask: Option<Field<u64>>,
     /// Process heap flags.
     pub process_heap_flags: Field<u32>,
     /// CSD version.
     pub csd_version: Field<u16>,
-    /// Reserved fields.
+    /// `DependentLoadFlags` (reserved in older SDKs).
     pub reserved1: Field<u16>,
-    /// Reserved fields.
-    pub reserved2: Field<u32>,
+    /// `EditList` VA (reserved, 4 or 8 bytes on disk).
+    pub reserved2: Field<u64>,
     /// Security cookie VA.
     pub security_cookie: Field<u64>,
+    /// SafeSEH handler table VA (`SEHandlerTable`, PE32 only in practice).
+    pub se_handler_table: Option<Field<u64>>,
+    /// Number of SafeSEH handlers (`SEHandlerCount`).
+    pub se_handler_count: Option<Field<u64>>,
+    /// CFG check-function pointer VA (`GuardCFCheckFunctionPointer`).
+    pub guard_cf_check_function_pointer: Option<Field<u64>>,
+    /// CFG dispatch-function pointer VA (`GuardCFDispatchFunctionPointer`).
+    pub guard_cf_dispatch_function_pointer: Option<Field<u64>>,
+    /// CFG function table VA (`GuardCFFunctionTable`).
+    pub guard_cf_function_table: Option<Field<u64>>,
+    /// CFG function table entry count (`GuardCFFunctionCount`).
+    pub guard_cf_function_count: Option<Field<u64>>,
+    /// `IMAGE_GUARD_*` flags (`GuardFlags`).
+    pub guard_flags: Option<Field<u32>>,
+    /// Code integrity information (`CodeIntegrity`).
+    pub code_integrity: Option<CodeIntegrity>,
+    /// Address-taken IAT entry table VA (`GuardAddressTakenIatEntryTable`).
+    pub guard_address_taken_iat_entry_table: Option<Field<u64>>,
+    /// Address-taken IAT entry count (`GuardAddressTakenIatEntryCount`).
+    pub guard_address_taken_iat_entry_count: Option<Field<u64>>,
+    /// `longjmp` target table VA (`GuardLongJumpTargetTable`).
+    pub guard_long_jump_target_table: Option<Field<u64>>,
+    /// `longjmp` target count (`GuardLongJumpTargetCount`).
+    pub guard_long_jump_target_count: Option<Field<u64>>,
+    /// Dynamic value relocation table VA (`DynamicValueRelocTable`).
+    pub dynamic_value_reloc_table: Option<Field<u64>>,
+    /// CHPE / hybrid metadata VA (`CHPEMetadataPointer`).
+    pub chpe_metadata_pointer: Option<Field<u64>>,
+    /// Return flow guard failure routine VA (`GuardRFFailureRoutine`).
+    pub guard_rf_failure_routine: Option<Field<u64>>,
+    /// Return flow guard failure routine pointer VA (`GuardRFFailureRoutineFunctionPointer`).
+    pub guard_rf_failure_routine_function_pointer: Option<Field<u64>>,
+    /// Offset of the dynamic value relocation table in its section (`DynamicValueRelocTableOffset`).
+    pub dynamic_value_reloc_table_offset: Option<Field<u32>>,
+    /// One-based section index of the dynamic value relocation table (`DynamicValueRelocTableSection`).
+    pub dynamic_value_reloc_table_section: Option<Field<u16>>,
+    /// Return flow guard stack-pointer check pointer VA (`GuardRFVerifyStackPointerFunctionPointer`).
+    pub guard_rf_verify_stack_pointer_function_pointer: Option<Field<u64>>,
+    /// Hot patch table offset (`HotPatchTableOffset`).
+    pub hot_patch_table_offset: Option<Field<u32>>,
+    /// Enclave configuration VA (`EnclaveConfigurationPointer`).
+    pub enclave_configuration_pointer: Option<Field<u64>>,
+    /// Volatile metadata VA (`VolatileMetadataPointer`).
+    pub volatile_metadata_pointer: Option<Field<u64>>,
+    /// EH continuation target table VA (`GuardEHContinuationTable`).
+    pub guard_eh_continuation_table: Option<Field<u64>>,
+    /// EH continuation target count (`GuardEHContinuationCount`).
+    pub guard_eh_continuation_count: Option<Field<u64>>,
+    /// XFG check-function pointer VA (`GuardXFGCheckFunctionPointer`).
+    pub guard_xfg_check_function_pointer: Option<Field<u64>>,
+    /// XFG dispatch-function pointer VA (`GuardXFGDispatchFunctionPointer`).
+    pub guard_xfg_dispatch_function_pointer: Option<Field<u64>>,
+    /// XFG table dispatch-function pointer VA (`GuardXFGTableDispatchFunctionPointer`).
+    pub guard_xfg_table_dispatch_function_pointer: Option<Field<u64>>,
+    /// CastGuard failure mode VA (`CastGuardOsDeterminedFailureMode`).
+    pub cast_guard_os_determined_failure_mode: Option<Field<u64>>,
+    /// Guarded `memcpy` pointer VA (`GuardMemcpyFunctionPointer`).
+    pub guard_memcpy_function_pointer: Option<Field<u64>>,
+}
+
+/// One entry of a CFG target table (function, address-taken IAT, `longjmp`, EH continuation).
+pub struct GuardTableEntry {
+    /// Target RVA.
+    pub rva: Field<u32>,
+    /// First metadata byte (`IMAGE_GUARD_FLAG_*`) when the table stride carries one.
+    pub flags: Option<Field<u8>>,
+}
+
+/// Sequential reader over the load config blob that tracks the PE32 / PE32+ pointer width.
+struct Reader<'a> {
+    buffer: &'a [u8],
+    offset: usize,
+    cursor: usize,
+    size: usize,
+    pointer_size: usize,
+}
+
+impl Reader<'_> {
+    fn u16(&mut self) -> Result<Field<u16>, FileParseError> {
+        let offset = self.offset + self.cursor;
+        self.cursor += 2;
+        Ok(Field::new(extract_u16(self.buffer, offset)?, offset, 2))
+    }
+
+    fn u32(&mut self) -> Result<Field<u32>, FileParseError> {
+        let offset = self.offset + self.cursor;
+        self.cursor += 4;
+        Ok(Field::new(extract_u32(self.buffer, offset)?, offset, 4))
+    }
+
+    fn pointer(&mut self) -> Result<Field<u64>, FileParseError> {
+        let offset = self.offset + self.cursor;
+        let value = match self.pointer_size {
+            4 => extract_u32(self.buffer, offset)? as u64,
+            _ => extract_u64(self.buffer, offset)?,
+        };
+        self.cursor += self.pointer_size;
+        Ok(Field::new(value, offset, self.pointer_size))
+    }
+
+    /// Reads a versioned field only when `Size` covers all `width` bytes of it.
+    fn versioned<T>(
+        &mut self,
+        width: usize,
+        read: impl FnOnce(&mut Self) -> Result<T, FileParseError>,
+    ) -> Result<Option<T>, FileParseError> {
+        if self.cursor + width > self.size {
+            self.cursor += width;
+            return Ok(None);
+        }
+        read(self).map(Some)
+    }
+
+    fn opt_u16(&mut self) -> Result<Option<Field<u16>>, FileParseError> {
+        self.versioned(2, Self::u16)
+    }
+
+    fn opt_u32(&mut self) -> Result<Option<Field<u32>>, FileParseError> {
+        self.versioned(4, Self::u32)
+    }
+
+    fn opt_pointer(&mut self) -> Result<Option<Field<u64>>, FileParseError> {
+        self.versioned(self.pointer_size, Self::pointer)
+    }
 }
 
 impl LoadConfigDirectory {
@@ -56,96 +239,261 @@ impl LoadConfigDirectory {
             return Err(FileParseError::BufferOverflow);
         }
 
-        match pe_type {
+        let mut reader = Reader {
+            buffer,
+            offset,
+            cursor: 4,
+            size: size as usize,
+            pointer_size: match pe_type {
+                PEType::PE32 => 4,
+                PEType::PE32Plus => 8,
+            },
+        };
+        let r = &mut reader;
+
+        let time_date_stamp = r.u32()?;
+        let major_version = r.u16()?;
+        let minor_version = r.u16()?;
+        let global_flags_clear = r.u32()?;
+        let global_flags_set = r.u32()?;
+        let critical_section_default_timeout = r.u32()?;
+        let de_commit_free_block_threshold = r.pointer()?;
+        let de_commit_total_free_threshold = r.pointer()?;
+        let lock_prefix_table = r.pointer()?;
+        let maximum_allocation_size = r.pointer()?;
+        let virtual_memory_threshold = r.pointer()?;
+        let (process_affinity_mask, process_heap_flags) = match pe_type {
             PEType::PE32 => {
-                let read_u64 = |off: usize| -> Result<u64, FileParseError> {
-                    Ok(extract_u32(buffer, off)? as u64)
-                };
-                Ok(LoadConfigDirectory {
-                    size: Field::new(size, offset, 4),
-                    time_date_stamp: Field::new(extract_u32(buffer, offset + 4)?, offset + 4, 4),
-                    major_version: Field::new(extract_u16(buffer, offset + 8)?, offset + 8, 2),
-                    minor_version: Field::new(extract_u16(buffer, offset + 10)?, offset + 10, 2),
-                    global_flags_clear: Field::new(
-                        extract_u32(buffer, offset + 12)?,
-                        offset + 12,
-                        4,
-                    ),
-                    global_flags_set: Field::new(extract_u32(buffer, offset + 16)?, offset + 16, 4),
-                    critical_section_default_timeout: Field::new(
-                        extract_u32(buffer, offset + 20)?,
-                        offset + 20,
-                        4,
-                    ),
-                    de_commit_free_block_threshold: Field::new(
-                        read_u64(offset + 24)?,
-                        offset + 24,
-                        4,
-                    ),
-                    de_commit_total_free_threshold: Field::new(
-                        read_u64(offset + 28)?,
-                        offset + 28,
-                        4,
-                    ),
-                    lock_prefix_table: Field::new(read_u64(offset + 32)?, offset + 32, 4),
-                    maximum_allocation_size: Field::new(read_u64(offset + 36)?, offset + 36, 4),
-                    virtual_memory_threshold: Field::new(read_u64(offset + 40)?, offset + 40, 4),
-                    process_affinity_mask: None,
-                    process_heap_flags: Field::new(
-                        extract_u32(buffer, offset + 44)?,
-                        offset + 44,
-                        4,
-                    ),
-                    csd_version: Field::new(extract_u16(buffer, offset + 48)?, offset + 48, 2),
-                    reserved1: Field::new(extract_u16(buffer, offset + 50)?, offset + 50, 2),
-                    reserved2: Field::new(extract_u32(buffer, offset + 52)?, offset + 52, 4),
-                    security_cookie: Field::new(read_u64(offset + 56)?, offset + 56, 4),
+                let heap_flags = r.u32()?;
+                (r.pointer()?, heap_flags)
+            }
+            PEType::PE32Plus => {
+                let affinity = r.pointer()?;
+                (affinity, r.u32()?)
+            }
+        };
+        let csd_version = r.u16()?;
+        let reserved1 = r.u16()?;
+        let reserved2 = r.pointer()?;
+        let security_cookie = r.pointer()?;
+
+        Ok(LoadConfigDirectory {
+            size: Field::new(size, offset, 4),
+            time_date_stamp,
+            major_version,
+            minor_version,
+            global_flags_clear,
+            global_flags_set,
+            critical_section_default_timeout,
+            de_commit_free_block_threshold,
+            de_commit_total_free_threshold,
+            lock_prefix_table,
+            maximum_allocation_size,
+            virtual_memory_threshold,
+            process_affinity_mask: Some(process_affinity_mask),
+            process_heap_flags,
+            csd_version,
+            reserved1,
+            reserved2,
+            security_cookie,
+            se_handler_table: r.opt_pointer()?,
+            se_handler_count: r.opt_pointer()?,
+            guard_cf_check_function_pointer: r.opt_pointer()?,
+            guard_cf_dispatch_function_pointer: r.opt_pointer()?,
+            guard_cf_function_table: r.opt_pointer()?,
+            guard_cf_function_count: r.opt_pointer()?,
+            guard_flags: r.opt_u32()?,
+            code_integrity: r.versioned(12, |r| {
+                Ok(CodeIntegrity {
+                    flags: r.u16()?,
+                    catalog: r.u16()?,
+                    catalog_offset: r.u32()?,
+                    reserved: r.u32()?,
                 })
+            })?,
+            guard_address_taken_iat_entry_table: r.opt_pointer()?,
+            guard_address_taken_iat_entry_count: r.opt_pointer()?,
+            guard_long_jump_target_table: r.opt_pointer()?,
+            guard_long_jump_target_count: r.opt_pointer()?,
+            dynamic_value_reloc_table: r.opt_pointer()?,
+            chpe_metadata_pointer: r.opt_pointer()?,
+            guard_rf_failure_routine: r.opt_pointer()?,
+            guard_rf_failure_routine_function_pointer: r.opt_pointer()?,
+            dynamic_value_reloc_table_offset: r.opt_u32()?,
+            dynamic_value_reloc_table_section: r.opt_u16()?,
+            guard_rf_verify_stack_pointer_function_pointer: {
+                r.cursor += 2; // Reserved2
+                r.opt_pointer()?
+            },
+            hot_patch_table_offset: r.opt_u32()?,
+            enclave_configuration_pointer: {
+                r.cursor += 4; // Reserved3
+                r.opt_pointer()?
+            },
+            volatile_metadata_pointer: r.opt_pointer()?,
+            guard_eh_continuation_table: r.opt_pointer()?,
+            guard_eh_continuation_count: r.opt_pointer()?,
+            guard_xfg_check_function_pointer: r.opt_pointer()?,
+            guard_xfg_dispatch_function_pointer: r.opt_pointer()?,
+            guard_xfg_table_dispatch_function_pointer: r.opt_pointer()?,
+            cast_guard_os_determined_failure_mode: r.opt_pointer()?,
+            guard_memcpy_function_pointer: r.opt_pointer()?,
+        })
+    }
+
+    /// `GuardFlags`, or `0` when the structure predates Control Flow Guard.
+    pub fn guard_flags(&self) -> u32 {
+        self.guard_flags.as_ref().map_or(0, |flags| flags.value)
+    }
+
+    /// Bytes per guard table entry: a 4-byte RVA plus the metadata size encoded in `GuardFlags`.
+    pub fn guard_table_stride(&self) -> usize {
+        4 + ((self.guard_flags() & IMAGE_GUARD_CF_FUNCTION_TABLE_SIZE_MASK)
+            >> IMAGE_GUARD_CF_FUNCTION_TABLE_SIZE_SHIFT) as usize
+    }
+}
+
+/// Parses `count` guard table entries of `stride` bytes at `offset`.
+pub fn parse_guard_table(
+    buffer: &[u8],
+    offset: usize,
+    count: u64,
+    stride: usize,
+) -> Result<Vec<GuardTableEntry>, FileParseError> {
+    if stride < 4 {
+        return Err(FileParseError::InvalidFileFormat);
+    }
+    let count = usize::try_from(count).map_err(|_| FileParseError::ValueTooLarge)?;
+    let end = count
+        .checked_mul(stride)
+        .and_then(|length| length.checked_add(offset))
+        .ok_or(FileParseError::BufferOverflow)?;
+    if end > buffer.len() {
+        return Err(FileParseError::BufferOverflow);
+    }
+
+    (0..count)
+        .map(|index| {
+            let entry = offset + index * stride;
+            Ok(GuardTableEntry {
+                rva: Field::new(extract_u32(buffer, entry)?, entry, 4),
+                flags: (stride > 4).then(|| Field::new(buffer[entry + 4], entry + 4, 1)),
+            })
+        })
+        .collect()
+}
+
+/// Parses the SafeSEH handler table: `count` handler RVAs at `offset`.
+pub fn parse_se_handler_table(
+    buffer: &[u8],
+    offset: usize,
+    count: u64,
+) -> Result<Vec<Field<u32>>, FileParseError> {
+    let count = usize::try_from(count).map_err(|_| FileParseError::ValueTooLarge)?;
+    let end = count
+        .checked_mul(4)
+        .and_then(|length| length.checked_add(offset))
+        .ok_or(FileParseError::BufferOverflow)?;
+    if end > buffer.len() {
+        return Err(FileParseError::BufferOverflow);
+    }
+    (0..count)
+        .map(|index| {
+            let entry = offset + index * 4;
+            Ok(Field::new(extract_u32(buffer, entry)?, entry, 4))
+        })
+        .collect()
+}
+
+impl PE {
+    /// Control Flow Guard valid call targets (`GuardCFFunctionTable`).
+    pub fn guard_cf_functions(&self) -> Result<Vec<GuardTableEntry>, FileParseError> {
+        self.guard_table(|config| {
+            (
+                config.guard_cf_function_table.as_ref(),
+                config.guard_cf_function_count.as_ref(),
+            )
+        })
+    }
+
+    /// IAT entries whose targets are address-taken (`GuardAddressTakenIatEntryTable`).
+    pub fn guard_address_taken_iat_entries(&self) -> Result<Vec<GuardTableEntry>, FileParseError> {
+        self.guard_table(|config| {
+            (
+                config.guard_address_taken_iat_entry_table.as_ref(),
+                config.guard_address_taken_iat_entry_count.as_ref(),
+            )
+        })
+    }
+
+    /// Valid `longjmp` targets (`GuardLongJumpTargetTable`).
+    pub fn guard_long_jump_targets(&self) -> Result<Vec<GuardTableEntry>, FileParseError> {
+        self.guard_table(|config| {
+            (
+                config.guard_long_jump_target_table.as_ref(),
+                config.guard_long_jump_target_count.as_ref(),
+            )
+        })
+    }
+
+    /// Valid exception-handling continuation targets (`GuardEHContinuationTable`).
+    pub fn guard_eh_continuation_targets(&self) -> Result<Vec<GuardTableEntry>, FileParseError> {
+        self.guard_table(|config| {
+            (
+                config.guard_eh_continuation_table.as_ref(),
+                config.guard_eh_continuation_count.as_ref(),
+            )
+        })
+    }
+
+    /// SafeSEH handler RVAs (`SEHandlerTable`); empty for images without one.
+    pub fn se_handlers(&self) -> Result<Vec<Field<u32>>, FileParseError> {
+        let Some(config) = self.load_config()? else {
+            return Ok(Vec::new());
+        };
+        match self.load_config_table(
+            config.se_handler_table.as_ref(),
+            config.se_handler_count.as_ref(),
+        )? {
+            Some((offset, count)) => parse_se_handler_table(&self.buffer, offset, count),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    fn guard_table(
+        &self,
+        select: impl Fn(&LoadConfigDirectory) -> (Option<&Field<u64>>, Option<&Field<u64>>),
+    ) -> Result<Vec<GuardTableEntry>, FileParseError> {
+        let Some(config) = self.load_config()? else {
+            return Ok(Vec::new());
+        };
+        let (table, count) = select(&config);
+        match self.load_config_table(table, count)? {
+            Some((offset, count)) => {
+                parse_guard_table(&self.buffer, offset, count, config.guard_table_stride())
             }
-            PEType::PE32Plus => Ok(LoadConfigDirectory {
-                size: Field::new(size, offset, 4),
-                time_date_stamp: Field::new(extract_u32(buffer, offset + 4)?, offset + 4, 4),
-                major_version: Field::new(extract_u16(buffer, offset + 8)?, offset + 8, 2),
-                minor_version: Field::new(extract_u16(buffer, offset + 10)?, offset + 10, 2),
-                global_flags_clear: Field::new(extract_u32(buffer, offset + 12)?, offset + 12, 4),
-                global_flags_set: Field::new(extract_u32(buffer, offset + 16)?, offset + 16, 4),
-                critical_section_default_timeout: Field::new(
-                    extract_u32(buffer, offset + 20)?,
-                    offset + 20,
-                    4,
-                ),
-                de_commit_free_block_threshold: Field::new(
-                    extract_u64(buffer, offset + 24)?,
-                    offset + 24,
-                    8,
-                ),
-                de_commit_total_free_threshold: Field::new(
-                    extract_u64(buffer, offset + 32)?,
-                    offset + 32,
-                    8,
-                ),
-                lock_prefix_table: Field::new(extract_u64(buffer, offset + 40)?, offset + 40, 8),
-                maximum_allocation_size: Field::new(
-                    extract_u64(buffer, offset + 48)?,
-                    offset + 48,
-                    8,
-                ),
-                virtual_memory_threshold: Field::new(
-                    extract_u64(buffer, offset + 56)?,
-                    offset + 56,
-                    8,
-                ),
-                process_affinity_mask: Some(Field::new(
-                    extract_u64(buffer, offset + 64)?,
-                    offset + 64,
-                    8,
-                )),
-                process_heap_flags: Field::new(extract_u32(buffer, offset + 72)?, offset + 72, 4),
-                csd_version: Field::new(extract_u16(buffer, offset + 76)?, offset + 76, 2),
-                reserved1: Field::new(extract_u16(buffer, offset + 78)?, offset + 78, 2),
-                reserved2: Field::new(extract_u32(buffer, offset + 80)?, offset + 80, 4),
-                security_cookie: Field::new(extract_u64(buffer, offset + 84)?, offset + 84, 8),
-            }),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Maps a load config table VA / count pair to a file offset, `None` when absent or empty.
+    fn load_config_table(
+        &self,
+        table: Option<&Field<u64>>,
+        count: Option<&Field<u64>>,
+    ) -> Result<Option<(usize, u64)>, FileParseError> {
+        let (Some(table), Some(count)) = (table, count) else {
+            return Ok(None);
+        };
+        if table.value == 0 || count.value == 0 {
+            return Ok(None);
         }
+        let image_base = self.optional_header.image_base.value.value();
+        let rva = table
+            .value
+            .checked_sub(image_base)
+            .and_then(|rva| u32::try_from(rva).ok())
+            .ok_or(FileParseError::InvalidFileFormat)?;
+        Ok(Some((self.rva_to_offset(rva)?, count.value)))
     }
 }
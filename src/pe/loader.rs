@@ -0,0 +1,106 @@
+//! Loader emulation: lays the image out as the Windows loader maps it.
+//!
+//! The mapped buffer is `SizeOfImage` bytes long. Headers sit at RVA 0, every section's raw data
+//! is copied to its `VirtualAddress` (the rest of the virtual span stays zero), base relocations
+//! are applied for the requested base, and IAT slots can be bound through a resolver.
+
+use crate::errors::FileParseError;
+use crate::pe::header::{ImageBase, PEType};
+use crate::pe::import_builder::ImportSymbol;
+use crate::pe::{coff, PE};
+
+/// Caller-supplied import resolver: `(dll name, symbol)` to an absolute address.
+type Resolver<'a> = &'a mut dyn FnMut(&str, &ImportSymbol) -> Option<u64>;
+
+impl PE {
+    /// Maps the image at `base` without touching the IAT.
+    pub fn map_image(&self, base: ImageBase) -> Result<Vec<u8>, FileParseError> {
+        self.map_image_inner(base, None)
+    }
+
+    /// Maps the image at `base` and fills each IAT slot with `resolver(dll, symbol)`.
+    ///
+    /// Slots for which the resolver returns `None` keep their on-disk value.
+    pub fn map_image_with_imports(
+        &self,
+        base: ImageBase,
+        mut resolver: impl FnMut(&str, &ImportSymbol) -> Option<u64>,
+    ) -> Result<Vec<u8>, FileParseError> {
+        self.map_image_inner(base, Some(&mut resolver))
+    }
+
+    fn map_image_inner(
+        &self,
+        base: ImageBase,
+        resolver: Option<Resolver<'_>>,
+    ) -> Result<Vec<u8>, FileParseError> {
+        let pe_type = self.optional_header.pe_type()?;
+        let current = self.optional_header.image_base.value.value();
+        let requested = base.value();
+        if requested != current
+            && self.coff_header.characteristics.value & coff::IMAGE_FILE_RELOCS_STRIPPED != 0
+        {
+            return Err(FileParseError::UnsupportedFeature(
+                "relocations stripped; image must load at its preferred base".to_string(),
+            ));
+        }
+
+        let mut relocated = PE::from_buffer(self.buffer.clone())?;
+        relocated.apply_image_base(base)?;
+
+        let size_of_image = relocated.optional_header.size_of_image.value as usize;
+        let mut image = vec![0u8; size_of_image];
+        let headers = (relocated.optional_header.size_of_headers.value as usize)
+            .min(relocated.buffer.len())
+            .min(size_of_image);
+        image[..headers].copy_from_slice(&relocated.buffer[..headers]);
+
+        for section in &relocated.sections {
+            let virtual_address = section.virtual_address.value as usize;
+            let raw_size = section.size_of_raw_data.value as usize;
+            let length = match section.virtual_size.value as usize {
+                0 => raw_size,
+                virtual_size => raw_size.min(virtual_size),
+            };
+            let start = section.pointer_to_raw_data.value as usize;
+            let length = length
+                .min(relocated.buffer.len().saturating_sub(start))
+                .min(size_of_image.saturating_sub(virtual_address));
+            if length == 0 {
+                continue;
+            }
+            image[virtual_address..virtual_address + length]
+                .copy_from_slice(&relocated.buffer[start..start + length]);
+        }
+
+        if let Some(resolver) = resolver {
+            let thunk_size = match pe_type {
+                PEType::PE32 => 4,
+                PEType::PE32Plus => 8,
+            };
+            for dll in relocated.imports()?.dlls {
+                let iat = dll.descriptor.first_thunk.value as usize;
+                for (index, entry) in dll.entries.iter().enumerate() {
+                    let symbol = ImportSymbol::from(entry);
+                    let Some(address) = resolver(&dll.dll_name, &symbol) else {
+                        continue;
+                    };
+                    let slot = iat + index * thunk_size;
+                    let target = image
+                        .get_mut(slot..slot + thunk_size)
+                        .ok_or(FileParseError::BufferOverflow)?;
+                    match pe_type {
+                        PEType::PE32 => {
+                            let address = u32::try_from(address)
+                                .map_err(|_| FileParseError::ValueTooLarge)?;
+                            target.copy_from_slice(&address.to_le_bytes());
+                        }
+                        PEType::PE32Plus => target.copy_from_slice(&address.to_le_bytes()),
+                    }
+                }
+            }
+        }
+
+        Ok(image)
+    }
+}
@@ -18,27 +18,42 @@ use crate::errors::FileParseError;
 use crate::field::{Field, FixedBytes};
 
 pub mod arch_data;
+pub mod arm64_exception;
+pub mod authenticode;
 pub mod bound;
 pub mod certificate;
 pub mod clr;
+pub mod clr_metadata;
 pub mod coff;
 pub mod debug;
 pub mod delay;
+pub mod delay_builder;
 pub mod dos;
+pub mod dump;
+pub mod dynamic_relocation;
 pub mod exception;
 pub mod export;
+pub mod export_builder;
 pub mod header;
+pub mod imphash;
 pub mod import;
+pub mod import_builder;
 pub mod layout;
 pub mod linenum;
 pub mod load_config;
+pub mod loader;
+pub mod overlay;
+pub mod pkcs7;
 pub mod relocation;
 pub mod resource;
+pub mod resource_builder;
+pub mod resource_types;
 pub mod rich;
 pub mod section;
 pub mod section_reloc;
 pub mod symbol;
 pub mod tls;
+pub mod version;
 
 /// A parsed PE image backed by an owned byte buffer.
 pub struct PE {
@@ -175,8 +190,22 @@ impl PE {
         )?))
     }
 
-    /// Parses the exception / runtime function directory when present.
+    /// Parses the x64 exception / runtime function directory when present.
+    ///
+    /// ARM64 images use a different `.pdata` layout and return
+    /// [`FileParseError::UnsupportedFeature`]; use [`PE::exception_table`] instead.
     pub fn exceptions(&self) -> Result<Option<exception::ExceptionDirectory>, FileParseError> {
+        match self.exception_table()? {
+            Some(exception::ExceptionTable::X64(directory)) => Ok(Some(directory)),
+            Some(exception::ExceptionTable::Arm64(_)) => Err(FileParseError::UnsupportedFeature(
+                "ARM64 .pdata through PE::exceptions (use PE::exception_table)".into(),
+            )),
+            None => Ok(None),
+        }
+    }
+
+    /// Parses the exception directory with the `.pdata` layout selected from [`PE::architecture`].
+    pub fn exception_table(&self) -> Result<Option<exception::ExceptionTable>, FileParseError> {
         if !self.optional_header.has_data_directory(header::EXCEPTION) {
             return Ok(None);
         }
@@ -185,11 +214,37 @@ impl PE {
             return Ok(None);
         }
         let offset = self.rva_to_offset(entry.virtual_address.value)?;
-        Ok(Some(exception::ExceptionDirectory::parse(
-            &self.buffer,
-            offset,
-            entry.size.value as usize,
-        )?))
+        let size = entry.size.value as usize;
+        Ok(Some(match self.architecture() {
+            header::Architecture::Arm64 | header::Architecture::Arm64x => {
+                exception::ExceptionTable::Arm64(arm64_exception::Arm64ExceptionDirectory::parse(
+                    &self.buffer,
+                    offset,
+                    size,
+                )?)
+            }
+            _ => exception::ExceptionTable::X64(exception::ExceptionDirectory::parse(
+                &self.buffer,
+                offset,
+                size,
+            )?),
+        }))
+    }
+
+    /// Decodes the x64 `UNWIND_INFO` behind `function`.
+    pub fn unwind_info(
+        &self,
+        function: &exception::RuntimeFunction,
+    ) -> Result<exception::UnwindInfo, FileParseError> {
+        function.unwind_info(&self.buffer, |rva| self.rva_to_offset(rva))
+    }
+
+    /// Decodes the ARM64 `.xdata` record behind `function` (`Ok(None)` for packed entries).
+    pub fn arm64_xdata(
+        &self,
+        function: &arm64_exception::Arm64RuntimeFunction,
+    ) -> Result<Option<arm64_exception::Arm64XData>, FileParseError> {
+        function.xdata(&self.buffer, |rva| self.rva_to_offset(rva))
     }
 
     /// Parses the debug directory when present.
@@ -209,6 +264,19 @@ impl PE {
         )?))
     }
 
+    /// Decodes the first CodeView (`RSDS` / `NB10`) record referenced by the debug directory.
+    pub fn codeview(&self) -> Result<Option<debug::CodeViewRecord>, FileParseError> {
+        let Some(directory) = self.debug_directory()? else {
+            return Ok(None);
+        };
+        for entry in &directory.entries {
+            if let Some(record) = entry.codeview(&self.buffer)? {
+                return Ok(Some(record));
+            }
+        }
+        Ok(None)
+    }
+
     /// Parses the load configuration directory when present.
     pub fn load_config(&self) -> Result<Option<load_config::LoadConfigDirectory>, FileParseError> {
         if !self.optional_header.has_data_directory(header::LOAD_CONFIG) {
@@ -243,6 +311,22 @@ impl PE {
         )?))
     }
 
+    /// Parses the first `RT_VERSION` resource when present.
+    pub fn version_info(&self) -> Result<Option<version::VersionInfo>, FileParseError> {
+        let Some(tree) = self.resources()? else {
+            return Ok(None);
+        };
+        let Some(data) = tree.find_data(version::RT_VERSION) else {
+            return Ok(None);
+        };
+        let offset = self.rva_to_offset(data.offset_to_data.value)?;
+        Ok(Some(version::VersionInfo::parse(
+            &self.buffer,
+            offset,
+            data.size.value as usize,
+        )?))
+    }
+
     /// Parses the COFF symbol table referenced by the file header.
     pub fn coff_symbols(&self) -> Result<symbol::CoffSymbolTable, FileParseError> {
         symbol::CoffSymbolTable::parse(
@@ -281,7 +365,7 @@ impl PE {
         rich::RichHeader::parse(&self.buffer, self.dos_header.e_lfanew.value as usize)
     }
 
-    /// Parses the Authenticode certificate table when present (read-only overlay).
+    /// Parses the Authenticode certificate table when present (file-offset overlay).
     pub fn certificates(&self) -> Result<Option<certificate::CertificateTable>, FileParseError> {
         if !self.optional_header.has_data_directory(header::SECURITY) {
             return Ok(None);
@@ -388,14 +472,8 @@ impl PE {
     /// Updates the preferred image base and applies base relocations.
     pub fn apply_image_base(&mut self, new_base: header::ImageBase) -> Result<(), FileParseError> {
         let pe_type = self.optional_header.pe_type()?;
-        let old_base = match self.optional_header.image_base.value {
-            header::ImageBase::Base32(value) => value as u64,
-            header::ImageBase::Base64(value) => value,
-        };
-        let new_base_value = match new_base {
-            header::ImageBase::Base32(value) => value as u64,
-            header::ImageBase::Base64(value) => value,
-        };
+        let old_base = self.optional_header.image_base.value.value();
+        let new_base_value = new_base.value();
 
         relocation::apply_base_relocations(
             &mut self.buffer,
@@ -466,6 +544,24 @@ impl PE {
 
     /// Parses a PE image from an owned byte buffer.
     pub fn from_buffer(buffer: Vec<u8>) -> Result<Self, FileParseError> {
+        let mut pe = Self::parse_headers(buffer)?;
+        let optional_header = &pe.optional_header;
+        if optional_header.number_of_rva_and_sizes.value > header::BASERELOC as u32 {
+            let directory = &optional_header.data_directories[header::BASERELOC];
+            if directory.virtual_address.value != 0 && directory.size.value != 0 {
+                let offset = pe.rva_to_offset(directory.virtual_address.value)?;
+                pe.base_relocations = relocation::parse_base_relocations(
+                    &pe.buffer,
+                    offset,
+                    directory.size.value as usize,
+                )?;
+            }
+        }
+        Ok(pe)
+    }
+
+    /// Parses the DOS, COFF and optional headers and the section table only.
+    fn parse_headers(buffer: Vec<u8>) -> Result<Self, FileParseError> {
         let dos_header = dos::DosHeader::parse(&buffer)?;
         let pe_header_offset = dos_header.e_lfanew.value as usize;
 
@@ -496,28 +592,13 @@ impl PE {
             current_offset += 40;
         }
 
-        let base_relocations = if optional_header.number_of_rva_and_sizes.value
-            > header::BASERELOC as u32
-        {
-            let directory = &optional_header.data_directories[header::BASERELOC];
-            if directory.virtual_address.value == 0 || directory.size.value == 0 {
-                Vec::new()
-            } else {
-                let offset =
-                    import::rva_to_offset(&buffer, &sections, directory.virtual_address.value)?;
-                relocation::parse_base_relocations(&buffer, offset, directory.size.value as usize)?
-            }
-        } else {
-            Vec::new()
-        };
-
         Ok(PE {
             buffer,
             dos_header,
             coff_header,
             optional_header,
             sections,
-            base_relocations,
+            base_relocations: Vec::new(),
         })
     }
 
@@ -581,14 +662,67 @@ impl PE {
     }
 
     /// Inserts a pre-built [`section::PeSection`] header and raw section data.
+    ///
+    /// A bound import directory in the way of the new header is moved behind it, or stripped when
+    /// the header slack has no room left.
     pub fn insert_section_raw(
         &mut self,
         new_section: section::PeSection,
         data: Vec<u8>,
     ) -> Result<(), FileParseError> {
+        let header = new_section.name.offset
+            ..new_section.characteristics.offset + new_section.characteristics.size;
+        self.make_room_for_section_header(header)?;
         self.insert_section_impl(new_section, data)
     }
 
+    /// Appends `header` and its raw data after the last section without losing the file tail.
+    ///
+    /// Everything past [`PE::image_end_offset`] (overlay and certificate table) is detached, then
+    /// re-attached behind the new section with the table re-pointed and kept 8-byte aligned.
+    /// Directories moved into the new section leave their previous bytes unreferenced.
+    pub(crate) fn append_section_raw(
+        &mut self,
+        header: section::PeSection,
+        data: Vec<u8>,
+    ) -> Result<(), FileParseError> {
+        let tail_start = self.image_end_offset().min(self.buffer.len());
+        let table = if self.optional_header.has_data_directory(header::SECURITY) {
+            let entry = &self.optional_header.data_directories[header::SECURITY];
+            let (offset, size) = (
+                entry.virtual_address.value as usize,
+                entry.size.value as usize,
+            );
+            (offset != 0 && size != 0 && offset >= tail_start).then(|| (offset - tail_start, size))
+        } else {
+            None
+        };
+        let tail = self.buffer.split_off(tail_start);
+
+        self.insert_section_raw(header, data)?;
+        let start = self.buffer.len();
+        self.buffer.extend_from_slice(&tail);
+        if let Some((offset, size)) = table {
+            self.place_certificate_table(start + offset, size)?;
+        }
+        self.sync_layout()
+    }
+
+    /// Pads the certificate table at `offset` to an 8-byte boundary and re-points `SECURITY`.
+    fn place_certificate_table(
+        &mut self,
+        offset: usize,
+        size: usize,
+    ) -> Result<(), FileParseError> {
+        let aligned = offset.next_multiple_of(8);
+        self.buffer
+            .splice(offset..offset, std::iter::repeat_n(0u8, aligned - offset));
+        let aligned = u32::try_from(aligned).map_err(|_| FileParseError::ValueTooLarge)?;
+        let size = u32::try_from(size).map_err(|_| FileParseError::ValueTooLarge)?;
+        self.sync_data_directory_rva(header::SECURITY, aligned)?;
+        self.sync_data_directory_size(header::SECURITY, size)
+    }
+
     fn insert_section_impl(
         &mut self,
         mut new_section: section::PeSection,
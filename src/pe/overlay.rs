@@ -0,0 +1,116 @@
+//! Overlay: file data past the end of the mapped image.
+//!
+//! The image ends at the furthest of `SizeOfHeaders` and every section's raw data. Everything
+//! after that is overlay except the Authenticode certificate table, which conventionally sits at
+//! the very end of the file. When present, the overlay is split around the table: the data
+//! between the image and the table, and any data left behind the table.
+
+use crate::errors::FileParseError;
+use crate::pe::PE;
+
+/// Overlay ranges (absolute file offsets).
+pub struct Overlay {
+    /// File offset of the first overlay byte.
+    pub offset: usize,
+    /// Length in bytes of the data before the certificate table (or up to EOF).
+    pub size: usize,
+    /// File offset of the data following the certificate table; EOF without a table.
+    pub trailing_offset: usize,
+    /// Length in bytes of the data following the certificate table.
+    pub trailing_size: usize,
+}
+
+impl Overlay {
+    /// Overlay bytes before the certificate table.
+    pub fn data<'a>(&self, buffer: &'a [u8]) -> Result<&'a [u8], FileParseError> {
+        buffer
+            .get(self.offset..self.offset + self.size)
+            .ok_or(FileParseError::BufferOverflow)
+    }
+
+    /// Overlay bytes after the certificate table.
+    pub fn trailing_data<'a>(&self, buffer: &'a [u8]) -> Result<&'a [u8], FileParseError> {
+        buffer
+            .get(self.trailing_offset..self.trailing_offset + self.trailing_size)
+            .ok_or(FileParseError::BufferOverflow)
+    }
+}
+
+impl PE {
+    /// File offset where the mapped image ends (headers and section raw data).
+    pub fn image_end_offset(&self) -> usize {
+        self.sections
+            .iter()
+            .filter(|section| section.size_of_raw_data.value != 0)
+            .map(|section| {
+                section.pointer_to_raw_data.value as usize + section.size_of_raw_data.value as usize
+            })
+            .max()
+            .unwrap_or(0)
+            .max(self.optional_header.size_of_headers.value as usize)
+    }
+
+    /// Returns the overlay ranges; `Ok(None)` when the file holds nothing past the image but the
+    /// certificate table.
+    pub fn overlay(&self) -> Result<Option<Overlay>, FileParseError> {
+        let (offset, end, trailing_offset) = self.overlay_bounds()?;
+        let trailing_size = self.buffer.len() - trailing_offset;
+        Ok((end > offset || trailing_size != 0).then(|| Overlay {
+            offset,
+            size: end - offset,
+            trailing_offset,
+            trailing_size,
+        }))
+    }
+
+    /// Removes the overlay on both sides of the certificate table; the table moves down and keeps
+    /// its 8-byte alignment.
+    pub fn strip_overlay(&mut self) -> Result<(), FileParseError> {
+        let (offset, end, trailing_offset) = self.overlay_bounds()?;
+        self.buffer.truncate(trailing_offset);
+        self.replace_overlay(offset, end, &[])
+    }
+
+    /// Appends `data` to the overlay, ahead of any certificate table.
+    ///
+    /// The table is moved behind the new data and zero padding is added so it stays 8-byte
+    /// aligned; that padding becomes part of the overlay. Existing signatures no longer match the
+    /// file afterwards since the overlay is covered by the Authenticode digest.
+    pub fn append_overlay(&mut self, data: &[u8]) -> Result<(), FileParseError> {
+        let (_, end, _) = self.overlay_bounds()?;
+        self.replace_overlay(end, end, data)
+    }
+
+    /// Returns `(image end, certificate table start or EOF, certificate table end or EOF)`.
+    fn overlay_bounds(&self) -> Result<(usize, usize, usize), FileParseError> {
+        let len = self.buffer.len();
+        let offset = self.image_end_offset().min(len);
+        Ok(match self.certificates()? {
+            Some(table) if table.offset >= offset => {
+                (offset, table.offset, (table.offset + table.size).min(len))
+            }
+            _ => (offset, len, len),
+        })
+    }
+
+    /// Replaces `start..end` with `data`, then realigns and re-points a following certificate
+    /// table.
+    fn replace_overlay(
+        &mut self,
+        start: usize,
+        end: usize,
+        data: &[u8],
+    ) -> Result<(), FileParseError> {
+        let table = self
+            .certificates()?
+            .filter(|table| table.offset >= end)
+            .map(|table| (table.offset, table.size));
+        self.buffer.splice(start..end, data.iter().copied());
+
+        if let Some((old_offset, size)) = table {
+            let moved = old_offset - (end - start) + data.len();
+            self.place_certificate_table(moved, size)?;
+        }
+        self.sync_layout()
+    }
+}
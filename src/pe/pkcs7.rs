@@ -0,0 +1,412 @@
+//! PKCS#7 `SignedData` model for Authenticode signatures.
+//!
+//! Decodes the `ContentInfo` carried by a `WIN_CERT_TYPE_PKCS_SIGNED_DATA` certificate: the
+//! `SpcIndirectDataContent` image digest, the embedded X.509 certificates, and the signer infos
+//! including nested signatures (`szOID_NESTED_SIGNATURE`). Signatures are not verified.
+
+use std::fmt;
+
+use crate::der::{self, DerElement};
+use crate::errors::FileParseError;
+use crate::hash::HashAlgorithm;
+
+/// `pkcs7-signedData`.
+pub const OID_SIGNED_DATA: &str = "1.2.840.113549.1.7.2";
+/// `SPC_INDIRECT_DATA_OBJID`.
+pub const OID_SPC_INDIRECT_DATA: &str = "1.3.6.1.4.1.311.2.1.4";
+/// `szOID_NESTED_SIGNATURE` unauthenticated attribute.
+pub const OID_NESTED_SIGNATURE: &str = "1.3.6.1.4.1.311.2.4.1";
+/// `sha1`.
+pub const OID_SHA1: &str = "1.3.14.3.2.26";
+/// `sha256`.
+pub const OID_SHA256: &str = "2.16.840.1.101.3.4.2.1";
+
+/// X.501 distinguished name as ordered `(attribute OID, value)` pairs.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DistinguishedName {
+    /// Attributes in encoding order.
+    pub attributes: Vec<(String, String)>,
+}
+
+/// `SpcIndirectDataContent.messageDigest`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpcIndirectData {
+    /// Digest algorithm OID.
+    pub digest_algorithm: String,
+    /// Signed image digest.
+    pub digest: Vec<u8>,
+}
+
+/// Fields of an embedded X.509 certificate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct X509Certificate {
+    /// Offset of the certificate within the `SignedData` encoding.
+    pub offset: usize,
+    /// Encoded length in bytes.
+    pub length: usize,
+    /// Serial number magnitude bytes.
+    pub serial: Vec<u8>,
+    /// Signature algorithm OID.
+    pub signature_algorithm: String,
+    /// Issuer name.
+    pub issuer: DistinguishedName,
+    /// Subject name.
+    pub subject: DistinguishedName,
+    /// `notBefore` as encoded (`UTCTime` or `GeneralizedTime`).
+    pub not_before: String,
+    /// `notAfter` as encoded.
+    pub not_after: String,
+}
+
+/// One `SignerInfo`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignerInfo {
+    /// Version.
+    pub version: u64,
+    /// Issuer of the signing certificate.
+    pub issuer: DistinguishedName,
+    /// Serial number of the signing certificate.
+    pub serial: Vec<u8>,
+    /// Digest algorithm OID.
+    pub digest_algorithm: String,
+    /// Signature algorithm OID (`digestEncryptionAlgorithm`).
+    pub signature_algorithm: String,
+    /// Signature value (`encryptedDigest`).
+    pub signature: Vec<u8>,
+    /// OIDs of the authenticated attributes.
+    pub authenticated_attributes: Vec<String>,
+    /// OIDs of the unauthenticated attributes.
+    pub unauthenticated_attributes: Vec<String>,
+    /// Signatures nested under `szOID_NESTED_SIGNATURE`.
+    pub nested: Vec<SignedData>,
+}
+
+/// PKCS#7 `SignedData`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedData {
+    /// Version.
+    pub version: u64,
+    /// Digest algorithm OIDs.
+    pub digest_algorithms: Vec<String>,
+    /// Inner content type OID.
+    pub content_type: String,
+    /// Authenticode digest, when the content is `SpcIndirectDataContent`.
+    pub indirect_data: Option<SpcIndirectData>,
+    /// Embedded certificates.
+    pub certificates: Vec<X509Certificate>,
+    /// Signers.
+    pub signers: Vec<SignerInfo>,
+}
+
+impl DistinguishedName {
+    fn parse(name: &DerElement) -> Result<Self, FileParseError> {
+        let mut attributes = Vec::new();
+        for rdn in name.expect_children(der::TAG_SEQUENCE)? {
+            for pair in rdn.expect(der::TAG_SET)?.children()? {
+                let pair = pair.expect(der::TAG_SEQUENCE)?.children()?;
+                let [oid, value, ..] = pair.as_slice() else {
+                    return Err(FileParseError::InvalidFileFormat);
+                };
+                let value = value.string().unwrap_or_else(|_| hex(value.contents));
+                attributes.push((oid.oid()?, value));
+            }
+        }
+        Ok(DistinguishedName { attributes })
+    }
+
+    /// First value of attribute `oid`.
+    pub fn get(&self, oid: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == oid)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Common name (`2.5.4.3`).
+    pub fn common_name(&self) -> Option<&str> {
+        self.get("2.5.4.3")
+    }
+}
+
+impl fmt::Display for DistinguishedName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (oid, value)) in self.attributes.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            let key = match oid.as_str() {
+                "2.5.4.3" => "CN",
+                "2.5.4.6" => "C",
+                "2.5.4.7" => "L",
+                "2.5.4.8" => "ST",
+                "2.5.4.10" => "O",
+                "2.5.4.11" => "OU",
+                "1.2.840.113549.1.9.1" => "emailAddress",
+                other => other,
+            };
+            write!(f, "{key}={value}")?;
+        }
+        Ok(())
+    }
+}
+
+impl SpcIndirectData {
+    /// Maps [`Self::digest_algorithm`] onto a supported [`HashAlgorithm`].
+    pub fn hash_algorithm(&self) -> Option<HashAlgorithm> {
+        match self.digest_algorithm.as_str() {
+            OID_SHA1 => Some(HashAlgorithm::Sha1),
+            OID_SHA256 => Some(HashAlgorithm::Sha256),
+            _ => None,
+        }
+    }
+
+    fn parse(content: &DerElement) -> Result<Self, FileParseError> {
+        let fields = content.expect_children(der::TAG_SEQUENCE)?;
+        let digest_info = fields
+            .get(1)
+            .ok_or(FileParseError::InvalidFileFormat)?
+            .expect_children(der::TAG_SEQUENCE)?;
+        let [algorithm, digest, ..] = digest_info.as_slice() else {
+            return Err(FileParseError::InvalidFileFormat);
+        };
+        Ok(SpcIndirectData {
+            digest_algorithm: algorithm_oid(algorithm)?,
+            digest: digest.expect(der::TAG_OCTET_STRING)?.contents.to_vec(),
+        })
+    }
+}
+
+impl X509Certificate {
+    fn parse(certificate: &DerElement) -> Result<Self, FileParseError> {
+        let fields = certificate.expect_children(der::TAG_SEQUENCE)?;
+        let tbs = fields
+            .first()
+            .ok_or(FileParseError::InvalidFileFormat)?
+            .expect_children(der::TAG_SEQUENCE)?;
+        // Skip the optional `[0] EXPLICIT version`.
+        let tbs = match tbs.first() {
+            Some(first) if first.tag == der::context(0) => &tbs[1..],
+            _ => &tbs[..],
+        };
+        let [serial, signature, issuer, validity, subject, ..] = tbs else {
+            return Err(FileParseError::InvalidFileFormat);
+        };
+        let validity = validity.expect_children(der::TAG_SEQUENCE)?;
+        let [not_before, not_after] = validity.as_slice() else {
+            return Err(FileParseError::InvalidFileFormat);
+        };
+
+        Ok(X509Certificate {
+            offset: certificate.offset,
+            length: certificate.end() - certificate.offset,
+            serial: serial.integer_bytes()?.to_vec(),
+            signature_algorithm: algorithm_oid(signature)?,
+            issuer: DistinguishedName::parse(issuer)?,
+            subject: DistinguishedName::parse(subject)?,
+            not_before: not_before.string()?,
+            not_after: not_after.string()?,
+        })
+    }
+
+    /// Serial number as upper-case hex.
+    pub fn serial_hex(&self) -> String {
+        hex(&self.serial).to_uppercase()
+    }
+}
+
+impl SignerInfo {
+    fn parse(signer: &DerElement) -> Result<Self, FileParseError> {
+        let fields = signer.expect_children(der::TAG_SEQUENCE)?;
+        let mut fields = fields.iter();
+        let mut next = || fields.next().ok_or(FileParseError::InvalidFileFormat);
+
+        let version = next()?.integer_u64()?;
+        let issuer_and_serial = next()?.expect_children(der::TAG_SEQUENCE)?;
+        let [issuer, serial, ..] = issuer_and_serial.as_slice() else {
+            return Err(FileParseError::InvalidFileFormat);
+        };
+        let digest_algorithm = algorithm_oid(next()?)?;
+
+        let mut element = next()?;
+        let mut authenticated_attributes = Vec::new();
+        if element.tag == der::context(0) {
+            for (oid, _) in attributes(element)? {
+                authenticated_attributes.push(oid);
+            }
+            element = next()?;
+        }
+        let signature_algorithm = algorithm_oid(element)?;
+        let signature = next()?.expect(der::TAG_OCTET_STRING)?.contents.to_vec();
+
+        let mut unauthenticated_attributes = Vec::new();
+        let mut nested = Vec::new();
+        if let Ok(element) = next() {
+            if element.tag == der::context(1) {
+                for (oid, values) in attributes(element)? {
+                    if oid == OID_NESTED_SIGNATURE {
+                        for value in &values {
+                            nested.push(SignedData::parse_content_info(value)?);
+                        }
+                    }
+                    unauthenticated_attributes.push(oid);
+                }
+            }
+        }
+
+        Ok(SignerInfo {
+            version,
+            issuer: DistinguishedName::parse(issuer)?,
+            serial: serial.integer_bytes()?.to_vec(),
+            digest_algorithm,
+            signature_algorithm,
+            signature,
+            authenticated_attributes,
+            unauthenticated_attributes,
+            nested,
+        })
+    }
+}
+
+impl SignedData {
+    /// Parses a DER `ContentInfo` wrapping `SignedData` (a `WIN_CERTIFICATE` payload).
+    pub fn parse(data: &[u8]) -> Result<Self, FileParseError> {
+        let content_info = DerElement::parse_tagged(data, 0, der::TAG_SEQUENCE)?;
+        SignedData::parse_content_info(&content_info)
+    }
+
+    fn parse_content_info(content_info: &DerElement) -> Result<Self, FileParseError> {
+        let fields = content_info.expect_children(der::TAG_SEQUENCE)?;
+        let [content_type, content, ..] = fields.as_slice() else {
+            return Err(FileParseError::InvalidFileFormat);
+        };
+        if content_type.expect(der::TAG_OID)?.oid()? != OID_SIGNED_DATA {
+            return Err(FileParseError::UnsupportedFeature(
+                "ContentInfo is not pkcs7-signedData".to_string(),
+            ));
+        }
+        let signed_data = content
+            .expect_children(der::context(0))?
+            .first()
+            .ok_or(FileParseError::InvalidFileFormat)?
+            .expect_children(der::TAG_SEQUENCE)?;
+
+        let mut fields = signed_data.iter().peekable();
+        let version = fields
+            .next()
+            .ok_or(FileParseError::InvalidFileFormat)?
+            .integer_u64()?;
+        let digest_algorithms = fields
+            .next()
+            .ok_or(FileParseError::InvalidFileFormat)?
+            .expect_children(der::TAG_SET)?
+            .iter()
+            .map(algorithm_oid)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let inner = fields
+            .next()
+            .ok_or(FileParseError::InvalidFileFormat)?
+            .expect_children(der::TAG_SEQUENCE)?;
+        let content_type = inner
+            .first()
+            .ok_or(FileParseError::InvalidFileFormat)?
+            .oid()?;
+        let indirect_data = match inner.get(1) {
+            Some(wrapper) if content_type == OID_SPC_INDIRECT_DATA => {
+                let value = *wrapper
+                    .expect_children(der::context(0))?
+                    .first()
+                    .ok_or(FileParseError::InvalidFileFormat)?;
+                // CMS encoders wrap eContent in an OCTET STRING; Authenticode embeds it directly.
+                let value = if value.tag == der::TAG_OCTET_STRING {
+                    DerElement::parse(value.contents, 0)?
+                } else {
+                    value
+                };
+                Some(SpcIndirectData::parse(&value)?)
+            }
+            _ => None,
+        };
+
+        let mut certificates = Vec::new();
+        if let Some(element) = fields.next_if(|e| e.tag == der::context(0)) {
+            for certificate in element.children()? {
+                // Attribute certificates and other choices are tagged and skipped.
+                if certificate.tag == der::TAG_SEQUENCE {
+                    certificates.push(X509Certificate::parse(&certificate)?);
+                }
+            }
+        }
+        fields.next_if(|e| e.tag == der::context(1));
+
+        let signers = fields
+            .next()
+            .ok_or(FileParseError::InvalidFileFormat)?
+            .expect_children(der::TAG_SET)?
+            .iter()
+            .map(SignerInfo::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(SignedData {
+            version,
+            digest_algorithms,
+            content_type,
+            indirect_data,
+            certificates,
+            signers,
+        })
+    }
+
+    /// This signature followed by every nested signature, depth-first.
+    pub fn all_signatures(&self) -> Vec<&SignedData> {
+        let mut out = vec![self];
+        for signer in &self.signers {
+            for nested in &signer.nested {
+                out.extend(nested.all_signatures());
+            }
+        }
+        out
+    }
+
+    /// Certificate whose issuer and serial match `signer`.
+    pub fn signer_certificate(&self, signer: &SignerInfo) -> Option<&X509Certificate> {
+        self.certificates
+            .iter()
+            .find(|cert| cert.issuer == signer.issuer && cert.serial == signer.serial)
+    }
+}
+
+impl DerElement<'_> {
+    /// Checks the tag and returns the children.
+    fn expect_children(&self, tag: u8) -> Result<Vec<Self>, FileParseError> {
+        self.expect(tag)?.children()
+    }
+}
+
+/// OID of an `AlgorithmIdentifier`.
+fn algorithm_oid(algorithm: &DerElement) -> Result<String, FileParseError> {
+    algorithm
+        .expect_children(der::TAG_SEQUENCE)?
+        .first()
+        .ok_or(FileParseError::InvalidFileFormat)?
+        .oid()
+}
+
+/// `(OID, values)` for each `Attribute` in an implicitly tagged `SET OF Attribute`.
+fn attributes<'a>(
+    set: &DerElement<'a>,
+) -> Result<Vec<(String, Vec<DerElement<'a>>)>, FileParseError> {
+    let mut out = Vec::new();
+    for attribute in set.children()? {
+        let fields = attribute.expect_children(der::TAG_SEQUENCE)?;
+        let [oid, values, ..] = fields.as_slice() else {
+            return Err(FileParseError::InvalidFileFormat);
+        };
+        out.push((oid.oid()?, values.expect_children(der::TAG_SET)?));
+    }
+    Ok(out)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
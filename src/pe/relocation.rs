@@ -3,12 +3,17 @@
 //! The base relocation directory is a sequence of `IMAGE_BASE_RELOCATION`
 //! blocks. Each block owns a page RVA and a list of 16-bit entries whose high
 //! nibble is the relocation type and low 12 bits are the offset within the page.
+//!
+//! [`BaseRelocationBuilder`] is the editable counterpart: it keeps one relocation per RVA and
+//! regroups them into 4 KiB page blocks padded to a 32-bit boundary when serialized.
+
+use std::collections::BTreeMap;
 
 use crate::errors::FileParseError;
 use crate::field::Field;
-use crate::pe::header::PEType;
-use crate::pe::import;
+use crate::pe::header::{self, PEType};
 use crate::pe::section::PeSection;
+use crate::pe::{coff, import, section, PE};
 use crate::utils::{extract_u16, extract_u32};
 
 /// `IMAGE_REL_BASED_ABSOLUTE` padding entry.
@@ -21,9 +26,20 @@ pub const IMAGE_REL_BASED_LOW: u16 = 2;
 pub const IMAGE_REL_BASED_HIGHLOW: u16 = 3;
 /// `IMAGE_REL_BASED_HIGHADJ`.
 pub const IMAGE_REL_BASED_HIGHADJ: u16 = 4;
-/// `IMAGE_REL_BASED_DIR64`.
+/// `IMAGE_REL_BASED_ARM_MOV32` (also `MIPS_JMPADDR` / `RISCV_HIGH20` by machine).
+pub const IMAGE_REL_BASED_ARM_MOV32: u16 = 5;
+/// `IMAGE_REL_BASED_THUMB_MOV32` (also `RISCV_LOW12I` by machine).
+pub const IMAGE_REL_BASED_THUMB_MOV32: u16 = 7;
+/// `IMAGE_REL_BASED_RISCV_LOW12S` (also `LOONGARCH32_MARK_LA` / `LOONGARCH64_MARK_LA`).
+pub const IMAGE_REL_BASED_RISCV_LOW12S: u16 = 8;
+/// `IMAGE_REL_BASED_MIPS_JMPADDR16`.
+pub const IMAGE_REL_BASED_MIPS_JMPADDR16: u16 = 9;
+/// `IMAGE_REL_BASED_DIR64` (x64 and ARM64 absolute pointers).
 pub const IMAGE_REL_BASED_DIR64: u16 = 10;
 
+/// Page size covered by one `IMAGE_BASE_RELOCATION` block.
+const PAGE_SIZE: u32 = 0x1000;
+
 /// One 16-bit relocation entry inside an `IMAGE_BASE_RELOCATION` block.
 #[derive(Debug, Clone)]
 pub struct BaseRelocationEntry {
@@ -196,3 +212,214 @@ pub fn apply_base_relocations(
 
     Ok(())
 }
+
+/// Editable base relocation table: one relocation type per target RVA.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BaseRelocationBuilder {
+    /// Relocation type keyed by the RVA it patches.
+    pub entries: BTreeMap<u32, u16>,
+}
+
+impl BaseRelocationBuilder {
+    /// Creates an empty table.
+    pub fn new() -> Self {
+        BaseRelocationBuilder::default()
+    }
+
+    /// Collects the entries of parsed blocks, dropping `IMAGE_REL_BASED_ABSOLUTE` padding.
+    ///
+    /// `IMAGE_REL_BASED_HIGHADJ` carries a parameter word in the next slot and is rejected.
+    pub fn from_blocks(blocks: &[BaseRelocationBlock]) -> Result<Self, FileParseError> {
+        let mut builder = BaseRelocationBuilder::new();
+        for block in blocks {
+            for entry in &block.entries {
+                match entry.relocation_type() {
+                    IMAGE_REL_BASED_ABSOLUTE => {}
+                    kind => builder.add(entry.rva(block.page_rva.value), kind)?,
+                }
+            }
+        }
+        Ok(builder)
+    }
+
+    /// Adds or replaces the relocation patching `rva`.
+    pub fn add(&mut self, rva: u32, kind: u16) -> Result<(), FileParseError> {
+        match kind {
+            IMAGE_REL_BASED_ABSOLUTE => Err(FileParseError::InvalidFileFormat),
+            IMAGE_REL_BASED_HIGHADJ => Err(FileParseError::UnsupportedFeature(
+                "IMAGE_REL_BASED_HIGHADJ relocations".to_string(),
+            )),
+            0x10.. => Err(FileParseError::ValueTooLarge),
+            _ => {
+                self.entries.insert(rva, kind);
+                Ok(())
+            }
+        }
+    }
+
+    /// Removes the relocation patching `rva` and returns its type.
+    pub fn remove(&mut self, rva: u32) -> Option<u16> {
+        self.entries.remove(&rva)
+    }
+
+    /// Removes every relocation in `start..end` and returns how many were dropped.
+    pub fn remove_range(&mut self, start: u32, end: u32) -> usize {
+        let doomed: Vec<u32> = self
+            .entries
+            .range(start..end)
+            .map(|(rva, _)| *rva)
+            .collect();
+        for rva in &doomed {
+            self.entries.remove(rva);
+        }
+        doomed.len()
+    }
+
+    /// Serializes page blocks in RVA order, padding odd entry counts with an absolute entry.
+    pub fn build(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut entries = self.entries.iter().peekable();
+        while let Some((&first, _)) = entries.peek() {
+            let page = first & !(PAGE_SIZE - 1);
+            let header = out.len();
+            out.extend_from_slice(&page.to_le_bytes());
+            out.extend_from_slice(&0u32.to_le_bytes());
+            while let Some((&rva, &kind)) = entries.next_if(|(&rva, _)| rva - page < PAGE_SIZE) {
+                out.extend_from_slice(&((kind << 12) | (rva - page) as u16).to_le_bytes());
+            }
+            if (out.len() - header) % 4 != 0 {
+                out.extend_from_slice(&IMAGE_REL_BASED_ABSOLUTE.to_le_bytes());
+            }
+            let block_size = (out.len() - header) as u32;
+            out[header + 4..header + 8].copy_from_slice(&block_size.to_le_bytes());
+        }
+        out
+    }
+}
+
+impl PE {
+    /// Loads [`PE::base_relocations`] into an editable [`BaseRelocationBuilder`].
+    pub fn base_relocation_builder(&self) -> Result<BaseRelocationBuilder, FileParseError> {
+        BaseRelocationBuilder::from_blocks(&self.base_relocations)
+    }
+
+    /// Serializes `builder` into the base relocation directory and re-parses
+    /// [`PE::base_relocations`].
+    ///
+    /// Blocks are rewritten in place when they fit the current directory or the zero bytes after
+    /// it, otherwise into a new `.reloc` section. A non-empty table clears
+    /// `IMAGE_FILE_RELOCS_STRIPPED` so the image stays relocatable.
+    pub fn set_base_relocations(
+        &mut self,
+        builder: &BaseRelocationBuilder,
+    ) -> Result<(), FileParseError> {
+        if !self.optional_header.has_data_directory(header::BASERELOC) {
+            return Err(FileParseError::UnsupportedFeature(
+                "image has no base relocation data directory slot".to_string(),
+            ));
+        }
+        let data = builder.build();
+        let current = &self.optional_header.data_directories[header::BASERELOC];
+        let (current_rva, current_size) = (current.virtual_address.value, current.size.value);
+        let section_align = self.optional_header.section_alignment.value.max(1);
+
+        let mut in_place = None;
+        if current_rva != 0 {
+            let start = self.rva_to_offset(current_rva)?;
+            let end = start
+                .checked_add(current_size as usize)
+                .ok_or(FileParseError::BufferOverflow)?;
+            self.buffer
+                .get_mut(start..end)
+                .ok_or(FileParseError::BufferOverflow)?
+                .fill(0);
+
+            in_place = self.sections.iter().position(|section| {
+                let va = section.virtual_address.value;
+                let capacity = section.size_of_raw_data.value.min(
+                    section
+                        .virtual_size
+                        .value
+                        .max(1)
+                        .next_multiple_of(section_align),
+                );
+                if current_rva < va || current_rva - va >= capacity {
+                    return false;
+                }
+                let room = (capacity - (current_rva - va)) as usize;
+                let section_end = start + room;
+                data.len() <= current_size as usize
+                    || (data.len() <= room
+                        && self
+                            .buffer
+                            .get(end..section_end)
+                            .is_some_and(|rest| rest.iter().all(|&byte| byte == 0)))
+            });
+        }
+
+        let rva = match in_place {
+            Some(index) => {
+                let offset = self.rva_to_offset(current_rva)?;
+                self.buffer[offset..offset + data.len()].copy_from_slice(&data);
+                let section = &mut self.sections[index];
+                let used = current_rva - section.virtual_address.value + data.len() as u32;
+                if section.virtual_size.value < used {
+                    section.virtual_size.update(&mut self.buffer, used)?;
+                }
+                current_rva
+            }
+            None if data.is_empty() => 0,
+            None => {
+                let characteristics =
+                    section::INITIALIZED_DATA | section::READ | section::DISCARDABLE;
+                let header =
+                    self.build_section_header(".reloc", data.len() as u32, characteristics)?;
+                let rva = header.virtual_address.value;
+                self.append_section_raw(header, data.clone())?;
+                rva
+            }
+        };
+        let rva = if data.is_empty() { 0 } else { rva };
+        self.sync_data_directory_rva(header::BASERELOC, rva)?;
+        self.sync_data_directory_size(header::BASERELOC, data.len() as u32)?;
+
+        self.base_relocations = if data.is_empty() {
+            Vec::new()
+        } else {
+            parse_base_relocations(&self.buffer, self.rva_to_offset(rva)?, data.len())?
+        };
+        if !data.is_empty() {
+            let characteristics = self.coff_header.characteristics.value;
+            self.coff_header.characteristics.update(
+                &mut self.buffer,
+                characteristics & !coff::IMAGE_FILE_RELOCS_STRIPPED,
+            )?;
+        }
+
+        let checksum = self.calc_checksum();
+        self.optional_header
+            .checksum
+            .update(&mut self.buffer, checksum)
+    }
+
+    /// Adds `HIGHLOW` (PE32) or `DIR64` (PE32+) relocations for pointers written at `rvas`.
+    ///
+    /// Images with no relocations and `IMAGE_FILE_RELOCS_STRIPPED` set only load at their
+    /// preferred base and are left without a table.
+    pub(crate) fn add_pointer_relocations(&mut self, rvas: &[u32]) -> Result<(), FileParseError> {
+        let stripped =
+            self.coff_header.characteristics.value & coff::IMAGE_FILE_RELOCS_STRIPPED != 0;
+        if rvas.is_empty() || (stripped && self.base_relocations.is_empty()) {
+            return Ok(());
+        }
+        let kind = match self.optional_header.pe_type()? {
+            PEType::PE32 => IMAGE_REL_BASED_HIGHLOW,
+            PEType::PE32Plus => IMAGE_REL_BASED_DIR64,
+        };
+        let mut builder = self.base_relocation_builder()?;
+        for &rva in rvas {
+            builder.add(rva, kind)?;
+        }
+        self.set_base_relocations(&builder)
+    }
+}
@@ -161,6 +161,27 @@ impl ResourceTree {
         let root = parse_node(buffer, offset, offset, &rva_to_offset)?;
         Ok(ResourceTree { offset, root })
     }
+
+    /// Returns the first data leaf under the top-level entry with type ID `type_id`.
+    pub fn find_data(&self, type_id: u16) -> Option<&ResourceDataEntry> {
+        self.root.entries.iter().find_map(|entry| match entry {
+            ResourceEntry::Directory { id, directory, .. } if *id == Some(type_id) => {
+                directory.first_data()
+            }
+            ResourceEntry::Data { id, data, .. } if *id == Some(type_id) => Some(data),
+            _ => None,
+        })
+    }
+}
+
+impl ResourceDirectoryNode {
+    /// Returns the first data leaf reached by a depth-first walk of this node.
+    pub fn first_data(&self) -> Option<&ResourceDataEntry> {
+        self.entries.iter().find_map(|entry| match entry {
+            ResourceEntry::Directory { directory, .. } => directory.first_data(),
+            ResourceEntry::Data { data, .. } => Some(data),
+        })
+    }
 }
 
 #[allow(clippy::only_used_in_recursion)]
@@ -232,7 +253,7 @@ fn read_resource_name(
     name_offset: u32,
 ) -> Result<String, FileParseError> {
     let off = resource_base
-        .checked_add(name_offset as usize)
+        .checked_add((name_offset & 0x7FFF_FFFF) as usize)
         .ok_or(FileParseError::BufferOverflow)?;
     if buffer.len() < off + 2 {
         return Err(FileParseError::BufferOverflow);
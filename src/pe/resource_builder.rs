@@ -0,0 +1,422 @@
+//! Editable resource model and `.rsrc` serializer.
+//!
+//! [`ResourceBuilder`] flattens the usual three-level tree (type / name / language) into a map of
+//! leaves. [`ResourceBuilder::build`] writes the directory tables, then the name strings, then the
+//! `IMAGE_RESOURCE_DATA_ENTRY` records, and finally the 8-byte aligned resource data.
+
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+use crate::errors::FileParseError;
+use crate::pe::resource::{ResourceDataEntry, ResourceDirectory, ResourceEntry, ResourceTree};
+use crate::pe::{header, section, PE};
+
+/// Type, name, or language key of a resource directory entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResourceName {
+    /// Numeric ID entry.
+    Id(u16),
+    /// Named entry (stored as a length-prefixed UTF-16 string).
+    Name(String),
+}
+
+/// One resource leaf.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResourceLeaf {
+    /// Raw resource bytes.
+    pub data: Vec<u8>,
+    /// Code page written to `IMAGE_RESOURCE_DATA_ENTRY.CodePage`.
+    pub code_page: u32,
+}
+
+/// Editable type / name / language → bytes resource model.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResourceBuilder {
+    /// Leaves keyed by `(type, name, language)` in on-disk order.
+    pub leaves: BTreeMap<(ResourceName, ResourceName, u16), ResourceLeaf>,
+}
+
+impl Ord for ResourceName {
+    /// Named entries sort first (case-insensitively), then IDs in ascending order.
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (ResourceName::Name(a), ResourceName::Name(b)) => a
+                .to_uppercase()
+                .cmp(&b.to_uppercase())
+                .then_with(|| a.cmp(b)),
+            (ResourceName::Name(_), ResourceName::Id(_)) => Ordering::Less,
+            (ResourceName::Id(_), ResourceName::Name(_)) => Ordering::Greater,
+            (ResourceName::Id(a), ResourceName::Id(b)) => a.cmp(b),
+        }
+    }
+}
+
+impl PartialOrd for ResourceName {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl ResourceName {
+    fn from_entry(name: &Option<String>, id: Option<u16>) -> Self {
+        match name {
+            Some(name) => ResourceName::Name(name.clone()),
+            None => ResourceName::Id(id.unwrap_or(0)),
+        }
+    }
+}
+
+impl ResourceBuilder {
+    /// Creates an empty builder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Copies every leaf of a type / name / language tree into a builder.
+    pub fn from_tree(
+        buffer: &[u8],
+        tree: &ResourceTree,
+        rva_to_offset: impl Fn(u32) -> Result<usize, FileParseError>,
+    ) -> Result<Self, FileParseError> {
+        let unsupported = || {
+            FileParseError::UnsupportedFeature(
+                "resource tree is not laid out as type / name / language".to_string(),
+            )
+        };
+
+        let mut builder = ResourceBuilder::new();
+        for type_entry in &tree.root.entries {
+            let ResourceEntry::Directory {
+                name,
+                id,
+                directory,
+                ..
+            } = type_entry
+            else {
+                return Err(unsupported());
+            };
+            let type_key = ResourceName::from_entry(name, *id);
+            for name_entry in &directory.entries {
+                let ResourceEntry::Directory {
+                    name,
+                    id,
+                    directory,
+                    ..
+                } = name_entry
+                else {
+                    return Err(unsupported());
+                };
+                let name_key = ResourceName::from_entry(name, *id);
+                for language_entry in &directory.entries {
+                    let ResourceEntry::Data { id, data, .. } = language_entry else {
+                        return Err(unsupported());
+                    };
+                    let start = rva_to_offset(data.offset_to_data.value)?;
+                    let end = start
+                        .checked_add(data.size.value as usize)
+                        .ok_or(FileParseError::BufferOverflow)?;
+                    let bytes = buffer
+                        .get(start..end)
+                        .ok_or(FileParseError::BufferOverflow)?;
+                    builder.leaves.insert(
+                        (type_key.clone(), name_key.clone(), id.unwrap_or(0)),
+                        ResourceLeaf {
+                            data: bytes.to_vec(),
+                            code_page: data.code_page.value,
+                        },
+                    );
+                }
+            }
+        }
+        Ok(builder)
+    }
+
+    /// Adds or replaces a leaf, returning the previous one.
+    pub fn set(
+        &mut self,
+        resource_type: ResourceName,
+        name: ResourceName,
+        language: u16,
+        data: Vec<u8>,
+    ) -> Option<ResourceLeaf> {
+        self.leaves.insert(
+            (resource_type, name, language),
+            ResourceLeaf { data, code_page: 0 },
+        )
+    }
+
+    /// Removes a leaf, returning it when present.
+    pub fn remove(
+        &mut self,
+        resource_type: &ResourceName,
+        name: &ResourceName,
+        language: u16,
+    ) -> Option<ResourceLeaf> {
+        self.leaves
+            .remove(&(resource_type.clone(), name.clone(), language))
+    }
+
+    /// Returns the bytes of a leaf.
+    pub fn get(
+        &self,
+        resource_type: &ResourceName,
+        name: &ResourceName,
+        language: u16,
+    ) -> Option<&[u8]> {
+        self.leaves
+            .get(&(resource_type.clone(), name.clone(), language))
+            .map(|leaf| leaf.data.as_slice())
+    }
+
+    /// Serializes the resource section for placement at `base_rva`.
+    pub fn build(&self, base_rva: u32) -> Result<Vec<u8>, FileParseError> {
+        // Group leaves into type -> name -> language levels (BTreeMap order is on-disk order).
+        let mut types: Vec<(&ResourceName, NameLevel)> = Vec::new();
+        for ((resource_type, name, language), leaf) in &self.leaves {
+            if types.last().map(|(t, _)| *t) != Some(resource_type) {
+                types.push((resource_type, Vec::new()));
+            }
+            let names = &mut types.last_mut().expect("type just pushed").1;
+            if names.last().map(|(n, _)| *n) != Some(name) {
+                names.push((name, Vec::new()));
+            }
+            names
+                .last_mut()
+                .expect("name just pushed")
+                .1
+                .push((*language, leaf));
+        }
+
+        let table_size = |count: usize| ResourceDirectory::SIZE + count * 8;
+        let mut cursor = table_size(types.len());
+        let mut type_tables = Vec::with_capacity(types.len());
+        for (_, names) in &types {
+            type_tables.push(cursor);
+            cursor += table_size(names.len());
+        }
+        let mut name_tables = Vec::new();
+        for (_, names) in &types {
+            for (_, languages) in names {
+                name_tables.push(cursor);
+                cursor += table_size(languages.len());
+            }
+        }
+
+        let mut strings: Vec<(&str, usize)> = Vec::new();
+        for (resource_type, names) in &types {
+            let keys = std::iter::once(*resource_type).chain(names.iter().map(|(n, _)| *n));
+            for key in keys {
+                if let ResourceName::Name(text) = key {
+                    if !strings.iter().any(|(s, _)| *s == text) {
+                        strings.push((text, cursor));
+                        cursor += 2 + text.encode_utf16().count() * 2;
+                    }
+                }
+            }
+        }
+
+        cursor = cursor.next_multiple_of(4);
+        let data_entries_offset = cursor;
+        cursor += self.leaves.len() * ResourceDataEntry::SIZE;
+        let mut data_offsets = Vec::with_capacity(self.leaves.len());
+        for leaf in self.leaves.values() {
+            cursor = cursor.next_multiple_of(8);
+            data_offsets.push(cursor);
+            cursor += leaf.data.len();
+        }
+        if cursor > u32::MAX as usize - base_rva as usize {
+            return Err(FileParseError::ValueTooLarge);
+        }
+
+        let mut out = vec![0u8; cursor];
+        let string_offset = |text: &str| {
+            strings
+                .iter()
+                .find(|(s, _)| *s == text)
+                .map(|(_, off)| *off)
+                .expect("string collected above")
+        };
+        let name_field = |key: &ResourceName| match key {
+            ResourceName::Id(id) => *id as u32,
+            ResourceName::Name(text) => 0x8000_0000 | string_offset(text) as u32,
+        };
+
+        write_table(
+            &mut out,
+            0,
+            types.iter().map(|(t, _)| *t),
+            type_tables.iter().map(|off| 0x8000_0000 | *off as u32),
+            &name_field,
+        )?;
+        let mut name_index = 0;
+        let mut leaf_index = 0;
+        for ((_, names), type_table) in types.iter().zip(&type_tables) {
+            let first = name_index;
+            write_table(
+                &mut out,
+                *type_table,
+                names.iter().map(|(n, _)| *n),
+                name_tables[first..first + names.len()]
+                    .iter()
+                    .map(|off| 0x8000_0000 | *off as u32),
+                &name_field,
+            )?;
+            for (_, languages) in names {
+                let language_keys: Vec<ResourceName> = languages
+                    .iter()
+                    .map(|(language, _)| ResourceName::Id(*language))
+                    .collect();
+                let entries = (leaf_index..leaf_index + languages.len())
+                    .map(|i| (data_entries_offset + i * ResourceDataEntry::SIZE) as u32);
+                write_table(
+                    &mut out,
+                    name_tables[name_index],
+                    language_keys.iter(),
+                    entries,
+                    &name_field,
+                )?;
+                name_index += 1;
+                leaf_index += languages.len();
+            }
+        }
+
+        for (text, offset) in &strings {
+            let units: Vec<u16> = text.encode_utf16().collect();
+            let length = u16::try_from(units.len()).map_err(|_| FileParseError::ValueTooLarge)?;
+            out[*offset..*offset + 2].copy_from_slice(&length.to_le_bytes());
+            for (i, unit) in units.iter().enumerate() {
+                let at = offset + 2 + i * 2;
+                out[at..at + 2].copy_from_slice(&unit.to_le_bytes());
+            }
+        }
+
+        for (i, (leaf, data_offset)) in self.leaves.values().zip(&data_offsets).enumerate() {
+            let entry = data_entries_offset + i * ResourceDataEntry::SIZE;
+            let rva = base_rva + *data_offset as u32;
+            out[entry..entry + 4].copy_from_slice(&rva.to_le_bytes());
+            out[entry + 4..entry + 8].copy_from_slice(&(leaf.data.len() as u32).to_le_bytes());
+            out[entry + 8..entry + 12].copy_from_slice(&leaf.code_page.to_le_bytes());
+            out[*data_offset..*data_offset + leaf.data.len()].copy_from_slice(&leaf.data);
+        }
+
+        Ok(out)
+    }
+}
+
+/// Name entries of one type, each with its language leaves.
+type NameLevel<'a> = Vec<(&'a ResourceName, Vec<(u16, &'a ResourceLeaf)>)>;
+
+/// Writes an `IMAGE_RESOURCE_DIRECTORY` and its entries; `keys` must already be sorted.
+fn write_table<'a>(
+    out: &mut [u8],
+    offset: usize,
+    keys: impl Iterator<Item = &'a ResourceName>,
+    targets: impl Iterator<Item = u32>,
+    name_field: &impl Fn(&ResourceName) -> u32,
+) -> Result<(), FileParseError> {
+    let mut named = 0u16;
+    let mut ids = 0u16;
+    let mut cursor = offset + ResourceDirectory::SIZE;
+    for (key, target) in keys.zip(targets) {
+        let counter = match key {
+            ResourceName::Name(_) => &mut named,
+            ResourceName::Id(_) => &mut ids,
+        };
+        *counter = counter
+            .checked_add(1)
+            .ok_or(FileParseError::ValueTooLarge)?;
+        out[cursor..cursor + 4].copy_from_slice(&name_field(key).to_le_bytes());
+        out[cursor + 4..cursor + 8].copy_from_slice(&target.to_le_bytes());
+        cursor += 8;
+    }
+    out[offset + 12..offset + 14].copy_from_slice(&named.to_le_bytes());
+    out[offset + 14..offset + 16].copy_from_slice(&ids.to_le_bytes());
+    Ok(())
+}
+
+impl PE {
+    /// Loads the current resource tree into an editable [`ResourceBuilder`] (empty when absent).
+    pub fn resource_builder(&self) -> Result<ResourceBuilder, FileParseError> {
+        match self.resources()? {
+            Some(tree) => {
+                ResourceBuilder::from_tree(&self.buffer, &tree, |rva| self.rva_to_offset(rva))
+            }
+            None => Ok(ResourceBuilder::new()),
+        }
+    }
+
+    /// Serializes `builder` in place when the current directory starts a section with room for
+    /// it, otherwise into a new `.rsrc` section.
+    pub fn set_resources(&mut self, builder: &ResourceBuilder) -> Result<(), FileParseError> {
+        if !self.optional_header.has_data_directory(header::RESOURCE) {
+            return Err(FileParseError::UnsupportedFeature(
+                "image has no resource data directory slot".to_string(),
+            ));
+        }
+        let current = &self.optional_header.data_directories[header::RESOURCE];
+        let (current_rva, current_size) = (current.virtual_address.value, current.size.value);
+        let section_align = self.optional_header.section_alignment.value;
+        let in_place = self
+            .sections
+            .iter()
+            .position(|section| current_rva != 0 && section.virtual_address.value == current_rva);
+
+        let size = if let Some(index) = in_place {
+            let data = builder.build(current_rva)?;
+            let section = &self.sections[index];
+            let capacity = (section.size_of_raw_data.value as usize).min(
+                section
+                    .virtual_size
+                    .value
+                    .max(1)
+                    .next_multiple_of(section_align.max(1)) as usize,
+            );
+            let start = section.pointer_to_raw_data.value as usize;
+            let raw = self
+                .buffer
+                .get_mut(start..start + capacity)
+                .ok_or(FileParseError::BufferOverflow)?;
+            let old_end = (current_size as usize).min(capacity);
+            let fits = data.len() <= old_end
+                || (data.len() <= capacity && raw[old_end..].iter().all(|&byte| byte == 0));
+            if fits {
+                raw[..old_end].fill(0);
+                raw[..data.len()].copy_from_slice(&data);
+                let section = &mut self.sections[index];
+                if (section.virtual_size.value as usize) < data.len() {
+                    section
+                        .virtual_size
+                        .update(&mut self.buffer, data.len() as u32)?;
+                }
+                Some(data.len())
+            } else {
+                None
+            }
+        } else {
+            None
+        };
+
+        let size = match size {
+            Some(size) => size,
+            None => {
+                let characteristics = section::INITIALIZED_DATA | section::READ;
+                let probe = builder.build(0)?;
+                let header =
+                    self.build_section_header(".rsrc", probe.len() as u32, characteristics)?;
+                let rva = header.virtual_address.value;
+                let data = builder.build(rva)?;
+                let size = data.len();
+                self.append_section_raw(header, data)?;
+                self.sync_data_directory_rva(header::RESOURCE, rva)?;
+                size
+            }
+        };
+        self.sync_data_directory_size(header::RESOURCE, size as u32)?;
+
+        let checksum = self.calc_checksum();
+        self.optional_header
+            .checksum
+            .update(&mut self.buffer, checksum)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,602 @@
+//! Typed decoders for common resource types (icons, manifests, strings, message tables, dialogs).
+//!
+//! Each `PE` accessor walks the type / name / language leaves from [`PE::resource_builder`] and
+//! returns one decoded value per leaf, tagged with its resource name and language ID.
+
+use crate::errors::FileParseError;
+use crate::pe::resource_builder::{ResourceBuilder, ResourceName};
+use crate::pe::PE;
+use crate::utils::{extract_u16, extract_u32};
+
+/// `RT_ICON` resource type ID.
+pub const RT_ICON: u16 = 3;
+/// `RT_DIALOG` resource type ID.
+pub const RT_DIALOG: u16 = 5;
+/// `RT_STRING` resource type ID.
+pub const RT_STRING: u16 = 6;
+/// `RT_MESSAGETABLE` resource type ID.
+pub const RT_MESSAGETABLE: u16 = 11;
+/// `RT_GROUP_ICON` resource type ID.
+pub const RT_GROUP_ICON: u16 = 14;
+/// `RT_MANIFEST` resource type ID.
+pub const RT_MANIFEST: u16 = 24;
+
+/// `MESSAGE_RESOURCE_ENTRY.Flags` value for UTF-16 text.
+pub const MESSAGE_RESOURCE_UNICODE: u16 = 0x0001;
+/// `DS_SETFONT` dialog style: the template carries a font description.
+pub const DS_SETFONT: u32 = 0x0040;
+/// `DS_SHELLFONT` dialog style (`DS_SETFONT | DS_FIXEDSYS`).
+pub const DS_SHELLFONT: u32 = 0x0048;
+
+/// `GRPICONDIRENTRY` — 14 bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IconGroupEntry {
+    /// Width in pixels (0 means 256).
+    pub width: u8,
+    /// Height in pixels (0 means 256).
+    pub height: u8,
+    /// Palette size (0 when no palette).
+    pub color_count: u8,
+    /// Color planes.
+    pub planes: u16,
+    /// Bits per pixel.
+    pub bit_count: u16,
+    /// Size of the `RT_ICON` image in bytes.
+    pub bytes_in_res: u32,
+    /// `RT_ICON` resource ID holding the image.
+    pub id: u16,
+}
+
+/// `RT_GROUP_ICON` resource reassembled into an `.ico` file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IconGroup {
+    /// Resource name of the group.
+    pub name: ResourceName,
+    /// Language ID.
+    pub language: u16,
+    /// Group directory entries in file order.
+    pub entries: Vec<IconGroupEntry>,
+    /// `ICONDIR` + `ICONDIRENTRY` table + image data, ready to write as `.ico`.
+    pub ico: Vec<u8>,
+}
+
+/// `RT_MANIFEST` resource.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Manifest {
+    /// Resource name (1 for executables, 2 for DLLs by convention).
+    pub name: ResourceName,
+    /// Language ID.
+    pub language: u16,
+    /// Manifest XML (UTF-8, BOM stripped).
+    pub text: String,
+}
+
+/// One string from an `RT_STRING` block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StringResource {
+    /// String ID (`(block - 1) * 16 + index`).
+    pub id: u32,
+    /// Language ID.
+    pub language: u16,
+    /// String value.
+    pub value: String,
+}
+
+/// One `MESSAGE_RESOURCE_ENTRY`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    /// Message ID.
+    pub id: u32,
+    /// `Flags` (`MESSAGE_RESOURCE_UNICODE` for UTF-16 text).
+    pub flags: u16,
+    /// Message text with trailing NULs removed.
+    pub text: String,
+}
+
+/// `RT_MESSAGETABLE` resource.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageTable {
+    /// Resource name.
+    pub name: ResourceName,
+    /// Language ID.
+    pub language: u16,
+    /// Messages in block order.
+    pub messages: Vec<Message>,
+}
+
+/// Font description of a `DS_SETFONT` dialog.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DialogFont {
+    /// Point size.
+    pub point_size: u16,
+    /// Font weight (`DLGTEMPLATEEX` only; 0 otherwise).
+    pub weight: u16,
+    /// Italic flag (`DLGTEMPLATEEX` only).
+    pub italic: bool,
+    /// Character set (`DLGTEMPLATEEX` only).
+    pub charset: u8,
+    /// Typeface name.
+    pub typeface: String,
+}
+
+/// One control in a dialog template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DialogItem {
+    /// Help context ID (`DLGITEMTEMPLATEEX` only).
+    pub help_id: u32,
+    /// Window style.
+    pub style: u32,
+    /// Extended window style.
+    pub ex_style: u32,
+    /// X position in dialog units.
+    pub x: i16,
+    /// Y position in dialog units.
+    pub y: i16,
+    /// Width in dialog units.
+    pub cx: i16,
+    /// Height in dialog units.
+    pub cy: i16,
+    /// Control ID.
+    pub id: u32,
+    /// Window class (predefined atom or class name).
+    pub class: Option<ResourceName>,
+    /// Initial text (string or resource ordinal).
+    pub title: Option<ResourceName>,
+    /// Creation data passed to the control.
+    pub extra: Vec<u8>,
+}
+
+/// Decoded `DLGTEMPLATE` or `DLGTEMPLATEEX`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DialogTemplate {
+    /// `true` for `DLGTEMPLATEEX`.
+    pub extended: bool,
+    /// Help context ID (`DLGTEMPLATEEX` only).
+    pub help_id: u32,
+    /// Window style.
+    pub style: u32,
+    /// Extended window style.
+    pub ex_style: u32,
+    /// X position in dialog units.
+    pub x: i16,
+    /// Y position in dialog units.
+    pub y: i16,
+    /// Width in dialog units.
+    pub cx: i16,
+    /// Height in dialog units.
+    pub cy: i16,
+    /// Menu resource.
+    pub menu: Option<ResourceName>,
+    /// Window class.
+    pub class: Option<ResourceName>,
+    /// Caption.
+    pub title: String,
+    /// Font, when the style requests one.
+    pub font: Option<DialogFont>,
+    /// Controls in template order.
+    pub items: Vec<DialogItem>,
+}
+
+/// `RT_DIALOG` resource.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dialog {
+    /// Resource name.
+    pub name: ResourceName,
+    /// Language ID.
+    pub language: u16,
+    /// Decoded template.
+    pub template: DialogTemplate,
+}
+
+impl IconGroupEntry {
+    /// Size of `GRPICONDIRENTRY` in bytes.
+    pub const SIZE: usize = 14;
+}
+
+impl IconGroup {
+    /// Parses a `GRPICONDIR` and rebuilds an `.ico` from the `RT_ICON` images `icon` returns.
+    pub fn parse<'a>(
+        name: ResourceName,
+        language: u16,
+        data: &[u8],
+        icon: impl Fn(u16) -> Option<&'a [u8]>,
+    ) -> Result<Self, FileParseError> {
+        if extract_u16(data, 2)? != 1 {
+            return Err(FileParseError::InvalidFileFormat);
+        }
+        let count = extract_u16(data, 4)? as usize;
+        let mut entries = Vec::with_capacity(count);
+        for i in 0..count {
+            let off = 6 + i * IconGroupEntry::SIZE;
+            let bytes = data
+                .get(off..off + IconGroupEntry::SIZE)
+                .ok_or(FileParseError::BufferOverflow)?;
+            entries.push(IconGroupEntry {
+                width: bytes[0],
+                height: bytes[1],
+                color_count: bytes[2],
+                planes: extract_u16(bytes, 4)?,
+                bit_count: extract_u16(bytes, 6)?,
+                bytes_in_res: extract_u32(bytes, 8)?,
+                id: extract_u16(bytes, 12)?,
+            });
+        }
+
+        let mut images = Vec::with_capacity(count);
+        for entry in &entries {
+            let image = icon(entry.id).ok_or(FileParseError::InvalidFileFormat)?;
+            images.push(image);
+        }
+
+        let mut ico = Vec::new();
+        ico.extend_from_slice(&0u16.to_le_bytes());
+        ico.extend_from_slice(&1u16.to_le_bytes());
+        ico.extend_from_slice(&(count as u16).to_le_bytes());
+        let mut image_offset = 6 + count * 16;
+        for (entry, image) in entries.iter().zip(&images) {
+            ico.extend_from_slice(&[entry.width, entry.height, entry.color_count, 0]);
+            ico.extend_from_slice(&entry.planes.to_le_bytes());
+            ico.extend_from_slice(&entry.bit_count.to_le_bytes());
+            ico.extend_from_slice(&(image.len() as u32).to_le_bytes());
+            ico.extend_from_slice(&(image_offset as u32).to_le_bytes());
+            image_offset += image.len();
+        }
+        for image in images {
+            ico.extend_from_slice(image);
+        }
+
+        Ok(IconGroup {
+            name,
+            language,
+            entries,
+            ico,
+        })
+    }
+}
+
+impl Manifest {
+    /// Decodes manifest bytes as UTF-8, dropping a leading byte order mark.
+    pub fn parse(name: ResourceName, language: u16, data: &[u8]) -> Self {
+        let data = data.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(data);
+        Manifest {
+            name,
+            language,
+            text: String::from_utf8_lossy(data).into_owned(),
+        }
+    }
+}
+
+impl StringResource {
+    /// Decodes the 16 length-prefixed strings of `RT_STRING` block `block_id`, skipping empty slots.
+    pub fn parse_block(
+        block_id: u16,
+        language: u16,
+        data: &[u8],
+    ) -> Result<Vec<Self>, FileParseError> {
+        if block_id == 0 {
+            return Err(FileParseError::InvalidFileFormat);
+        }
+        let mut strings = Vec::new();
+        let mut cursor = 0usize;
+        for index in 0..16u32 {
+            if cursor >= data.len() {
+                break;
+            }
+            let length = extract_u16(data, cursor)? as usize;
+            let (value, _) = read_utf16(data, cursor + 2, length)?;
+            cursor += 2 + length * 2;
+            if length > 0 {
+                strings.push(StringResource {
+                    id: (block_id as u32 - 1) * 16 + index,
+                    language,
+                    value,
+                });
+            }
+        }
+        Ok(strings)
+    }
+}
+
+impl MessageTable {
+    /// Parses `MESSAGE_RESOURCE_DATA` and its entry blocks.
+    pub fn parse(name: ResourceName, language: u16, data: &[u8]) -> Result<Self, FileParseError> {
+        let block_count = extract_u32(data, 0)? as usize;
+        let mut messages = Vec::new();
+        for block in 0..block_count {
+            let off = 4 + block * 12;
+            let low_id = extract_u32(data, off)?;
+            let high_id = extract_u32(data, off + 4)?;
+            let mut cursor = extract_u32(data, off + 8)? as usize;
+            if high_id < low_id {
+                return Err(FileParseError::InvalidFileFormat);
+            }
+            for id in low_id..=high_id {
+                let length = extract_u16(data, cursor)? as usize;
+                let flags = extract_u16(data, cursor + 2)?;
+                if length < 4 {
+                    return Err(FileParseError::InvalidFileFormat);
+                }
+                let bytes = data
+                    .get(cursor + 4..cursor + length)
+                    .ok_or(FileParseError::BufferOverflow)?;
+                let text = if flags & MESSAGE_RESOURCE_UNICODE != 0 {
+                    read_utf16(bytes, 0, bytes.len() / 2)?.0
+                } else {
+                    String::from_utf8_lossy(bytes).into_owned()
+                };
+                messages.push(Message {
+                    id,
+                    flags,
+                    text: text.trim_end_matches('\0').to_string(),
+                });
+                cursor += length;
+            }
+        }
+        Ok(MessageTable {
+            name,
+            language,
+            messages,
+        })
+    }
+}
+
+impl DialogTemplate {
+    /// Parses a `DLGTEMPLATE` or `DLGTEMPLATEEX` and its controls.
+    pub fn parse(data: &[u8]) -> Result<Self, FileParseError> {
+        let mut reader = Reader { data, pos: 0 };
+        let extended = extract_u16(data, 0)? == 1 && extract_u16(data, 2)? == 0xFFFF;
+
+        let (help_id, style, ex_style) = if extended {
+            reader.pos = 4;
+            let help_id = reader.u32()?;
+            let ex_style = reader.u32()?;
+            (help_id, reader.u32()?, ex_style)
+        } else {
+            let style = reader.u32()?;
+            (0, style, reader.u32()?)
+        };
+        let count = reader.u16()?;
+        let (x, y, cx, cy) = (reader.i16()?, reader.i16()?, reader.i16()?, reader.i16()?);
+        let menu = reader.sz_or_ord()?;
+        let class = reader.sz_or_ord()?;
+        let title = reader.string()?;
+
+        let font = if style & DS_SETFONT != 0 {
+            let point_size = reader.u16()?;
+            let (weight, italic, charset) = if extended {
+                (reader.u16()?, reader.u8()? != 0, reader.u8()?)
+            } else {
+                (0, false, 0)
+            };
+            Some(DialogFont {
+                point_size,
+                weight,
+                italic,
+                charset,
+                typeface: reader.string()?,
+            })
+        } else {
+            None
+        };
+
+        let mut items = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            reader.align4();
+            let (help_id, style, ex_style) = if extended {
+                let help_id = reader.u32()?;
+                let ex_style = reader.u32()?;
+                (help_id, reader.u32()?, ex_style)
+            } else {
+                let style = reader.u32()?;
+                (0, style, reader.u32()?)
+            };
+            let (x, y, cx, cy) = (reader.i16()?, reader.i16()?, reader.i16()?, reader.i16()?);
+            let id = if extended {
+                reader.u32()?
+            } else {
+                reader.u16()? as u32
+            };
+            let class = reader.sz_or_ord()?;
+            let title = reader.sz_or_ord()?;
+            let extra_len = reader.u16()? as usize;
+            let extra = reader.bytes(extra_len)?.to_vec();
+            items.push(DialogItem {
+                help_id,
+                style,
+                ex_style,
+                x,
+                y,
+                cx,
+                cy,
+                id,
+                class,
+                title,
+                extra,
+            });
+        }
+
+        Ok(DialogTemplate {
+            extended,
+            help_id,
+            style,
+            ex_style,
+            x,
+            y,
+            cx,
+            cy,
+            menu,
+            class,
+            title,
+            font,
+            items,
+        })
+    }
+}
+
+impl PE {
+    /// Decodes every `RT_GROUP_ICON` resource into an `.ico` image.
+    pub fn icon_groups(&self) -> Result<Vec<IconGroup>, FileParseError> {
+        let builder = self.resource_builder()?;
+        leaves(&builder, RT_GROUP_ICON)
+            .map(|(name, language, data)| {
+                IconGroup::parse(name.clone(), language, data, |id| {
+                    let icon = ResourceName::Id(RT_ICON);
+                    builder
+                        .get(&icon, &ResourceName::Id(id), language)
+                        .or_else(|| {
+                            leaves(&builder, RT_ICON)
+                                .find(|(name, _, _)| **name == ResourceName::Id(id))
+                                .map(|(_, _, data)| data)
+                        })
+                })
+            })
+            .collect()
+    }
+
+    /// Decodes every `RT_MANIFEST` resource.
+    pub fn manifests(&self) -> Result<Vec<Manifest>, FileParseError> {
+        let builder = self.resource_builder()?;
+        Ok(leaves(&builder, RT_MANIFEST)
+            .map(|(name, language, data)| Manifest::parse(name.clone(), language, data))
+            .collect())
+    }
+
+    /// Resolves every `RT_STRING` block into `(id, string)` pairs per language.
+    pub fn string_resources(&self) -> Result<Vec<StringResource>, FileParseError> {
+        let builder = self.resource_builder()?;
+        let mut strings = Vec::new();
+        for (name, language, data) in leaves(&builder, RT_STRING) {
+            let ResourceName::Id(block_id) = name else {
+                return Err(FileParseError::InvalidFileFormat);
+            };
+            strings.extend(StringResource::parse_block(*block_id, language, data)?);
+        }
+        Ok(strings)
+    }
+
+    /// Decodes every `RT_MESSAGETABLE` resource.
+    pub fn message_tables(&self) -> Result<Vec<MessageTable>, FileParseError> {
+        let builder = self.resource_builder()?;
+        leaves(&builder, RT_MESSAGETABLE)
+            .map(|(name, language, data)| MessageTable::parse(name.clone(), language, data))
+            .collect()
+    }
+
+    /// Decodes every `RT_DIALOG` resource.
+    pub fn dialogs(&self) -> Result<Vec<Dialog>, FileParseError> {
+        let builder = self.resource_builder()?;
+        leaves(&builder, RT_DIALOG)
+            .map(|(name, language, data)| {
+                Ok(Dialog {
+                    name: name.clone(),
+                    language,
+                    template: DialogTemplate::parse(data)?,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Leaves of resource type `type_id` as `(name, language, bytes)`.
+fn leaves(
+    builder: &ResourceBuilder,
+    type_id: u16,
+) -> impl Iterator<Item = (&ResourceName, u16, &[u8])> {
+    builder
+        .leaves
+        .iter()
+        .filter(move |((resource_type, _, _), _)| *resource_type == ResourceName::Id(type_id))
+        .map(|((_, name, language), leaf)| (name, *language, leaf.data.as_slice()))
+}
+
+/// Reads `length` UTF-16 units at `offset`, returning the string and the offset past it.
+fn read_utf16(
+    data: &[u8],
+    offset: usize,
+    length: usize,
+) -> Result<(String, usize), FileParseError> {
+    let end = offset
+        .checked_add(length * 2)
+        .ok_or(FileParseError::BufferOverflow)?;
+    let bytes = data
+        .get(offset..end)
+        .ok_or(FileParseError::BufferOverflow)?;
+    let units: Vec<u16> = bytes
+        .as_chunks::<2>()
+        .0
+        .iter()
+        .map(|chunk| u16::from_le_bytes(*chunk))
+        .collect();
+    Ok((String::from_utf16_lossy(&units), end))
+}
+
+/// Little-endian cursor over a dialog template.
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], FileParseError> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .ok_or(FileParseError::BufferOverflow)?;
+        let bytes = self
+            .data
+            .get(self.pos..end)
+            .ok_or(FileParseError::BufferOverflow)?;
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, FileParseError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, FileParseError> {
+        let value = extract_u16(self.data, self.pos)?;
+        self.pos += 2;
+        Ok(value)
+    }
+
+    fn i16(&mut self) -> Result<i16, FileParseError> {
+        Ok(self.u16()? as i16)
+    }
+
+    fn u32(&mut self) -> Result<u32, FileParseError> {
+        let value = extract_u32(self.data, self.pos)?;
+        self.pos += 4;
+        Ok(value)
+    }
+
+    fn align4(&mut self) {
+        self.pos = self.pos.next_multiple_of(4);
+    }
+
+    /// NUL-terminated UTF-16 string.
+    fn string(&mut self) -> Result<String, FileParseError> {
+        let mut units = Vec::new();
+        loop {
+            match self.u16()? {
+                0 => return Ok(String::from_utf16_lossy(&units)),
+                unit => units.push(unit),
+            }
+        }
+    }
+
+    /// `sz_Or_Ord`: empty, `0xFFFF` + ordinal, or a NUL-terminated string.
+    fn sz_or_ord(&mut self) -> Result<Option<ResourceName>, FileParseError> {
+        match extract_u16(self.data, self.pos)? {
+            0 => {
+                self.pos += 2;
+                Ok(None)
+            }
+            0xFFFF => {
+                self.pos += 2;
+                Ok(Some(ResourceName::Id(self.u16()?)))
+            }
+            _ => Ok(Some(ResourceName::Name(self.string()?))),
+        }
+    }
+}
@@ -1,14 +1,25 @@
 //! Rich header (linker tool metadata between the DOS stub and PE signature).
+//!
+//! On disk the header is `DanS`, three zero dwords and the `(comp_id, count)` pairs, all XORed
+//! with a key, followed by the clear `Rich` marker and the key itself. The key is a checksum of
+//! the DOS header and stub (with `e_lfanew` skipped) and of every entry.
 
 use crate::errors::FileParseError;
+use crate::hash::{to_hex, Md5};
+use crate::pe::PE;
 use crate::utils::extract_u32;
 
 /// `DanS` signature (`0x536e6144`).
 const DANS_MAGIC: u32 = 0x536e_6144;
 /// `Rich` signature (`0x68636952`).
 const RICH_MAGIC: u32 = 0x6863_6952;
+/// File offset of `DanS` in headers written by the MSVC linker.
+const DEFAULT_OFFSET: usize = 0x80;
+/// `DanS` dword plus three zero padding dwords.
+const PREFIX_SIZE: usize = 16;
 
 /// One `(product_id, build_id)` pair and its occurrence count from the Rich header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct RichEntry {
     /// Tool product id (high 16 bits of the decoded tool dword).
     pub product_id: u16,
@@ -22,40 +33,76 @@ pub struct RichEntry {
 pub struct RichHeader {
     /// Absolute file offset of the encrypted `DanS` dword.
     pub offset: usize,
+    /// File offset just past the key dword that follows the `Rich` marker.
+    pub end: usize,
     /// XOR key used to encrypt Rich header dwords.
     pub xor_key: u32,
     /// Decoded tool entries in file order.
     pub entries: Vec<RichEntry>,
 }
 
+impl RichEntry {
+    /// Tool dword (`@comp.id`): product id in the high half, build id in the low half.
+    pub fn comp_id(&self) -> u32 {
+        ((self.product_id as u32) << 16) | self.build_id as u32
+    }
+}
+
 impl RichHeader {
     /// Parses the Rich header in `buffer` when present between the DOS stub and `pe_offset`.
     ///
-    /// Returns `Ok(None)` when no Rich header is found.
+    /// The `Rich` marker is normally stored in clear; a marker encrypted with the key is also
+    /// accepted. Returns `Ok(None)` when no Rich header is found.
     pub fn parse(buffer: &[u8], pe_offset: usize) -> Result<Option<Self>, FileParseError> {
-        if pe_offset < 0x80 + 16 || buffer.len() < 0x80 + 16 {
+        if pe_offset < DEFAULT_OFFSET + 16 || buffer.len() < DEFAULT_OFFSET + 16 {
             return Ok(None);
         }
 
-        let start = 0x80usize;
+        let start = DEFAULT_OFFSET;
         let encrypted_dans = extract_u32(buffer, start)?;
-        let xor_key = encrypted_dans ^ DANS_MAGIC;
-        if encrypted_dans ^ xor_key != DANS_MAGIC {
-            return Ok(None);
+        let end = pe_offset.min(buffer.len()).saturating_sub(8);
+
+        let mut marker = None;
+        let mut cursor = start + 4;
+        while cursor <= end {
+            if extract_u32(buffer, cursor)? == RICH_MAGIC {
+                let key = extract_u32(buffer, cursor + 4)?;
+                if encrypted_dans ^ key == DANS_MAGIC {
+                    marker = Some((cursor, key));
+                    break;
+                }
+            }
+            cursor += 4;
         }
+        let (marker, xor_key) = match marker {
+            Some(found) => found,
+            None => {
+                let xor_key = encrypted_dans ^ DANS_MAGIC;
+                let mut cursor = start + 4;
+                loop {
+                    if cursor > end {
+                        return Ok(None);
+                    }
+                    if extract_u32(buffer, cursor)? ^ xor_key == RICH_MAGIC {
+                        break (cursor, xor_key);
+                    }
+                    cursor += 4;
+                }
+            }
+        };
 
-        let mut entries = Vec::new();
         let mut cursor = start + 4;
-        let end = pe_offset.saturating_sub(8);
+        while cursor < start + PREFIX_SIZE
+            && cursor < marker
+            && extract_u32(buffer, cursor)? == xor_key
+        {
+            cursor += 4;
+        }
 
-        while cursor + 8 <= end {
+        let mut entries = Vec::new();
+        while cursor + 8 <= marker {
             let tool = extract_u32(buffer, cursor)? ^ xor_key;
             let count = extract_u32(buffer, cursor + 4)? ^ xor_key;
-
-            if tool == RICH_MAGIC {
-                break;
-            }
-
             entries.push(RichEntry {
                 product_id: (tool >> 16) as u16,
                 build_id: tool as u16,
@@ -70,8 +117,163 @@ impl RichHeader {
 
         Ok(Some(RichHeader {
             offset: start,
+            end: marker + 8,
             xor_key,
             entries,
         }))
     }
+
+    /// Recomputes the XOR key from the bytes before `offset` and the decoded entries.
+    pub fn computed_checksum(&self, buffer: &[u8]) -> Result<u32, FileParseError> {
+        let prefix = buffer
+            .get(..self.offset)
+            .ok_or(FileParseError::BufferOverflow)?;
+        Ok(checksum(prefix, &self.entries))
+    }
+
+    /// Decoded bytes from `DanS` up to the `Rich` marker (the input of the RichPE hash).
+    pub fn clear_data(&self, buffer: &[u8]) -> Result<Vec<u8>, FileParseError> {
+        let encoded = buffer
+            .get(self.offset..self.end - 8)
+            .ok_or(FileParseError::BufferOverflow)?;
+        let mut clear = Vec::with_capacity(encoded.len());
+        for word in encoded.as_chunks::<4>().0 {
+            clear.extend_from_slice(&(u32::from_le_bytes(*word) ^ self.xor_key).to_le_bytes());
+        }
+        Ok(clear)
+    }
+
+    /// Returns `true` when the stored key matches [`RichHeader::computed_checksum`].
+    pub fn is_checksum_valid(&self, buffer: &[u8]) -> Result<bool, FileParseError> {
+        Ok(self.computed_checksum(buffer)? == self.xor_key)
+    }
+
+    /// Encodes `entries` as a Rich header placed right after `prefix` (the DOS header and stub),
+    /// keyed with the checksum of both.
+    pub fn encode(prefix: &[u8], entries: &[RichEntry]) -> Vec<u8> {
+        let key = checksum(prefix, entries);
+        let mut out = Vec::with_capacity(PREFIX_SIZE + entries.len() * 8 + 8);
+        out.extend_from_slice(&(DANS_MAGIC ^ key).to_le_bytes());
+        for _ in 0..3 {
+            out.extend_from_slice(&key.to_le_bytes());
+        }
+        for entry in entries {
+            out.extend_from_slice(&(entry.comp_id() ^ key).to_le_bytes());
+            out.extend_from_slice(&(entry.count ^ key).to_le_bytes());
+        }
+        out.extend_from_slice(&RICH_MAGIC.to_le_bytes());
+        out.extend_from_slice(&key.to_le_bytes());
+        out
+    }
+}
+
+impl PE {
+    /// Computes the RichPE hash (lowercase hex MD5 of [`RichHeader::clear_data`]); `Ok(None)` when
+    /// no Rich header is present.
+    pub fn rich_hash(&self) -> Result<Option<String>, FileParseError> {
+        let Some(rich) = self.rich_header()? else {
+            return Ok(None);
+        };
+        Ok(Some(to_hex(&Md5::digest(&rich.clear_data(&self.buffer)?))))
+    }
+
+    /// Zeroes the Rich header; the DOS stub and `e_lfanew` are left unchanged.
+    ///
+    /// Does nothing when no Rich header is present.
+    pub fn remove_rich_header(&mut self) -> Result<(), FileParseError> {
+        let Some(rich) = self.rich_header()? else {
+            return Ok(());
+        };
+        self.buffer[rich.offset..rich.end].fill(0);
+        self.sync_layout()
+    }
+
+    /// Replaces the Rich header entries, re-encoding them with a freshly computed key.
+    ///
+    /// Images without a Rich header get one after the standard 64-byte DOS stub, which requires
+    /// the bytes from there up to the PE signature to be zero. When the gap before the PE
+    /// signature is too small, the PE headers and section table move down into zero slack left
+    /// before the first section's raw data.
+    pub fn set_rich_entries(&mut self, entries: &[RichEntry]) -> Result<(), FileParseError> {
+        let pe_offset = self.dos_header.e_lfanew.value as usize;
+        if pe_offset < DEFAULT_OFFSET {
+            return Err(FileParseError::UnsupportedFeature(
+                "DOS stub shorter than the Rich header offset".to_string(),
+            ));
+        }
+        let offset = match self.rich_header()? {
+            Some(rich) => rich.offset,
+            None if self.buffer[DEFAULT_OFFSET..pe_offset]
+                .iter()
+                .any(|&byte| byte != 0) =>
+            {
+                return Err(FileParseError::UnsupportedFeature(
+                    "DOS stub extends past the Rich header offset".to_string(),
+                ));
+            }
+            None => DEFAULT_OFFSET,
+        };
+        let encoded = RichHeader::encode(&self.buffer[..offset], entries);
+        let end = offset + encoded.len();
+        if end > pe_offset {
+            self.move_nt_headers((end - pe_offset).next_multiple_of(8))?;
+        }
+        let pe_offset = self.dos_header.e_lfanew.value as usize;
+        self.buffer[offset..pe_offset].fill(0);
+        self.buffer[offset..end].copy_from_slice(&encoded);
+        self.sync_layout()
+    }
+
+    /// Moves the PE signature, headers and section table `delta` bytes towards the first
+    /// section's raw data and updates `e_lfanew`.
+    fn move_nt_headers(&mut self, delta: usize) -> Result<(), FileParseError> {
+        let start = self.dos_header.e_lfanew.value as usize;
+        let table_end = match self.sections.last() {
+            Some(section) => section.characteristics.offset + section.characteristics.size,
+            None => {
+                self.optional_header.magic.offset
+                    + self.coff_header.size_of_optional_header.value as usize
+            }
+        };
+        let first_raw = self
+            .sections
+            .iter()
+            .filter(|section| section.size_of_raw_data.value != 0)
+            .map(|section| section.pointer_to_raw_data.value as usize)
+            .min()
+            .unwrap_or(self.buffer.len());
+        let slack = self
+            .buffer
+            .get(table_end..table_end + delta)
+            .ok_or(FileParseError::BufferOverflow)?;
+        if table_end + delta > first_raw || slack.iter().any(|&byte| byte != 0) {
+            return Err(FileParseError::UnsupportedFeature(
+                "no zero header slack to grow the Rich header".to_string(),
+            ));
+        }
+
+        self.buffer.copy_within(start..table_end, start + delta);
+        self.buffer[start..start + delta].fill(0);
+        let lfanew = u32::try_from(start + delta).map_err(|_| FileParseError::ValueTooLarge)?;
+        self.dos_header.e_lfanew.update(&mut self.buffer, lfanew)?;
+        let buffer = std::mem::take(&mut self.buffer);
+        *self = PE::from_buffer(buffer)?;
+        Ok(())
+    }
+}
+
+/// Rich header key: `offset` plus each byte of `prefix` (skipping `e_lfanew`) rotated left by its
+/// index, plus each tool dword rotated left by its count.
+fn checksum(prefix: &[u8], entries: &[RichEntry]) -> u32 {
+    let mut checksum = prefix.len() as u32;
+    for (index, &byte) in prefix.iter().enumerate() {
+        if (0x3c..0x40).contains(&index) {
+            continue;
+        }
+        checksum = checksum.wrapping_add((byte as u32).rotate_left(index as u32));
+    }
+    for entry in entries {
+        checksum = checksum.wrapping_add(entry.comp_id().rotate_left(entry.count));
+    }
+    checksum
 }
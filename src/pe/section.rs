@@ -38,6 +38,8 @@ impl Characteristics {
 pub const CODE: u32 = 0x00000020;
 /// `IMAGE_SCN_CNT_INITIALIZED_DATA`.
 pub const INITIALIZED_DATA: u32 = 0x00000040;
+/// `IMAGE_SCN_MEM_DISCARDABLE`.
+pub const DISCARDABLE: u32 = 0x02000000;
 /// `IMAGE_SCN_MEM_EXECUTE`.
 pub const EXECUTE: u32 = 0x20000000;
 /// `IMAGE_SCN_MEM_READ`.
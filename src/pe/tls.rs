@@ -1,10 +1,17 @@
 //! Thread local storage directory (`IMAGE_TLS_DIRECTORY32` / `IMAGE_TLS_DIRECTORY64`).
+//!
+//! `AddressOfCallBacks` holds the VA of a null-terminated array of callback VAs, called by the
+//! loader before the entry point.
 
 use crate::errors::FileParseError;
 use crate::field::Field;
-use crate::pe::header::{ImageBase, PEType};
+use crate::pe::header::{self, ImageBase, PEType};
+use crate::pe::{section, PE};
 use crate::utils::{extract_u32, extract_u64};
 
+/// Name of sections created for new TLS directories and moved callback arrays.
+const TLS_SECTION: &str = ".tls2";
+
 /// TLS directory fields (PE32 or PE32+ layout).
 pub struct TlsDirectory {
     /// Start VA of the TLS template (`StartAddressOfRawData`).
@@ -94,3 +101,203 @@ impl TlsDirectory {
         }
     }
 }
+
+impl PE {
+    /// Walks the `AddressOfCallBacks` array; each field holds a callback VA.
+    ///
+    /// Returns an empty list when there is no TLS directory or callback array.
+    pub fn tls_callbacks(&self) -> Result<Vec<Field<ImageBase>>, FileParseError> {
+        let Some(tls) = self.tls()? else {
+            return Ok(Vec::new());
+        };
+        let array = tls.address_of_callbacks.value.value();
+        if array == 0 {
+            return Ok(Vec::new());
+        }
+        let pe_type = self.optional_header.pe_type()?;
+        let mut offset = self.rva_to_offset(self.va_to_rva(array)?)?;
+        let mut callbacks = Vec::new();
+        loop {
+            let callback = match pe_type {
+                PEType::PE32 => Field::new(
+                    ImageBase::Base32(extract_u32(&self.buffer, offset)?),
+                    offset,
+                    4,
+                ),
+                PEType::PE32Plus => Field::new(
+                    ImageBase::Base64(extract_u64(&self.buffer, offset)?),
+                    offset,
+                    8,
+                ),
+            };
+            if callback.value.value() == 0 {
+                break;
+            }
+            offset += callback.size;
+            callbacks.push(callback);
+        }
+        Ok(callbacks)
+    }
+
+    /// Registers the functions at `rvas` as TLS callbacks.
+    ///
+    /// Images without a TLS directory get a `.tls2` section holding a directory with an empty
+    /// template, the index slot and the callback array. Otherwise callbacks are appended in place
+    /// when the array ends its section's virtual data and the raw data has room for the growth;
+    /// if not, the array moves to a `.tls2` section. Base relocations are added for every new
+    /// pointer unless relocations were stripped.
+    pub fn add_tls_callbacks(&mut self, rvas: &[u32]) -> Result<(), FileParseError> {
+        if rvas.is_empty() {
+            return Ok(());
+        }
+        if !self.optional_header.has_data_directory(header::TLS) {
+            return Err(FileParseError::UnsupportedFeature(
+                "image has no TLS data directory slot".to_string(),
+            ));
+        }
+        let pe_type = self.optional_header.pe_type()?;
+        let pointer: usize = match pe_type {
+            PEType::PE32 => 4,
+            PEType::PE32Plus => 8,
+        };
+        let base = self.optional_header.image_base.value.value();
+        let to_va = |rva: u32| match pe_type {
+            PEType::PE32 => u32::try_from(base + rva as u64)
+                .map(ImageBase::Base32)
+                .map_err(|_| FileParseError::ValueTooLarge),
+            PEType::PE32Plus => Ok(ImageBase::Base64(base + rva as u64)),
+        };
+        let encode = |value: ImageBase| match value {
+            ImageBase::Base32(value) => value.to_le_bytes().to_vec(),
+            ImageBase::Base64(value) => value.to_le_bytes().to_vec(),
+        };
+
+        let mut relocations = Vec::new();
+        match self.tls()? {
+            None => {
+                let directory_size = pointer * 4 + 8;
+                let index = directory_size;
+                let array = (index + 4).next_multiple_of(pointer);
+                let mut data = vec![0u8; array + (rvas.len() + 1) * pointer];
+                let rva = self.append_tls_section(data.len())?;
+                let index_va = encode(to_va(rva + index as u32)?);
+                data[..pointer].copy_from_slice(&index_va);
+                data[pointer..pointer * 2].copy_from_slice(&index_va);
+                data[pointer * 2..pointer * 3].copy_from_slice(&index_va);
+                data[pointer * 3..pointer * 4].copy_from_slice(&encode(to_va(rva + array as u32)?));
+                for (slot, &callback) in rvas.iter().enumerate() {
+                    let at = array + slot * pointer;
+                    data[at..at + pointer].copy_from_slice(&encode(to_va(callback)?));
+                    relocations.push(rva + at as u32);
+                }
+                relocations.extend((0..4).map(|field| rva + (field * pointer) as u32));
+                let offset = self.rva_to_offset(rva)?;
+                self.buffer[offset..offset + data.len()].copy_from_slice(&data);
+                self.sync_data_directory_rva(header::TLS, rva)?;
+                self.sync_data_directory_size(header::TLS, directory_size as u32)?;
+            }
+            Some(mut tls) => {
+                let existing = self.tls_callbacks()?;
+                let array_va = tls.address_of_callbacks.value.value();
+                let array_rva = if array_va == 0 {
+                    None
+                } else {
+                    Some(self.va_to_rva(array_va)?)
+                };
+                let total = existing.len() + rvas.len();
+                let in_place = array_rva
+                    .filter(|&rva| self.tls_array_has_room(rva, existing.len(), total, pointer));
+
+                let (rva, first_new) = match in_place {
+                    Some(rva) => (rva, existing.len()),
+                    None => {
+                        let rva = self.append_tls_section((total + 1) * pointer)?;
+                        let offset = self.rva_to_offset(rva)?;
+                        for (slot, callback) in existing.iter().enumerate() {
+                            let at = offset + slot * pointer;
+                            self.buffer[at..at + pointer].copy_from_slice(&encode(callback.value));
+                        }
+                        tls.address_of_callbacks
+                            .update(&mut self.buffer, to_va(rva)?)?;
+                        (rva, 0)
+                    }
+                };
+                let offset = self.rva_to_offset(rva)?;
+                for (slot, &callback) in rvas.iter().enumerate() {
+                    let at = offset + (existing.len() + slot) * pointer;
+                    self.buffer[at..at + pointer].copy_from_slice(&encode(to_va(callback)?));
+                }
+                let end = offset + total * pointer;
+                self.buffer[end..end + pointer].fill(0);
+                relocations.extend((first_new..total).map(|slot| rva + (slot * pointer) as u32));
+
+                if in_place.is_some() {
+                    let index = self.section_index_for_rva(rva)?;
+                    let used = rva + ((total + 1) * pointer) as u32;
+                    let section = &mut self.sections[index];
+                    let virtual_size = used - section.virtual_address.value;
+                    section
+                        .virtual_size
+                        .update(&mut self.buffer, virtual_size)?;
+                }
+            }
+        }
+
+        self.add_pointer_relocations(&relocations)?;
+        self.sync_layout()
+    }
+
+    /// `true` when the callback array at `rva` ends its section's virtual data and the section's
+    /// raw data (below the next section) can hold `total` callbacks plus the terminator.
+    fn tls_array_has_room(&self, rva: u32, existing: usize, total: usize, pointer: usize) -> bool {
+        let Ok(index) = self.section_index_for_rva(rva) else {
+            return false;
+        };
+        let section = &self.sections[index];
+        let start = section.virtual_address.value as u64;
+        let array_end = rva as u64 + ((existing + 1) * pointer) as u64;
+        let needed_end = rva as u64 + ((total + 1) * pointer) as u64;
+        let limit = self
+            .sections
+            .get(index + 1)
+            .map(|next| next.virtual_address.value as u64)
+            .unwrap_or(u64::MAX)
+            .min(start + section.size_of_raw_data.value as u64);
+        array_end == start + section.virtual_size.value as u64 && needed_end <= limit
+    }
+
+    /// Appends a writable `.tls2` section of `size` bytes whose virtual size is exactly `size`,
+    /// so a callback array at its end can later grow in place. Returns its RVA.
+    fn append_tls_section(&mut self, size: usize) -> Result<u32, FileParseError> {
+        let characteristics = section::INITIALIZED_DATA | section::READ | section::WRITE;
+        let size = u32::try_from(size).map_err(|_| FileParseError::ValueTooLarge)?;
+        let header = self.build_section_header(TLS_SECTION, size, characteristics)?;
+        let rva = header.virtual_address.value;
+        self.append_section_raw(header, vec![0u8; size as usize])?;
+        let section = self.sections.last_mut().expect("section just pushed");
+        section.virtual_size.update(&mut self.buffer, size)?;
+        Ok(rva)
+    }
+
+    /// Index of the section whose virtual span contains `rva`.
+    fn section_index_for_rva(&self, rva: u32) -> Result<usize, FileParseError> {
+        self.sections
+            .iter()
+            .position(|section| {
+                let start = section.virtual_address.value;
+                let size = section
+                    .virtual_size
+                    .value
+                    .max(section.size_of_raw_data.value);
+                rva >= start && rva < start.saturating_add(size)
+            })
+            .ok_or(FileParseError::InvalidFileFormat)
+    }
+
+    /// Converts a VA to an RVA using the optional header `ImageBase`.
+    fn va_to_rva(&self, va: u64) -> Result<u32, FileParseError> {
+        va.checked_sub(self.optional_header.image_base.value.value())
+            .and_then(|rva| u32::try_from(rva).ok())
+            .ok_or(FileParseError::InvalidFileFormat)
+    }
+}
@@ -0,0 +1,343 @@
+//! Version resource (`VS_VERSIONINFO`, `StringFileInfo`, `VarFileInfo`).
+//!
+//! Every block in the resource shares one layout: `wLength`, `wValueLength`, `wType`, a
+//! NUL-terminated UTF-16 key, padding to a 32-bit boundary, the value, and child blocks.
+
+use crate::errors::FileParseError;
+use crate::field::Field;
+use crate::utils::{extract_u16, extract_u32};
+
+/// `RT_VERSION` resource type ID.
+pub const RT_VERSION: u16 = 16;
+/// `VS_FIXEDFILEINFO.dwSignature`.
+pub const VS_FFI_SIGNATURE: u32 = 0xFEEF_04BD;
+
+/// `VS_FIXEDFILEINFO` — 52 bytes.
+pub struct FixedFileInfo {
+    /// Signature (`0xFEEF04BD`).
+    pub signature: Field<u32>,
+    /// Structure version (`dwStrucVersion`).
+    pub struc_version: Field<u32>,
+    /// High 32 bits of the file version (`dwFileVersionMS`).
+    pub file_version_ms: Field<u32>,
+    /// Low 32 bits of the file version (`dwFileVersionLS`).
+    pub file_version_ls: Field<u32>,
+    /// High 32 bits of the product version (`dwProductVersionMS`).
+    pub product_version_ms: Field<u32>,
+    /// Low 32 bits of the product version (`dwProductVersionLS`).
+    pub product_version_ls: Field<u32>,
+    /// Valid bits in `dwFileFlags`.
+    pub file_flags_mask: Field<u32>,
+    /// `VS_FF_*` flags.
+    pub file_flags: Field<u32>,
+    /// Target OS (`VOS_*`).
+    pub file_os: Field<u32>,
+    /// File type (`VFT_*`).
+    pub file_type: Field<u32>,
+    /// File subtype (`VFT2_*`).
+    pub file_subtype: Field<u32>,
+    /// High 32 bits of the file date.
+    pub file_date_ms: Field<u32>,
+    /// Low 32 bits of the file date.
+    pub file_date_ls: Field<u32>,
+}
+
+/// One `String` block inside a string table.
+pub struct VersionString {
+    /// Absolute file offset of the block header.
+    pub offset: usize,
+    /// Key (`CompanyName`, `FileVersion`, ...).
+    pub key: String,
+    /// Value without the terminating NUL.
+    pub value: String,
+    /// Absolute file offset of the UTF-16 value.
+    pub value_offset: usize,
+}
+
+/// `StringTable` block for one language / code page pair.
+pub struct StringTable {
+    /// Absolute file offset of the block header.
+    pub offset: usize,
+    /// Eight hex digits: language ID followed by code page (e.g. `040904B0`).
+    pub key: String,
+    /// Key/value pairs in file order.
+    pub strings: Vec<VersionString>,
+}
+
+/// One `Translation` entry from `VarFileInfo`.
+pub struct Translation {
+    /// Language ID.
+    pub language: Field<u16>,
+    /// Code page.
+    pub code_page: Field<u16>,
+}
+
+/// Parsed `VS_VERSIONINFO` resource.
+pub struct VersionInfo {
+    /// Absolute file offset of the root block.
+    pub offset: usize,
+    /// Fixed file information, when the root carries a value.
+    pub fixed: Option<FixedFileInfo>,
+    /// `StringFileInfo` tables.
+    pub string_tables: Vec<StringTable>,
+    /// `VarFileInfo\Translation` entries.
+    pub translations: Vec<Translation>,
+}
+
+/// Header common to every version block.
+struct Block {
+    offset: usize,
+    key: String,
+    value_length: usize,
+    value_offset: usize,
+    end: usize,
+}
+
+impl FixedFileInfo {
+    /// Size of `VS_FIXEDFILEINFO` in bytes.
+    pub const SIZE: usize = 52;
+
+    /// Parses `VS_FIXEDFILEINFO` at `offset`.
+    pub fn parse(buffer: &[u8], offset: usize) -> Result<Self, FileParseError> {
+        if buffer.len() < offset + Self::SIZE {
+            return Err(FileParseError::BufferOverflow);
+        }
+        let field = |index: usize| -> Result<Field<u32>, FileParseError> {
+            let off = offset + index * 4;
+            Ok(Field::new(extract_u32(buffer, off)?, off, 4))
+        };
+
+        let signature = field(0)?;
+        if signature.value != VS_FFI_SIGNATURE {
+            return Err(FileParseError::InvalidFileFormat);
+        }
+
+        Ok(FixedFileInfo {
+            signature,
+            struc_version: field(1)?,
+            file_version_ms: field(2)?,
+            file_version_ls: field(3)?,
+            product_version_ms: field(4)?,
+            product_version_ls: field(5)?,
+            file_flags_mask: field(6)?,
+            file_flags: field(7)?,
+            file_os: field(8)?,
+            file_type: field(9)?,
+            file_subtype: field(10)?,
+            file_date_ms: field(11)?,
+            file_date_ls: field(12)?,
+        })
+    }
+
+    /// File version as `(major, minor, build, revision)`.
+    pub fn file_version(&self) -> (u16, u16, u16, u16) {
+        split_version(self.file_version_ms.value, self.file_version_ls.value)
+    }
+
+    /// Product version as `(major, minor, build, revision)`.
+    pub fn product_version(&self) -> (u16, u16, u16, u16) {
+        split_version(self.product_version_ms.value, self.product_version_ls.value)
+    }
+}
+
+impl StringTable {
+    /// Language ID parsed from the first four hex digits of [`Self::key`].
+    pub fn language(&self) -> Option<u16> {
+        self.key
+            .get(..4)
+            .and_then(|s| u16::from_str_radix(s, 16).ok())
+    }
+
+    /// Code page parsed from the last four hex digits of [`Self::key`].
+    pub fn code_page(&self) -> Option<u16> {
+        self.key
+            .get(4..8)
+            .and_then(|s| u16::from_str_radix(s, 16).ok())
+    }
+
+    /// Looks up a value by key.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.strings
+            .iter()
+            .find(|s| s.key == key)
+            .map(|s| s.value.as_str())
+    }
+}
+
+impl VersionInfo {
+    /// Parses a `VS_VERSIONINFO` block of `size` bytes at `offset`.
+    pub fn parse(buffer: &[u8], offset: usize, size: usize) -> Result<Self, FileParseError> {
+        let limit = offset
+            .checked_add(size)
+            .ok_or(FileParseError::BufferOverflow)?;
+        if buffer.len() < limit {
+            return Err(FileParseError::BufferOverflow);
+        }
+
+        let root = read_block(buffer, offset, offset, limit)?;
+        if root.key != "VS_VERSION_INFO" {
+            return Err(FileParseError::InvalidFileFormat);
+        }
+        let fixed = if root.value_length >= FixedFileInfo::SIZE {
+            Some(FixedFileInfo::parse(buffer, root.value_offset)?)
+        } else {
+            None
+        };
+
+        let mut string_tables = Vec::new();
+        let mut translations = Vec::new();
+        let children = align4(offset, root.value_offset + root.value_length);
+        for info in read_children(buffer, offset, children, root.end)? {
+            match info.key.as_str() {
+                "StringFileInfo" => {
+                    let start = align4(offset, info.value_offset + info.value_length);
+                    for table in read_children(buffer, offset, start, info.end)? {
+                        string_tables.push(parse_string_table(buffer, offset, table)?);
+                    }
+                }
+                "VarFileInfo" => {
+                    let start = align4(offset, info.value_offset + info.value_length);
+                    for var in read_children(buffer, offset, start, info.end)? {
+                        if var.key != "Translation" {
+                            continue;
+                        }
+                        let end = (var.value_offset + var.value_length).min(var.end);
+                        let mut cursor = var.value_offset;
+                        while cursor + 4 <= end {
+                            translations.push(Translation {
+                                language: Field::new(extract_u16(buffer, cursor)?, cursor, 2),
+                                code_page: Field::new(
+                                    extract_u16(buffer, cursor + 2)?,
+                                    cursor + 2,
+                                    2,
+                                ),
+                            });
+                            cursor += 4;
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(VersionInfo {
+            offset,
+            fixed,
+            string_tables,
+            translations,
+        })
+    }
+
+    /// Looks up `key` in the first string table that defines it.
+    pub fn string(&self, key: &str) -> Option<&str> {
+        self.string_tables.iter().find_map(|table| table.get(key))
+    }
+}
+
+fn split_version(ms: u32, ls: u32) -> (u16, u16, u16, u16) {
+    ((ms >> 16) as u16, ms as u16, (ls >> 16) as u16, ls as u16)
+}
+
+/// Rounds `offset` up to a 32-bit boundary relative to `base`.
+fn align4(base: usize, offset: usize) -> usize {
+    base + (offset - base).next_multiple_of(4)
+}
+
+fn read_block(
+    buffer: &[u8],
+    base: usize,
+    offset: usize,
+    limit: usize,
+) -> Result<Block, FileParseError> {
+    let length = extract_u16(buffer, offset)? as usize;
+    let value_length = extract_u16(buffer, offset + 2)? as usize;
+    let value_type = extract_u16(buffer, offset + 4)?;
+    let end = offset
+        .checked_add(length)
+        .ok_or(FileParseError::BufferOverflow)?;
+    if length < 6 || end > limit {
+        return Err(FileParseError::InvalidFileFormat);
+    }
+
+    let (key, key_end) = read_utf16z(buffer, offset + 6, end)?;
+    // Blocks with an empty value may end before the padding that follows the key.
+    let value_offset = align4(base, key_end).min(end);
+    // Text values count UTF-16 units (some linkers write bytes); binary values count bytes.
+    let value_length = if value_type == 1 {
+        value_length * 2
+    } else {
+        value_length
+    }
+    .min(end - value_offset);
+
+    Ok(Block {
+        offset,
+        key,
+        value_length,
+        value_offset,
+        end,
+    })
+}
+
+fn read_children(
+    buffer: &[u8],
+    base: usize,
+    mut offset: usize,
+    end: usize,
+) -> Result<Vec<Block>, FileParseError> {
+    let mut blocks = Vec::new();
+    while offset + 6 <= end {
+        let block = read_block(buffer, base, offset, end)?;
+        offset = align4(base, block.end);
+        blocks.push(block);
+    }
+    Ok(blocks)
+}
+
+fn parse_string_table(
+    buffer: &[u8],
+    base: usize,
+    table: Block,
+) -> Result<StringTable, FileParseError> {
+    let mut strings = Vec::new();
+    let start = align4(base, table.value_offset + table.value_length);
+    for block in read_children(buffer, base, start, table.end)? {
+        let value_end = block.value_offset + block.value_length;
+        let value = read_utf16z(buffer, block.value_offset, value_end)?.0;
+        strings.push(VersionString {
+            offset: block.offset,
+            key: block.key,
+            value,
+            value_offset: block.value_offset,
+        });
+    }
+
+    Ok(StringTable {
+        offset: table.offset,
+        key: table.key,
+        strings,
+    })
+}
+
+/// Reads a NUL-terminated UTF-16 string, returning it and the offset past the terminator.
+fn read_utf16z(
+    buffer: &[u8],
+    offset: usize,
+    end: usize,
+) -> Result<(String, usize), FileParseError> {
+    let bytes = buffer
+        .get(offset..end)
+        .ok_or(FileParseError::BufferOverflow)?;
+    let mut units = Vec::new();
+    for chunk in bytes.as_chunks::<2>().0 {
+        let unit = u16::from_le_bytes(*chunk);
+        if unit == 0 {
+            return Ok((
+                String::from_utf16_lossy(&units),
+                offset + units.len() * 2 + 2,
+            ));
+        }
+        units.push(unit);
+    }
+    Ok((String::from_utf16_lossy(&units), offset + units.len() * 2))
+}
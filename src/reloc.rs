@@ -8,12 +8,14 @@
 //! |--------|--------|---------------|-------|
 //! | PE | `.reloc` / `IMAGE_BASE_RELOCATION` | page RVA + 12-bit offset | `IMAGE_REL_BASED_*` |
 //! | PE | section `IMAGE_RELOCATION` | RVA per entry | `IMAGE_REL_I386_*` / `IMAGE_REL_AMD64_*` |
+//! | PE | load config DVRT | page RVA + 12-bit offset per symbol | retpoline / ARM64X records |
 //! | ELF | `.rel` / `.rela` sections | `r_offset` (VA) | arch-specific `R_*` in `r_info` |
 //! | Mach-O | `section.reloff` | section-relative offset | `relocation_info` (not decoded yet) |
 
 use crate::elf::relocation::RelocationEntry;
 use crate::elf::ELF;
 use crate::errors::FileParseError;
+use crate::pe::dynamic_relocation::DynamicRelocationRecord;
 use crate::pe::relocation::{BaseRelocationBlock, BaseRelocationEntry};
 use crate::pe::section_reloc::SectionRelocation;
 use crate::pe::PE;
@@ -36,6 +38,16 @@ pub struct PeSectionRelocHit {
     pub entry: SectionRelocation,
 }
 
+/// One PE dynamic value relocation (DVRT) record targeting a specific RVA.
+#[derive(Debug, Clone)]
+pub struct PeDynamicRelocHit {
+    pub symbol: u64,
+    pub page_rva: u32,
+    pub rva: u32,
+    pub file_offset: usize,
+    pub record: DynamicRelocationRecord,
+}
+
 /// ELF relocation with the owning section index.
 #[derive(Debug, Clone)]
 pub struct ElfRelocHit {
@@ -131,6 +143,49 @@ pub fn pe_relocs_at_file_offset(
     Ok(hits)
 }
 
+/// Lists every decoded DVRT record from the load config dynamic value relocation table.
+pub fn pe_dynamic_relocs(pe: &PE) -> Result<Vec<PeDynamicRelocHit>, FileParseError> {
+    let Some(table) = pe.dynamic_relocations()? else {
+        return Ok(Vec::new());
+    };
+    let mut hits = Vec::new();
+    for relocation in table.relocations {
+        for block in relocation.blocks {
+            for record in block.records {
+                let rva = record.rva(block.page_rva.value);
+                hits.push(PeDynamicRelocHit {
+                    symbol: relocation.symbol.value,
+                    page_rva: block.page_rva.value,
+                    rva,
+                    file_offset: pe.rva_to_offset(rva)?,
+                    record,
+                });
+            }
+        }
+    }
+    Ok(hits)
+}
+
+/// Lists DVRT records whose patched RVA equals `rva`.
+pub fn pe_dynamic_relocs_at_rva(
+    pe: &PE,
+    rva: u32,
+) -> Result<Vec<PeDynamicRelocHit>, FileParseError> {
+    let mut hits = pe_dynamic_relocs(pe)?;
+    hits.retain(|hit| hit.rva == rva);
+    Ok(hits)
+}
+
+/// Lists DVRT records whose patched slot maps to `file_offset`.
+pub fn pe_dynamic_relocs_at_file_offset(
+    pe: &PE,
+    file_offset: usize,
+) -> Result<Vec<PeDynamicRelocHit>, FileParseError> {
+    let mut hits = pe_dynamic_relocs(pe)?;
+    hits.retain(|hit| hit.file_offset == file_offset);
+    Ok(hits)
+}
+
 /// Lists ELF relocations whose `r_offset` equals `va`.
 pub fn elf_relocs_at_va(elf: &ELF, va: u64) -> Result<Vec<ElfRelocHit>, FileParseError> {
     let mut hits = Vec::new();
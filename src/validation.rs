@@ -4,7 +4,6 @@
 use crate::elf::ELF;
 use crate::errors::FileParseError;
 use crate::macho::MachO;
-use crate::pe::header::ImageBase;
 use crate::pe::PE;
 
 /// Half-open file byte range `[start, end)`.
@@ -120,10 +119,7 @@ pub fn pe_rva_to_file_offset(pe: &PE, rva: u32) -> Result<usize, FileParseError>
 
 /// Maps a PE VA (preferred image base + RVA) to a file offset.
 pub fn pe_va_to_file_offset(pe: &PE, va: u64) -> Result<usize, FileParseError> {
-    let base = match pe.optional_header.image_base.value {
-        ImageBase::Base32(v) => v as u64,
-        ImageBase::Base64(v) => v,
-    };
+    let base = pe.optional_header.image_base.value.value();
     if va < base {
         return Err(FileParseError::InvalidFileFormat);
     }
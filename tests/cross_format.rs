@@ -177,6 +177,59 @@ fn reloc_pe_lazy_parse_matches_eager() {
     assert_eq!(lazy.len(), pe.base_relocations.len());
 }
 
+#[test]
+fn reloc_pe_dynamic_relocs_from_load_config() {
+    use pe::dynamic_relocation::{
+        DynamicRelocationKind, IMAGE_DYNAMIC_RELOCATION_GUARD_INDIR_CONTROL_TRANSFER,
+    };
+
+    let mut pe = pe::PE::from_file("tests/samples/sample64.exe").expect("pe64");
+    assert!(pe.dynamic_relocations().expect("dvrt").is_none());
+
+    let text = pe.sections[0].pointer_to_raw_data.value as usize;
+    let text_rva = pe.sections[0].virtual_address.value;
+    let blob = &mut pe.buffer[text..text + 0x170];
+    blob.fill(0);
+    blob[0..4].copy_from_slice(&0x140u32.to_le_bytes());
+    blob[224..228].copy_from_slice(&0x140u32.to_le_bytes()); // DynamicValueRelocTableOffset
+    blob[228..230].copy_from_slice(&1u16.to_le_bytes()); // DynamicValueRelocTableSection
+                                                         // IMAGE_DYNAMIC_RELOCATION_TABLE v1 with one IMAGE_DYNAMIC_RELOCATION64.
+    blob[0x140..0x144].copy_from_slice(&1u32.to_le_bytes());
+    blob[0x144..0x148].copy_from_slice(&24u32.to_le_bytes());
+    blob[0x148..0x150].copy_from_slice(&4u64.to_le_bytes());
+    blob[0x150..0x154].copy_from_slice(&12u32.to_le_bytes());
+    blob[0x154..0x158].copy_from_slice(&text_rva.to_le_bytes());
+    blob[0x158..0x15C].copy_from_slice(&12u32.to_le_bytes());
+    blob[0x15C..0x15E].copy_from_slice(&(0x20u16 | 1 << 12 | 1 << 13).to_le_bytes());
+    pe.sync_data_directory_rva(pe::header::LOAD_CONFIG, text_rva)
+        .expect("load config rva");
+    pe.sync_data_directory_size(pe::header::LOAD_CONFIG, 0x140)
+        .expect("load config size");
+
+    let table = pe.dynamic_relocations().expect("dvrt").expect("table");
+    assert_eq!(table.offset, text + 0x140);
+    assert_eq!(table.relocations.len(), 1);
+
+    let hits = reloc::pe_dynamic_relocs_at_rva(&pe, text_rva + 0x20).expect("hits");
+    assert_eq!(hits.len(), 1);
+    assert_eq!(
+        hits[0].symbol,
+        IMAGE_DYNAMIC_RELOCATION_GUARD_INDIR_CONTROL_TRANSFER
+    );
+    assert_eq!(hits[0].file_offset, text + 0x20);
+    assert!(matches!(
+        hits[0].record.kind,
+        DynamicRelocationKind::IndirectControlTransfer {
+            indirect_call: true,
+            rex_w_prefix: true,
+            cfg_check: false,
+        }
+    ));
+    let by_offset = reloc::pe_dynamic_relocs_at_file_offset(&pe, text + 0x20).expect("hits");
+    assert_eq!(by_offset.len(), 1);
+    assert_eq!(reloc::pe_dynamic_relocs(&pe).expect("all").len(), 1);
+}
+
 // --- Write planner ---
 
 #[test]
@@ -422,6 +422,113 @@ fn test_pe64_base_relocations() {
         .all(|entry| entry.relocation_type() == IMAGE_REL_BASED_DIR64));
 }
 
+/// Base relocation editing regroups page blocks in place, then moves to a new `.reloc`.
+#[test]
+fn test_pe64_base_relocation_builder() {
+    use pe::header::BASERELOC;
+    use pe::relocation::{
+        IMAGE_REL_BASED_ABSOLUTE, IMAGE_REL_BASED_DIR64, IMAGE_REL_BASED_HIGHLOW,
+    };
+
+    let mut pe = pe::PE::from_file("tests/samples/sample64.exe").expect("Failed to parse PE64");
+    let section_count = pe.sections.len();
+    let directory = &pe.optional_header.data_directories[BASERELOC];
+    let (reloc_rva, reloc_size) = (directory.virtual_address.value, directory.size.value);
+    let reloc_offset = pe.rva_to_offset(reloc_rva).unwrap();
+
+    let mut builder = pe.base_relocation_builder().unwrap();
+    assert_eq!(
+        builder.build(),
+        pe.buffer[reloc_offset..reloc_offset + reloc_size as usize]
+    );
+    assert_eq!(builder.remove(0x2738), Some(IMAGE_REL_BASED_DIR64));
+    builder.add(0x5008, IMAGE_REL_BASED_DIR64).unwrap();
+    builder.add(0x5010, IMAGE_REL_BASED_HIGHLOW).unwrap();
+    assert!(builder.add(0x5018, 0x10).is_err());
+    assert!(builder.add(0x5018, IMAGE_REL_BASED_ABSOLUTE).is_err());
+    pe.set_base_relocations(&builder).unwrap();
+
+    assert_eq!(pe.sections.len(), section_count);
+    assert_eq!(
+        pe.optional_header.data_directories[BASERELOC]
+            .virtual_address
+            .value,
+        reloc_rva
+    );
+    // 0x2738 was the only relocation on its page, so the 0x2000 block is gone.
+    assert!(pe
+        .base_relocations
+        .iter()
+        .all(|block| block.page_rva.value != 0x2000));
+    let page = pe
+        .base_relocations
+        .iter()
+        .find(|block| block.page_rva.value == 0x5000)
+        .expect("new page block");
+    assert_eq!(page.block_size.value, 12);
+    assert_eq!(page.entries[0].rva(0x5000), 0x5008);
+    assert_eq!(page.entries[1].relocation_type(), IMAGE_REL_BASED_HIGHLOW);
+    assert!(pe
+        .base_relocations
+        .iter()
+        .all(|block| block.block_size.value % 4 == 0));
+    assert_eq!(pe.base_relocation_builder().unwrap(), builder);
+
+    for rva in (0x10_0000..0x10_0000 + 0x4000).step_by(8) {
+        builder.add(rva, IMAGE_REL_BASED_DIR64).unwrap();
+    }
+    pe.set_base_relocations(&builder).unwrap();
+    assert_eq!(pe.sections.len(), section_count + 1);
+    let reloc = pe.sections.last().unwrap();
+    assert_eq!(reloc.name_str(), ".reloc");
+    assert_eq!(
+        pe.optional_header.data_directories[BASERELOC]
+            .virtual_address
+            .value,
+        reloc.virtual_address.value
+    );
+    assert_eq!(pe.optional_header.checksum.value, pe.calc_checksum());
+    let reparsed = pe::PE::from_buffer(pe.buffer.clone()).unwrap();
+    assert_eq!(reparsed.base_relocation_builder().unwrap(), builder);
+    assert_eq!(
+        pe.buffer[reloc_offset..reloc_offset + reloc_size as usize],
+        vec![0; reloc_size as usize]
+    );
+}
+
+/// set_base_relocations never grows over data after the directory and keeps the file tail.
+#[test]
+fn test_pe64_base_relocations_keep_neighbours_and_tail() {
+    use pe::header::BASERELOC;
+    use pe::relocation::IMAGE_REL_BASED_DIR64;
+
+    let mut pe = pe::PE::from_file("tests/samples/sample64.exe").expect("Failed to parse PE64");
+    sign_with_overlay(&mut pe, &[0xaa; 16]);
+    let section_count = pe.sections.len();
+    let directory = &pe.optional_header.data_directories[BASERELOC];
+    let (reloc_rva, reloc_size) = (directory.virtual_address.value, directory.size.value);
+    let marker = pe.rva_to_offset(reloc_rva).unwrap() + reloc_size as usize + 4;
+    pe.buffer[marker..marker + 4].copy_from_slice(b"KEEP");
+
+    // One more page block no longer fits the old directory and the bytes after it are in use.
+    let mut builder = pe.base_relocation_builder().unwrap();
+    builder.add(0x5008, IMAGE_REL_BASED_DIR64).unwrap();
+    pe.set_base_relocations(&builder).unwrap();
+    assert_eq!(&pe.buffer[marker..marker + 4], b"KEEP");
+    assert_eq!(pe.sections.len(), section_count + 1);
+    let reloc = pe.sections.last().unwrap();
+    assert_eq!(reloc.name_str(), ".reloc");
+    assert_eq!(
+        pe.optional_header.data_directories[BASERELOC]
+            .virtual_address
+            .value,
+        reloc.virtual_address.value
+    );
+    assert_signed_overlay(&pe, &[0xaa; 16]);
+    let reparsed = pe::PE::from_buffer(pe.buffer.clone()).unwrap();
+    assert_eq!(reparsed.base_relocation_builder().unwrap(), builder);
+}
+
 /// PE32 optional header P0 fields and data directories (sample1.exe).
 #[test]
 fn test_pe32_optional_header_p0() {
@@ -575,6 +682,93 @@ fn test_pe_imports_sample1() {
     assert!(empty.dlls.is_empty());
 }
 
+/// `add_import` rebuilds the import directory in a new section while keeping existing IAT RVAs.
+#[test]
+fn test_pe_add_import_sample1() {
+    use pe::import::ImportEntry;
+
+    let mut pe = pe::PE::from_file("tests/samples/sample1.exe").expect("Failed to parse PE32");
+    let before = pe.imports().unwrap();
+    let kernel32 = &before.dlls[0];
+    let exit_slot = kernel32
+        .entries
+        .iter()
+        .position(
+            |e| matches!(e, ImportEntry::ByName { by_name, .. } if by_name.name == "ExitProcess"),
+        )
+        .unwrap() as u32;
+    let old_first_thunks: Vec<u32> = before
+        .descriptors
+        .iter()
+        .map(|d| d.first_thunk.value)
+        .collect();
+    let section_count = pe.sections.len();
+
+    // Already imported: no rebuild, existing slot returned.
+    let slots = pe.add_import("kernel32.dll", &["ExitProcess"]).unwrap();
+    assert_eq!(slots, [0x9184 + exit_slot * 4]);
+    assert_eq!(pe.sections.len(), section_count);
+
+    let slots = pe
+        .add_import("USER32.dll", &["MessageBoxA", "GetDC", "MessageBoxW"])
+        .unwrap();
+    assert_eq!(pe.sections.len(), section_count + 1);
+    let idata = pe.sections.last().unwrap();
+    assert_eq!(idata.name_str(), ".idata2");
+    let import_dir = &pe.optional_header.data_directories[pe::header::IMPORT];
+    assert_eq!(
+        import_dir.virtual_address.value,
+        idata.virtual_address.value
+    );
+    assert_eq!(import_dir.size.value, 7 * 20);
+    assert_eq!(slots[1], slots[0] + 4);
+    assert_eq!(slots[2], slots[0] + 8);
+    assert_eq!(pe.optional_header.checksum.value, pe.calc_checksum());
+
+    let reparsed = pe::PE::from_buffer(pe.buffer.clone()).unwrap();
+    let after = reparsed.imports().unwrap();
+    assert_eq!(after.dlls.len(), 6);
+    for (dll, first_thunk) in after.dlls.iter().zip(&old_first_thunks) {
+        assert_eq!(dll.descriptor.first_thunk.value, *first_thunk);
+    }
+    assert_eq!(
+        pe::import::import_names_for_dll(&after, "KERNEL32.dll"),
+        pe::import::import_names_for_dll(&before, "KERNEL32.dll")
+    );
+    let user32 = &after.dlls[5];
+    assert_eq!(user32.dll_name, "USER32.dll");
+    assert_eq!(user32.descriptor.first_thunk.value, slots[0]);
+    let names: Vec<_> = user32
+        .entries
+        .iter()
+        .filter_map(|e| match e {
+            ImportEntry::ByName { by_name, .. } => Some(by_name.name.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(names, ["MessageBoxA", "GetDC", "MessageBoxW"]);
+    let iat = reparsed.rva_to_offset(slots[0]).unwrap();
+    let ilt = reparsed
+        .rva_to_offset(user32.descriptor.original_first_thunk.value)
+        .unwrap();
+    assert_eq!(
+        reparsed.buffer[iat..iat + 16],
+        reparsed.buffer[ilt..ilt + 16]
+    );
+}
+
+/// add_import on a signed image with an overlay keeps both behind the new section.
+#[test]
+fn test_pe_add_import_keeps_overlay_and_signature() {
+    let mut pe = pe::PE::from_file("tests/samples/sample1.exe").expect("Failed to parse PE32");
+    sign_with_overlay(&mut pe, &[0xaa; 64]);
+    pe.add_import("USER32.dll", &["MessageBoxA"]).unwrap();
+
+    let pe = pe::PE::from_buffer(pe.buffer).unwrap();
+    assert_eq!(pe.sections.last().unwrap().name_str(), ".idata2");
+    assert_signed_overlay(&pe, &[0xaa; 64]);
+}
+
 #[test]
 fn test_pe_write_file_fail() {
     let pe = pe::PE::from_file("tests/samples/sample1.exe").expect("Error parsing PE file");
@@ -672,6 +866,135 @@ fn test_pe_exports_sample2() {
     );
 }
 
+/// Export editing: rename / remove in place, then add forwarders and ordinal-only exports.
+#[test]
+fn test_pe_export_builder_sample2() {
+    use pe::export::FunctionExport;
+    use pe::export_builder::ExportTarget;
+    use pe::header::EXPORT;
+
+    let mut pe = pe::PE::from_file("tests/samples/sample2.dll").expect("Failed to parse PE");
+    let section_count = pe.sections.len();
+    let export_rva = pe.optional_header.data_directories[EXPORT]
+        .virtual_address
+        .value;
+
+    let mut builder = pe.export_builder().unwrap();
+    assert_eq!(builder.dll_name, "sample3.dll");
+    assert_eq!(builder.exports.len(), 4);
+    assert!(builder.rename("Add", "Plus"));
+    assert!(!builder.rename("Missing", "Other"));
+    assert!(!builder.rename("Plus", "Subtract"));
+    assert!(builder.remove("Divide"));
+    pe.set_exports(&builder).unwrap();
+    assert_eq!(pe.sections.len(), section_count);
+    assert_eq!(
+        pe.optional_header.data_directories[EXPORT]
+            .virtual_address
+            .value,
+        export_rva
+    );
+
+    let exports = pe.exports().unwrap().unwrap();
+    let names: Vec<_> = exports.named.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(names, ["Multiply", "Plus", "Subtract"]);
+    let plus = exports.named.iter().find(|e| e.name == "Plus").unwrap();
+    assert_eq!((plus.ordinal, plus.function_rva.value), (1, 0x1280));
+
+    let forward = builder
+        .add(
+            "Sleep",
+            ExportTarget::Forwarder("KERNEL32.Sleep".to_string()),
+        )
+        .unwrap();
+    assert_eq!(forward, 5);
+    builder.insert(10, None, ExportTarget::Rva(0x12f2));
+    assert!(builder.remove_ordinal(3));
+    pe.set_exports(&builder).unwrap();
+    assert_eq!(pe.sections.len(), section_count + 1);
+    assert_eq!(pe.sections.last().unwrap().name_str(), ".edata");
+    assert_eq!(pe.optional_header.checksum.value, pe.calc_checksum());
+
+    let reparsed = pe::PE::from_buffer(pe.buffer.clone()).unwrap();
+    let exports = reparsed.exports().unwrap().unwrap();
+    assert_eq!(exports.directory.base.value, 1);
+    assert_eq!(exports.directory.number_of_functions.value, 10);
+    let names: Vec<_> = exports.named.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(names, ["Plus", "Sleep", "Subtract"]);
+    assert!(exports.functions.iter().any(|f| matches!(
+        f,
+        FunctionExport::Forwarder { ordinal: 5, forwarder, .. } if forwarder == "KERNEL32.Sleep"
+    )));
+    let ordinal_only: Vec<u16> = exports
+        .ordinal_only_exports()
+        .iter()
+        .filter_map(|f| match f {
+            FunctionExport::Local {
+                ordinal,
+                function_rva,
+                ..
+            } if function_rva.value != 0 => Some(*ordinal),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(ordinal_only, [10]);
+    assert_eq!(reparsed.export_builder().unwrap(), builder);
+}
+
+/// Aliased export names (several name table entries for one ordinal) survive a round trip.
+#[test]
+fn test_pe_export_builder_aliases() {
+    let mut pe = pe::PE::from_file("tests/samples/sample2.dll").expect("Failed to parse PE");
+    let mut builder = pe.export_builder().unwrap();
+    let add = builder.ordinal_of("Add").unwrap();
+    builder
+        .exports
+        .get_mut(&add)
+        .unwrap()
+        .names
+        .push("Sum".to_string());
+    pe.set_exports(&builder).unwrap();
+
+    let reparsed = pe::PE::from_buffer(pe.buffer.clone()).unwrap();
+    let exports = reparsed.exports().unwrap().unwrap();
+    let names: Vec<_> = exports.named.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(names, ["Add", "Divide", "Multiply", "Subtract", "Sum"]);
+    assert_eq!(exports.named[0].ordinal, exports.named[4].ordinal);
+    let mut rebuilt = reparsed.export_builder().unwrap();
+    assert_eq!(rebuilt, builder);
+    assert_eq!(rebuilt.exports[&add].names, ["Add", "Sum"]);
+
+    // Removing one alias keeps the ordinal and its other name.
+    assert!(rebuilt.remove("Add"));
+    assert_eq!(rebuilt.exports[&add].names, ["Sum"]);
+    assert!(rebuilt.rename("Sum", "Total"));
+    assert_eq!(rebuilt.ordinal_of("Total"), Some(add));
+    assert!(rebuilt.remove("Total"));
+    assert!(!rebuilt.exports.contains_key(&add));
+}
+
+/// set_exports appending `.edata` to a signed image keeps the certificate table readable.
+#[test]
+fn test_pe_set_exports_keeps_signature() {
+    use pe::export_builder::ExportTarget;
+
+    let mut pe = pe::PE::from_file("tests/samples/sample2.dll").expect("Failed to parse PE");
+    sign_with_overlay(&mut pe, b"tail");
+    let mut builder = pe.export_builder().unwrap();
+    builder
+        .add(
+            "AVeryLongExportNameThatCannotFitInPlace",
+            ExportTarget::Rva(0x1000),
+        )
+        .unwrap();
+    pe.set_exports(&builder).unwrap();
+
+    let pe = pe::PE::from_buffer(pe.buffer).unwrap();
+    assert_eq!(pe.sections.last().unwrap().name_str(), ".edata");
+    assert_signed_overlay(&pe, b"tail");
+    assert_eq!(pe.export_builder().unwrap(), builder);
+}
+
 /// Executables without an export directory return `None`.
 #[test]
 fn test_pe_exports_absent() {
@@ -741,6 +1064,137 @@ fn test_pe_tls_directory() {
     ));
 }
 
+/// TLS callback enumeration and injection.
+#[test]
+fn test_pe_tls_callbacks() {
+    use pe::header::ImageBase;
+    use pe::relocation::{IMAGE_REL_BASED_DIR64, IMAGE_REL_BASED_HIGHLOW};
+
+    let pe32 = pe::PE::from_file("tests/samples/sample1.exe").expect("Failed to parse PE32");
+    let callbacks = pe32.tls_callbacks().unwrap();
+    assert_eq!(callbacks.len(), 2);
+    assert_eq!(callbacks[0].offset, pe32.rva_to_offset(0xa004).unwrap());
+    assert_eq!(callbacks[1].offset, callbacks[0].offset + 4);
+
+    let mut pe = pe::PE::from_file("tests/samples/sample64.exe").expect("Failed to parse PE64");
+    let original = pe.tls_callbacks().unwrap();
+    assert_eq!(original.len(), 2);
+    assert_eq!(original[0].offset, pe.rva_to_offset(0x9038).unwrap());
+    let originals: Vec<u64> = original
+        .iter()
+        .map(|callback| match callback.value {
+            ImageBase::Base64(value) => value,
+            ImageBase::Base32(_) => panic!("expected 64-bit callbacks"),
+        })
+        .collect();
+
+    // The .CRT array is followed by CRT data, so the array moves to a new section.
+    pe.add_tls_callbacks(&[0x1000]).unwrap();
+    let pe = pe::PE::from_buffer(pe.buffer).unwrap();
+    let moved = pe.sections.last().unwrap();
+    assert_eq!(moved.name_str(), ".tls2");
+    let callbacks = pe.tls_callbacks().unwrap();
+    assert_eq!(callbacks.len(), 3);
+    assert_eq!(
+        callbacks[0].offset,
+        moved.pointer_to_raw_data.value as usize
+    );
+    assert!(matches!(callbacks[0].value, ImageBase::Base64(v) if v == originals[0]));
+    assert!(matches!(callbacks[1].value, ImageBase::Base64(v) if v == originals[1]));
+    assert!(matches!(
+        callbacks[2].value,
+        ImageBase::Base64(0x1_4000_1000)
+    ));
+    let tls = pe.tls().unwrap().unwrap();
+    let array_rva = moved.virtual_address.value;
+    assert!(matches!(
+        tls.address_of_callbacks.value,
+        ImageBase::Base64(v) if v == 0x1_4000_0000 + array_rva as u64
+    ));
+    let relocations = pe.base_relocation_builder().unwrap().entries;
+    for slot in 0..3 {
+        assert_eq!(
+            relocations.get(&(array_rva + slot * 8)),
+            Some(&IMAGE_REL_BASED_DIR64)
+        );
+    }
+
+    // The moved array ends its section, so the next callback grows it in place.
+    let mut pe = pe;
+    pe.add_tls_callbacks(&[0x1010]).unwrap();
+    let pe = pe::PE::from_buffer(pe.buffer).unwrap();
+    assert_eq!(pe.sections.last().unwrap().name_str(), ".tls2");
+    assert_eq!(
+        pe.sections
+            .iter()
+            .filter(|s| s.name_str() == ".tls2")
+            .count(),
+        1
+    );
+    let callbacks = pe.tls_callbacks().unwrap();
+    assert_eq!(callbacks.len(), 4);
+    assert!(matches!(
+        callbacks[3].value,
+        ImageBase::Base64(0x1_4000_1010)
+    ));
+    assert_eq!(
+        pe.base_relocation_builder()
+            .unwrap()
+            .entries
+            .get(&(array_rva + 24)),
+        Some(&IMAGE_REL_BASED_DIR64)
+    );
+
+    // A DLL without a TLS directory gets a complete one.
+    let mut dll = pe::PE::from_file("tests/samples/sample2.dll").expect("Failed to parse PE");
+    dll.sync_data_directory_rva(pe::header::TLS, 0).unwrap();
+    dll.sync_data_directory_size(pe::header::TLS, 0).unwrap();
+    let dll = pe::PE::from_buffer(dll.buffer).unwrap();
+    assert!(dll.tls().unwrap().is_none());
+    assert!(dll.tls_callbacks().unwrap().is_empty());
+    let mut dll = dll;
+    dll.add_tls_callbacks(&[0x1000, 0x1020]).unwrap();
+    let dll = pe::PE::from_buffer(dll.buffer).unwrap();
+    let ImageBase::Base32(base) = dll.optional_header.image_base.value else {
+        panic!("expected a PE32 image base");
+    };
+    let section = dll.sections.last().unwrap();
+    let rva = section.virtual_address.value;
+    let tls = dll.tls().unwrap().expect("TLS directory");
+    assert!(matches!(tls.address_of_index.value, ImageBase::Base32(v) if v == base + rva + 24));
+    let callbacks = dll.tls_callbacks().unwrap();
+    assert_eq!(callbacks.len(), 2);
+    assert!(matches!(callbacks[1].value, ImageBase::Base32(v) if v == base + 0x1020));
+    let relocations = dll.base_relocation_builder().unwrap().entries;
+    for field in 0..4 {
+        assert_eq!(
+            relocations.get(&(rva + field * 4)),
+            Some(&IMAGE_REL_BASED_HIGHLOW)
+        );
+    }
+    assert_eq!(relocations.get(&(rva + 28)), Some(&IMAGE_REL_BASED_HIGHLOW));
+
+    // Relocations stay stripped on images without them.
+    let mut pe32 = pe32;
+    pe32.add_tls_callbacks(&[0x1000]).unwrap();
+    let pe32 = pe::PE::from_buffer(pe32.buffer).unwrap();
+    assert_eq!(pe32.tls_callbacks().unwrap().len(), 3);
+    assert!(pe32.base_relocations.is_empty());
+}
+
+/// Moving the TLS callback array into `.tls2` keeps the overlay and certificate table.
+#[test]
+fn test_pe_tls_callbacks_keep_overlay_and_signature() {
+    let mut pe = pe::PE::from_file("tests/samples/sample1.exe").expect("Failed to parse PE32");
+    sign_with_overlay(&mut pe, &[0xaa; 24]);
+    pe.add_tls_callbacks(&[0x1000]).unwrap();
+
+    let pe = pe::PE::from_buffer(pe.buffer).unwrap();
+    assert_eq!(pe.sections.last().unwrap().name_str(), ".tls2");
+    assert_eq!(pe.tls_callbacks().unwrap().len(), 3);
+    assert_signed_overlay(&pe, &[0xaa; 24]);
+}
+
 /// x64 exception directory (`RUNTIME_FUNCTION`) on sample64.exe.
 #[test]
 fn test_pe_exception_directory_sample64() {
@@ -759,6 +1213,160 @@ fn test_pe_exception_directory_sample64() {
     assert_eq!(second.end_address.value, 0x1136);
 }
 
+/// x64 `UNWIND_INFO` records behind sample64.exe runtime functions.
+#[test]
+fn test_pe_unwind_info_sample64() {
+    use pe::exception::{UnwindOperation, UNW_FLAG_EHANDLER};
+
+    let pe = pe::PE::from_file("tests/samples/sample64.exe").expect("Failed to parse PE64");
+    let exceptions = pe.exceptions().unwrap().expect("exception directory");
+
+    let info = pe.unwind_info(&exceptions.entries[1]).expect("unwind info");
+    assert_eq!(info.offset, pe.rva_to_offset(0x6004).unwrap());
+    assert_eq!(info.version(), 1);
+    assert_eq!(info.flags(), 0);
+    assert_eq!(info.size_of_prolog.value, 8);
+    assert_eq!(info.count_of_codes.value, 3);
+    assert_eq!(info.frame_register(), 5);
+    assert_eq!(info.frame_offset(), 0);
+    let operations: Vec<_> = info.codes.iter().map(|code| code.operation).collect();
+    assert_eq!(
+        operations,
+        [
+            UnwindOperation::AllocSmall { size: 32 },
+            UnwindOperation::SetFpreg,
+            UnwindOperation::PushNonvol { register: 5 },
+        ]
+    );
+    assert!(info.exception_handler.is_none());
+
+    let handled = pe.unwind_info(&exceptions.entries[4]).expect("unwind info");
+    assert_eq!(handled.flags(), UNW_FLAG_EHANDLER);
+    let handler = handled.exception_handler.expect("exception handler");
+    assert_eq!(handler.offset, handled.offset + 12);
+    assert_eq!(handled.handler_data_offset, Some(handled.offset + 16));
+
+    for entry in &exceptions.entries {
+        pe.unwind_info(entry).expect("every entry decodes");
+    }
+}
+
+/// Chained `UNWIND_INFO` and multi-slot unwind codes.
+#[test]
+fn test_pe_unwind_info_chained_synthetic() {
+    use pe::exception::{UnwindInfo, UnwindOperation, UNW_FLAG_CHAININFO};
+
+    let mut buffer = vec![0u8; 0x40];
+    buffer[0] = 1 | (UNW_FLAG_CHAININFO << 3);
+    buffer[1] = 0x10;
+    buffer[2] = 5; // slots: ALLOC_LARGE(3) + SAVE_NONVOL(2)
+    buffer[4..6].copy_from_slice(&[0x10, 0x11]); // ALLOC_LARGE, OpInfo=1
+    buffer[6..10].copy_from_slice(&0x0002_0000u32.to_le_bytes());
+    buffer[10..12].copy_from_slice(&[0x08, 0x34]); // SAVE_NONVOL rbx
+    buffer[12..14].copy_from_slice(&6u16.to_le_bytes());
+    // Five slots are padded to six before the chained RUNTIME_FUNCTION.
+    buffer[16..20].copy_from_slice(&0x1000u32.to_le_bytes());
+    buffer[20..24].copy_from_slice(&0x1080u32.to_le_bytes());
+    buffer[24..28].copy_from_slice(&0x6000u32.to_le_bytes());
+
+    let info = UnwindInfo::parse(&buffer, 0).unwrap();
+    assert_eq!(info.codes.len(), 2);
+    assert_eq!(
+        info.codes[0].operation,
+        UnwindOperation::AllocLarge { size: 0x2_0000 }
+    );
+    assert_eq!(info.codes[0].slots, 3);
+    assert_eq!(
+        info.codes[1].operation,
+        UnwindOperation::SaveNonvol {
+            register: 3,
+            offset: 48
+        }
+    );
+    let chained = info.chained.expect("chained runtime function");
+    assert_eq!(chained.begin_address.value, 0x1000);
+    assert_eq!(chained.unwind_data.value, 0x6000);
+    assert_eq!(chained.begin_address.offset, 16);
+}
+
+/// ARM64 `.pdata` entries decode packed unwind data and full `.xdata` records.
+#[test]
+fn test_pe_arm64_exception_directory_synthetic() {
+    use pe::arm64_exception::{Arm64ExceptionDirectory, Arm64PackedUnwind, Arm64XData};
+
+    let mut buffer = vec![0u8; 0x80];
+    let packed: u32 = 1 | (16 << 2) | (2 << 16) | (3 << 21) | (2 << 23);
+    buffer[0..4].copy_from_slice(&0x1000u32.to_le_bytes());
+    buffer[4..8].copy_from_slice(&packed.to_le_bytes());
+    buffer[8..12].copy_from_slice(&0x1040u32.to_le_bytes());
+    buffer[12..16].copy_from_slice(&0x40u32.to_le_bytes()); // .xdata at offset 0x40
+
+    let header: u32 = 0x40 | (1 << 20) | (1 << 22) | (1 << 27);
+    buffer[0x40..0x44].copy_from_slice(&header.to_le_bytes());
+    buffer[0x44..0x48].copy_from_slice(&(0x3cu32 | (2 << 22)).to_le_bytes());
+    buffer[0x48..0x4c].copy_from_slice(&[0xc8, 0x01, 0xe4, 0xe3]);
+    buffer[0x4c..0x50].copy_from_slice(&0x2000u32.to_le_bytes());
+
+    let directory = Arm64ExceptionDirectory::parse(&buffer, 0, 16).unwrap();
+    assert_eq!(directory.entries.len(), 2);
+    assert_eq!(
+        directory.entries[0].packed(),
+        Some(Arm64PackedUnwind {
+            flag: 1,
+            function_length: 0x40,
+            reg_f: 0,
+            reg_i: 2,
+            homes_parameters: false,
+            cr: 3,
+            frame_size: 0x20,
+        })
+    );
+    assert!(directory.entries[0]
+        .xdata(&buffer, |rva| Ok(rva as usize))
+        .unwrap()
+        .is_none());
+
+    let xdata: Arm64XData = directory.entries[1]
+        .xdata(&buffer, |rva| Ok(rva as usize))
+        .unwrap()
+        .expect("xdata record");
+    assert_eq!(xdata.function_length(), 0x100);
+    assert!(xdata.has_exception_data());
+    assert!(!xdata.single_epilog());
+    assert_eq!(xdata.epilog_count(), 1);
+    assert_eq!(xdata.code_words(), 1);
+    assert_eq!(xdata.epilog_scopes[0].start_offset(), 0xf0);
+    assert_eq!(xdata.epilog_scopes[0].start_index(), 2);
+    assert_eq!(xdata.unwind_codes, [0xc8, 0x01, 0xe4, 0xe3]);
+    assert_eq!(xdata.exception_handler.unwrap().value, 0x2000);
+    assert_eq!(xdata.handler_data_offset, Some(0x50));
+}
+
+/// `exception_table` selects the `.pdata` layout from the COFF machine.
+#[test]
+fn test_pe_exception_table_dispatch_by_architecture() {
+    use pe::exception::ExceptionTable;
+
+    let mut pe = pe::PE::from_file("tests/samples/sample64.exe").expect("Failed to parse PE64");
+    assert!(matches!(
+        pe.exception_table().unwrap(),
+        Some(ExceptionTable::X64(ref directory)) if directory.entries.len() == 44
+    ));
+
+    pe.coff_header
+        .machine
+        .update(&mut pe.buffer, 0xAA64)
+        .unwrap();
+    match pe.exception_table().unwrap() {
+        Some(ExceptionTable::Arm64(directory)) => assert_eq!(directory.entries.len(), 66),
+        _ => panic!("expected ARM64 exception table"),
+    }
+    assert!(matches!(
+        pe.exceptions(),
+        Err(FileParseError::UnsupportedFeature(_))
+    ));
+}
+
 /// COFF symbol table on sample64.exe.
 #[test]
 fn test_pe_coff_symbols_sample64() {
@@ -868,6 +1476,240 @@ fn test_pe_section_relocations_empty() {
     assert!(relocs.entries.is_empty());
 }
 
+/// Load config fields past `SecurityCookie` are gated on `Size` for both pointer widths.
+#[test]
+fn test_pe_load_config_versioned_fields_synthetic() {
+    use pe::header::PEType;
+    use pe::load_config::LoadConfigDirectory;
+
+    // PE32 up to `GuardFlags`.
+    let mut buffer = vec![0u8; 0x100];
+    buffer[0..4].copy_from_slice(&0x5Cu32.to_le_bytes());
+    buffer[48..52].copy_from_slice(&0x0Fu32.to_le_bytes()); // ProcessAffinityMask
+    buffer[60..64].copy_from_slice(&0x0040_3000u32.to_le_bytes()); // SecurityCookie
+    buffer[64..68].copy_from_slice(&0x0040_4000u32.to_le_bytes()); // SEHandlerTable
+    buffer[68..72].copy_from_slice(&3u32.to_le_bytes()); // SEHandlerCount
+    buffer[88..92].copy_from_slice(&0x1000_0500u32.to_le_bytes()); // GuardFlags
+    buffer[92..96].copy_from_slice(&0xFFFF_FFFFu32.to_le_bytes()); // beyond Size
+
+    let config = LoadConfigDirectory::parse(&buffer, 0, PEType::PE32).unwrap();
+    assert_eq!(config.process_affinity_mask.as_ref().unwrap().value, 0x0F);
+    assert_eq!(config.security_cookie.value, 0x0040_3000);
+    assert_eq!(config.security_cookie.offset, 60);
+    assert_eq!(config.se_handler_table.as_ref().unwrap().value, 0x0040_4000);
+    assert_eq!(config.se_handler_count.as_ref().unwrap().value, 3);
+    assert_eq!(config.guard_flags(), 0x1000_0500);
+    assert_eq!(config.guard_table_stride(), 5);
+    assert!(config.code_integrity.is_none());
+    assert!(config.guard_address_taken_iat_entry_table.is_none());
+
+    // PE32+ with the full structure.
+    let mut buffer = vec![0u8; 0x140];
+    buffer[0..4].copy_from_slice(&0x140u32.to_le_bytes());
+    buffer[88..96].copy_from_slice(&0x1_4000_3000u64.to_le_bytes()); // SecurityCookie
+    buffer[144..148].copy_from_slice(&0x0040_0500u32.to_le_bytes()); // GuardFlags
+    buffer[150..152].copy_from_slice(&0xFFFFu16.to_le_bytes()); // CodeIntegrity.Catalog
+    buffer[200..208].copy_from_slice(&0x1_4000_6000u64.to_le_bytes()); // CHPEMetadataPointer
+    buffer[228..230].copy_from_slice(&4u16.to_le_bytes()); // DynamicValueRelocTableSection
+    buffer[256..264].copy_from_slice(&0x1_4000_7000u64.to_le_bytes()); // VolatileMetadataPointer
+    buffer[264..272].copy_from_slice(&0x1_4000_8000u64.to_le_bytes()); // GuardEHContinuationTable
+    buffer[272..280].copy_from_slice(&2u64.to_le_bytes()); // GuardEHContinuationCount
+    buffer[312..320].copy_from_slice(&0x1_4000_9000u64.to_le_bytes()); // GuardMemcpyFunctionPointer
+
+    let config = LoadConfigDirectory::parse(&buffer, 0, PEType::PE32Plus).unwrap();
+    assert_eq!(config.security_cookie.value, 0x1_4000_3000);
+    assert_eq!(config.security_cookie.size, 8);
+    assert_eq!(config.guard_flags(), 0x0040_0500);
+    assert_eq!(
+        config.code_integrity.as_ref().unwrap().catalog.value,
+        0xFFFF
+    );
+    assert_eq!(config.chpe_metadata_pointer.unwrap().offset, 200);
+    assert_eq!(config.dynamic_value_reloc_table_section.unwrap().value, 4);
+    assert_eq!(
+        config.volatile_metadata_pointer.unwrap().value,
+        0x1_4000_7000
+    );
+    assert_eq!(
+        config.guard_eh_continuation_table.unwrap().value,
+        0x1_4000_8000
+    );
+    assert_eq!(config.guard_eh_continuation_count.unwrap().value, 2);
+    let memcpy = config.guard_memcpy_function_pointer.unwrap();
+    assert_eq!((memcpy.value, memcpy.offset), (0x1_4000_9000, 312));
+}
+
+/// CFG tables referenced from the load config resolve through the image base.
+#[test]
+fn test_pe_guard_tables_synthetic() {
+    use pe::load_config::{
+        IMAGE_GUARD_CF_FUNCTION_TABLE_PRESENT, IMAGE_GUARD_CF_INSTRUMENTED,
+        IMAGE_GUARD_FLAG_FID_SUPPRESSED,
+    };
+
+    let mut pe = pe::PE::from_file("tests/samples/sample64.exe").expect("Failed to parse PE64");
+    assert!(pe.guard_cf_functions().unwrap().is_empty());
+
+    let image_base = match pe.optional_header.image_base.value {
+        pe::header::ImageBase::Base64(value) => value,
+        pe::header::ImageBase::Base32(value) => value as u64,
+    };
+    let text = pe.sections[0].pointer_to_raw_data.value as usize;
+    let text_rva = pe.sections[0].virtual_address.value;
+    let functions_rva = text_rva + 0x140;
+    let eh_rva = functions_rva + 0x10;
+    let guard_flags =
+        IMAGE_GUARD_CF_INSTRUMENTED | IMAGE_GUARD_CF_FUNCTION_TABLE_PRESENT | (1 << 28);
+
+    let config = &mut pe.buffer[text..text + 0x160];
+    config.fill(0);
+    config[0..4].copy_from_slice(&0x140u32.to_le_bytes());
+    config[128..136].copy_from_slice(&(image_base + functions_rva as u64).to_le_bytes());
+    config[136..144].copy_from_slice(&2u64.to_le_bytes());
+    config[144..148].copy_from_slice(&guard_flags.to_le_bytes());
+    config[264..272].copy_from_slice(&(image_base + eh_rva as u64).to_le_bytes());
+    config[272..280].copy_from_slice(&1u64.to_le_bytes());
+    config[0x140..0x144].copy_from_slice(&0x1010u32.to_le_bytes());
+    config[0x144] = IMAGE_GUARD_FLAG_FID_SUPPRESSED;
+    config[0x145..0x149].copy_from_slice(&0x1020u32.to_le_bytes());
+    config[0x150..0x154].copy_from_slice(&0x1030u32.to_le_bytes());
+    pe.sync_data_directory_rva(pe::header::LOAD_CONFIG, text_rva)
+        .unwrap();
+    pe.sync_data_directory_size(pe::header::LOAD_CONFIG, 0x140)
+        .unwrap();
+
+    let functions = pe.guard_cf_functions().unwrap();
+    assert_eq!(functions.len(), 2);
+    assert_eq!(functions[0].rva.value, 0x1010);
+    assert_eq!(
+        functions[0].flags.as_ref().unwrap().value,
+        IMAGE_GUARD_FLAG_FID_SUPPRESSED
+    );
+    assert_eq!(functions[1].rva.value, 0x1020);
+    assert_eq!(functions[1].rva.offset, text + 0x145);
+    let eh = pe.guard_eh_continuation_targets().unwrap();
+    assert_eq!(eh.len(), 1);
+    assert_eq!(eh[0].rva.value, 0x1030);
+    assert!(pe.guard_long_jump_targets().unwrap().is_empty());
+    assert!(pe.guard_address_taken_iat_entries().unwrap().is_empty());
+    assert!(pe.se_handlers().unwrap().is_empty());
+}
+
+/// DVRT v1 / v2 headers, per-symbol blocks and typed retpoline / ARM64X records.
+#[test]
+fn test_pe_dynamic_relocation_table_synthetic() {
+    use pe::dynamic_relocation::{
+        DynamicRelocationKind, DynamicRelocationTable, IMAGE_DYNAMIC_RELOCATION_ARM64X,
+        IMAGE_DYNAMIC_RELOCATION_FUNCTION_OVERRIDE,
+        IMAGE_DYNAMIC_RELOCATION_GUARD_IMPORT_CONTROL_TRANSFER,
+    };
+    use pe::header::PEType;
+
+    let mut fixups = Vec::new();
+    // Import control transfer: 32-bit records.
+    fixups.extend_from_slice(&IMAGE_DYNAMIC_RELOCATION_GUARD_IMPORT_CONTROL_TRANSFER.to_le_bytes());
+    fixups.extend_from_slice(&16u32.to_le_bytes());
+    fixups.extend_from_slice(&0x1000u32.to_le_bytes());
+    fixups.extend_from_slice(&16u32.to_le_bytes());
+    fixups.extend_from_slice(&(0x10u32 | 1 << 12 | 5 << 13).to_le_bytes());
+    fixups.extend_from_slice(&(0x20u32 | 7 << 13).to_le_bytes());
+    // ARM64X: zero fill, 8-byte value, negative delta scaled by 8.
+    fixups.extend_from_slice(&IMAGE_DYNAMIC_RELOCATION_ARM64X.to_le_bytes());
+    fixups.extend_from_slice(&24u32.to_le_bytes());
+    fixups.extend_from_slice(&0x2000u32.to_le_bytes());
+    fixups.extend_from_slice(&24u32.to_le_bytes());
+    fixups.extend_from_slice(&(0x008u16 | 2 << 14).to_le_bytes());
+    fixups.extend_from_slice(&(0x010u16 | 1 << 12 | 3 << 14).to_le_bytes());
+    fixups.extend_from_slice(&0x1122_3344_5566_7788u64.to_le_bytes());
+    fixups.extend_from_slice(&(0x018u16 | 2 << 12 | 3 << 14).to_le_bytes());
+    fixups.extend_from_slice(&2u16.to_le_bytes());
+    // Function override: kept raw.
+    fixups.extend_from_slice(&IMAGE_DYNAMIC_RELOCATION_FUNCTION_OVERRIDE.to_le_bytes());
+    fixups.extend_from_slice(&4u32.to_le_bytes());
+    fixups.extend_from_slice(&[0xAA; 4]);
+
+    let mut buffer = vec![0u8; 8];
+    buffer.extend_from_slice(&1u32.to_le_bytes());
+    buffer.extend_from_slice(&(fixups.len() as u32).to_le_bytes());
+    buffer.extend_from_slice(&fixups);
+
+    let table = DynamicRelocationTable::parse(&buffer, 8, PEType::PE32Plus).unwrap();
+    assert_eq!(table.version.value, 1);
+    assert_eq!(table.relocations.len(), 3);
+
+    let imports = &table.relocations[0].blocks[0];
+    assert_eq!(imports.page_rva.value, 0x1000);
+    assert_eq!(imports.records.len(), 2);
+    assert_eq!(imports.records[0].rva(imports.page_rva.value), 0x1010);
+    assert_eq!(imports.records[0].raw.size, 4);
+    assert!(matches!(
+        imports.records[0].kind,
+        DynamicRelocationKind::ImportControlTransfer {
+            indirect_call: true,
+            iat_index: 5
+        }
+    ));
+    assert!(matches!(
+        imports.records[1].kind,
+        DynamicRelocationKind::ImportControlTransfer {
+            indirect_call: false,
+            iat_index: 7
+        }
+    ));
+
+    let arm64x = &table.relocations[1].blocks[0].records;
+    assert_eq!(arm64x.len(), 3);
+    assert!(matches!(
+        arm64x[0].kind,
+        DynamicRelocationKind::Arm64xZeroFill { size: 4 }
+    ));
+    match &arm64x[1].kind {
+        DynamicRelocationKind::Arm64xValue { value } => {
+            assert_eq!(value.value, 0x1122_3344_5566_7788);
+            assert_eq!(value.size, 8);
+        }
+        other => panic!("unexpected record {other:?}"),
+    }
+    assert_eq!(arm64x[2].page_offset, 0x18);
+    assert!(matches!(
+        arm64x[2].kind,
+        DynamicRelocationKind::Arm64xDelta { delta: -16, .. }
+    ));
+
+    let raw = &table.relocations[2];
+    assert!(raw.blocks.is_empty());
+    assert_eq!(&buffer[raw.fixup_offset..raw.fixup_offset + 4], [0xAA; 4]);
+
+    // v2 header on PE32 with a padded indirect control transfer block.
+    let mut buffer = Vec::new();
+    buffer.extend_from_slice(&2u32.to_le_bytes());
+    buffer.extend_from_slice(&32u32.to_le_bytes());
+    buffer.extend_from_slice(&20u32.to_le_bytes()); // HeaderSize
+    buffer.extend_from_slice(&12u32.to_le_bytes()); // FixupInfoSize
+    buffer.extend_from_slice(&4u32.to_le_bytes()); // Symbol
+    buffer.extend_from_slice(&9u32.to_le_bytes()); // SymbolGroup
+    buffer.extend_from_slice(&1u32.to_le_bytes()); // Flags
+    buffer.extend_from_slice(&0x3000u32.to_le_bytes());
+    buffer.extend_from_slice(&12u32.to_le_bytes());
+    buffer.extend_from_slice(&(0x030u16 | 1 << 12 | 1 << 14).to_le_bytes());
+    buffer.extend_from_slice(&0u16.to_le_bytes());
+
+    let table = DynamicRelocationTable::parse(&buffer, 0, PEType::PE32).unwrap();
+    let relocation = &table.relocations[0];
+    assert_eq!(relocation.header_size.as_ref().unwrap().value, 20);
+    assert_eq!(relocation.symbol.size, 4);
+    assert_eq!(relocation.symbol_group.as_ref().unwrap().value, 9);
+    assert_eq!(relocation.blocks[0].records.len(), 1);
+    assert!(matches!(
+        relocation.blocks[0].records[0].kind,
+        DynamicRelocationKind::IndirectControlTransfer {
+            indirect_call: true,
+            rex_w_prefix: false,
+            cfg_check: true
+        }
+    ));
+}
+
 /// Absent optional directories return `None` without error.
 #[test]
 fn test_pe_optional_directories_absent() {
@@ -938,39 +1780,325 @@ fn test_pe_bound_and_delay_import_synthetic() {
     )));
 }
 
-/// Synthetic resource tree with one named leaf.
+/// Adding and converting delay-load imports keeps the overlay and certificate table.
 #[test]
-fn test_pe_resource_tree_synthetic() {
-    use pe::resource::{ResourceEntry, ResourceTree};
+fn test_pe_delay_import_keeps_overlay_and_signature() {
+    let mut pe = pe::PE::from_file("tests/samples/sample1.exe").expect("Failed to parse PE32");
+    sign_with_overlay(&mut pe, &[0xaa; 24]);
+    pe.add_delay_import("USER32.dll", &["MessageBoxA"]).unwrap();
+    let mut pe = pe::PE::from_buffer(pe.buffer).unwrap();
+    assert_eq!(pe.sections.last().unwrap().name_str(), ".didat2");
+    assert_signed_overlay(&pe, &[0xaa; 24]);
+
+    assert!(pe.convert_delay_import("USER32.dll").unwrap());
+    let pe = pe::PE::from_buffer(pe.buffer).unwrap();
+    assert_eq!(pe.sections.last().unwrap().name_str(), ".idata2");
+    assert_signed_overlay(&pe, &[0xaa; 24]);
+}
 
-    let mut buffer = vec![0u8; 0x200];
-    let resource_base = 0usize;
+/// Binding imports against target images, including a forwarder, then stripping the binding.
+#[test]
+fn test_pe_bind_and_strip_bound_imports() {
+    use pe::export::FunctionExport;
+    use pe::export_builder::ExportTarget;
+    use pe::header::ImageBase;
 
-    // Root directory: 1 named entry, 0 id entries
-    buffer[0x0c..0x0e].copy_from_slice(&1u16.to_le_bytes()); // named entries
-                                                             // Entry: name offset 0x30, data offset 0x50 (leaf)
-    buffer[0x10..0x14].copy_from_slice(&0x30u32.to_le_bytes());
-    buffer[0x14..0x18].copy_from_slice(&0x50u32.to_le_bytes()); // leaf (high bit clear)
-                                                                // Unicode name at 0x30: length 4 chars "TEST"
-    buffer[0x30..0x32].copy_from_slice(&4u16.to_le_bytes());
-    buffer[0x32..0x3a].copy_from_slice(b"T\x00E\x00S\x00T\x00");
-    // Data entry at 0x50
-    buffer[0x50..0x54].copy_from_slice(&0x1200u32.to_le_bytes());
-    buffer[0x54..0x58].copy_from_slice(&16u32.to_le_bytes());
-    buffer[0x58..0x5c].copy_from_slice(&0u32.to_le_bytes());
-    buffer[0x5c..0x60].copy_from_slice(&0u32.to_le_bytes());
+    let target = pe::PE::from_file("tests/samples/sample2.dll").expect("Failed to parse PE");
+    let mut relay = pe::PE::from_file("tests/samples/sample2.dll").expect("Failed to parse PE");
+    let mut exports = relay.export_builder().unwrap();
+    exports
+        .add(
+            "Relay",
+            ExportTarget::Forwarder("sample2.Divide".to_string()),
+        )
+        .unwrap();
+    relay.set_exports(&exports).unwrap();
+    relay
+        .coff_header
+        .time_date_stamp
+        .update(&mut relay.buffer, 0x1234_5678)
+        .unwrap();
+    let relay = pe::PE::from_buffer(relay.buffer).unwrap();
 
-    let sections = vec![pe::section::PeSection {
-        name: hexspell::field::Field::new(
-            hexspell::field::FixedBytes([b'.', b'r', b's', b'r', b'c', 0, 0, 0]),
-            0,
-            8,
-        ),
-        virtual_size: hexspell::field::Field::new(0x200, 8, 4),
-        virtual_address: hexspell::field::Field::new(0x1000, 12, 4),
-        size_of_raw_data: hexspell::field::Field::new(0x200, 16, 4),
-        pointer_to_raw_data: hexspell::field::Field::new(0, 20, 4),
-        pointer_to_relocations: hexspell::field::Field::new(0, 24, 4),
+    let ImageBase::Base32(base) = target.optional_header.image_base.value else {
+        panic!("expected a PE32 image base");
+    };
+    let export_va = |name: &str| {
+        let exports = target.exports().unwrap().unwrap();
+        let ordinal = exports
+            .named
+            .iter()
+            .find(|e| e.name == name)
+            .unwrap()
+            .ordinal;
+        exports
+            .functions
+            .iter()
+            .find_map(|function| match function {
+                FunctionExport::Local {
+                    ordinal: o,
+                    function_rva,
+                    ..
+                } if *o == ordinal => Some(base + function_rva.value),
+                _ => None,
+            })
+            .unwrap()
+    };
+
+    let mut pe = pe::PE::from_file("tests/samples/sample1.exe").expect("Failed to parse PE32");
+    let direct = pe.add_import("sample2.dll", &["Add", "Multiply"]).unwrap();
+    let forwarded = pe.add_import("relay.dll", &["Relay"]).unwrap();
+    let resolver = |dll: &str| match dll.to_ascii_lowercase().as_str() {
+        "sample2.dll" => Some(&target),
+        "relay.dll" => Some(&relay),
+        _ => None,
+    };
+
+    // A directory that outgrows the header slack leaves the previous binding in place.
+    let only_target = |dll: &str| dll.eq_ignore_ascii_case("sample2.dll").then_some(&target);
+    assert_eq!(pe.bind_imports(only_target).unwrap(), 1);
+    let blocker = pe
+        .bound_imports()
+        .unwrap()
+        .expect("bound import directory")
+        .offset
+        + 40;
+    pe.buffer[blocker] = 0xcc;
+    let unchanged = pe.buffer.clone();
+    assert!(pe.bind_imports(resolver).is_err());
+    assert_eq!(pe.buffer, unchanged);
+    pe.buffer[blocker] = 0;
+
+    assert_eq!(pe.bind_imports(resolver).unwrap(), 2);
+
+    let read = |pe: &pe::PE, rva: u32| {
+        let offset = pe.rva_to_offset(rva).unwrap();
+        u32::from_le_bytes(pe.buffer[offset..offset + 4].try_into().unwrap())
+    };
+    assert_eq!(read(&pe, direct[0]), export_va("Add"));
+    assert_eq!(read(&pe, direct[1]), export_va("Multiply"));
+    assert_eq!(read(&pe, forwarded[0]), export_va("Divide"));
+
+    let imports = pe.imports().unwrap();
+    for dll in &imports.dlls {
+        let bound = matches!(dll.dll_name.as_str(), "sample2.dll" | "relay.dll");
+        let expected = if bound { u32::MAX } else { 0 };
+        assert_eq!(dll.descriptor.time_date_stamp.value, expected);
+    }
+    let bound = pe.bound_imports().unwrap().expect("bound import directory");
+    let sections_end = {
+        let last = pe.sections.last().unwrap();
+        last.characteristics.offset + last.characteristics.size
+    };
+    assert_eq!(bound.offset, sections_end);
+    assert_eq!(bound.modules.len(), 2);
+    assert_eq!(bound.modules[0].module_name, "sample2.dll");
+    assert_eq!(
+        bound.modules[0].descriptor.time_date_stamp.value,
+        target.coff_header.time_date_stamp.value
+    );
+    assert_eq!(bound.modules[1].module_name, "relay.dll");
+    assert_eq!(
+        bound.modules[1].descriptor.time_date_stamp.value,
+        0x1234_5678
+    );
+    assert_eq!(bound.modules[1].forwarder_refs.len(), 1);
+    assert_eq!(
+        bound.modules[1].forwarder_refs[0].time_date_stamp.value,
+        target.coff_header.time_date_stamp.value
+    );
+    assert_eq!(pe.optional_header.checksum.value, pe.calc_checksum());
+
+    // Binding again replaces the directory with an identical one.
+    let bound_buffer = pe.buffer.clone();
+    pe.bind_imports(resolver).unwrap();
+    assert_eq!(pe.buffer, bound_buffer);
+
+    // A section inserted afterwards moves the directory behind its header.
+    let mut grown = pe::PE::from_buffer(pe.buffer.clone()).unwrap();
+    grown
+        .insert_section(pe::section::NewSection {
+            name: ".extra".to_string(),
+            data: vec![0; 0x10],
+            characteristics: pe::section::INITIALIZED_DATA | pe::section::READ,
+        })
+        .unwrap();
+    let grown = pe::PE::from_buffer(grown.buffer).unwrap();
+    let moved = grown
+        .bound_imports()
+        .unwrap()
+        .expect("bound import directory");
+    assert_eq!(moved.offset, sections_end + 40);
+    let names: Vec<_> = moved
+        .modules
+        .iter()
+        .map(|m| m.module_name.as_str())
+        .collect();
+    assert_eq!(names, ["sample2.dll", "relay.dll"]);
+    assert_eq!(moved.modules[1].forwarder_refs.len(), 1);
+
+    pe.strip_bound_imports().unwrap();
+    assert!(pe.bound_imports().unwrap().is_none());
+    let bound_end = bound.offset + bound.modules.len() * 8 + 16;
+    assert!(pe.buffer[bound.offset..bound_end].iter().all(|&b| b == 0));
+    for dll in pe.imports().unwrap().dlls {
+        assert_eq!(dll.descriptor.time_date_stamp.value, 0);
+        assert_eq!(dll.descriptor.forwarder_chain.value, 0);
+        if !matches!(dll.dll_name.as_str(), "sample2.dll" | "relay.dll") {
+            continue;
+        }
+        for (index, entry) in dll.entries.iter().enumerate() {
+            let thunk = match entry {
+                pe::import::ImportEntry::Ordinal { thunk, .. }
+                | pe::import::ImportEntry::ByName { thunk, .. } => thunk,
+            };
+            let slot = dll.descriptor.first_thunk.value + index as u32 * 4;
+            assert_eq!(read(&pe, slot) as u64, thunk.raw_value());
+        }
+    }
+}
+
+/// Delay-load descriptors: add, thunk, convert to regular imports and remove.
+#[test]
+fn test_pe_delay_import_editing() {
+    use pe::import::ImportEntry;
+    use pe::relocation::IMAGE_REL_BASED_DIR64;
+
+    let mut pe = pe::PE::from_file("tests/samples/sample64.exe").expect("Failed to parse PE64");
+    assert!(pe.delay_imports().unwrap().is_none());
+    let import_count = pe.imports().unwrap().dlls.len();
+
+    let slots = pe
+        .add_delay_import("USER32.dll", &["MessageBoxA", "GetDC"])
+        .unwrap();
+    assert_eq!(slots[1], slots[0] + 8);
+    assert_eq!(pe.sections.last().unwrap().name_str(), ".didat2");
+    pe.set_delay_import_thunk(slots[0], 0x1000).unwrap();
+    pe.set_delay_import_thunk(slots[1], 0x1010).unwrap();
+    let offset = pe.rva_to_offset(slots[0]).unwrap();
+    assert_eq!(
+        u64::from_le_bytes(pe.buffer[offset..offset + 8].try_into().unwrap()),
+        0x1_4000_1000
+    );
+    assert_eq!(
+        pe.base_relocation_builder().unwrap().entries.get(&slots[0]),
+        Some(&IMAGE_REL_BASED_DIR64)
+    );
+
+    let shell32 = pe
+        .add_delay_import("SHELL32.dll", &["ShellExecuteW"])
+        .unwrap();
+    assert!(pe.add_delay_import("user32.dll", &["GetDC"]).is_err());
+    // Writing the same descriptors again reuses the current directory.
+    let (sections, unchanged) = (pe.sections.len(), pe.buffer.clone());
+    let builder = pe.delay_import_builder().unwrap();
+    pe.set_delay_imports(&builder).unwrap();
+    assert_eq!(pe.sections.len(), sections);
+    assert_eq!(pe.buffer, unchanged);
+    let pe = pe::PE::from_buffer(pe.buffer).unwrap();
+    assert_eq!(pe.optional_header.checksum.value, pe.calc_checksum());
+    let delay = pe.delay_imports().unwrap().expect("delay-load directory");
+    assert_eq!(delay.dlls.len(), 2);
+    let user32 = &delay.dlls[0];
+    assert_eq!(user32.dll_name, "USER32.dll");
+    assert_eq!(user32.descriptor.attributes.value, 1);
+    assert_eq!(
+        user32.descriptor.delay_import_address_table_rva.value,
+        slots[0]
+    );
+    assert_eq!(
+        delay.dlls[1]
+            .descriptor
+            .delay_import_address_table_rva
+            .value,
+        shell32[0]
+    );
+    let names: Vec<_> = user32
+        .entries
+        .iter()
+        .filter_map(|e| match e {
+            ImportEntry::ByName { by_name, .. } => Some(by_name.name.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(names, ["MessageBoxA", "GetDC"]);
+
+    let mut pe = pe;
+    assert!(pe.convert_delay_import("user32.dll").unwrap());
+    assert!(!pe.convert_delay_import("user32.dll").unwrap());
+    let pe = pe::PE::from_buffer(pe.buffer).unwrap();
+    let imports = pe.imports().unwrap();
+    assert_eq!(imports.dlls.len(), import_count + 1);
+    let user32 = imports.dlls.last().unwrap();
+    assert_eq!(user32.dll_name, "USER32.dll");
+    assert_eq!(user32.descriptor.first_thunk.value, slots[0]);
+    assert_eq!(
+        pe::import::import_names_for_dll(&imports, "USER32.dll"),
+        ["MessageBoxA", "GetDC"]
+    );
+    for (index, entry) in user32.entries.iter().enumerate() {
+        let ImportEntry::ByName { thunk, .. } = entry else {
+            panic!("expected a by-name import");
+        };
+        let offset = pe.rva_to_offset(slots[index]).unwrap();
+        let slot = u64::from_le_bytes(pe.buffer[offset..offset + 8].try_into().unwrap());
+        assert_eq!(slot, thunk.raw_value());
+    }
+    let relocations = pe.base_relocation_builder().unwrap().entries;
+    assert!(!relocations.contains_key(&slots[0]));
+    assert!(!relocations.contains_key(&slots[1]));
+    let delay = pe.delay_imports().unwrap().expect("delay-load directory");
+    assert_eq!(delay.dlls.len(), 1);
+    assert_eq!(delay.dlls[0].dll_name, "SHELL32.dll");
+    assert_eq!(
+        delay.dlls[0]
+            .descriptor
+            .delay_import_address_table_rva
+            .value,
+        shell32[0]
+    );
+
+    let mut pe = pe;
+    assert!(pe.remove_delay_import("shell32.dll").unwrap());
+    assert!(!pe.remove_delay_import("shell32.dll").unwrap());
+    assert!(pe.delay_imports().unwrap().is_none());
+    let dir = &pe.optional_header.data_directories[pe::header::DELAY_IMPORT];
+    assert_eq!((dir.virtual_address.value, dir.size.value), (0, 0));
+}
+
+/// Synthetic resource tree with one named leaf.
+#[test]
+fn test_pe_resource_tree_synthetic() {
+    use pe::resource::{ResourceEntry, ResourceTree};
+
+    let mut buffer = vec![0u8; 0x200];
+    let resource_base = 0usize;
+
+    // Root directory: 1 named entry, 0 id entries
+    buffer[0x0c..0x0e].copy_from_slice(&1u16.to_le_bytes()); // named entries
+                                                             // Entry: name offset 0x30, data offset 0x50 (leaf)
+    buffer[0x10..0x14].copy_from_slice(&0x30u32.to_le_bytes());
+    buffer[0x14..0x18].copy_from_slice(&0x50u32.to_le_bytes()); // leaf (high bit clear)
+                                                                // Unicode name at 0x30: length 4 chars "TEST"
+    buffer[0x30..0x32].copy_from_slice(&4u16.to_le_bytes());
+    buffer[0x32..0x3a].copy_from_slice(b"T\x00E\x00S\x00T\x00");
+    // Data entry at 0x50
+    buffer[0x50..0x54].copy_from_slice(&0x1200u32.to_le_bytes());
+    buffer[0x54..0x58].copy_from_slice(&16u32.to_le_bytes());
+    buffer[0x58..0x5c].copy_from_slice(&0u32.to_le_bytes());
+    buffer[0x5c..0x60].copy_from_slice(&0u32.to_le_bytes());
+
+    let sections = vec![pe::section::PeSection {
+        name: hexspell::field::Field::new(
+            hexspell::field::FixedBytes([b'.', b'r', b's', b'r', b'c', 0, 0, 0]),
+            0,
+            8,
+        ),
+        virtual_size: hexspell::field::Field::new(0x200, 8, 4),
+        virtual_address: hexspell::field::Field::new(0x1000, 12, 4),
+        size_of_raw_data: hexspell::field::Field::new(0x200, 16, 4),
+        pointer_to_raw_data: hexspell::field::Field::new(0, 20, 4),
+        pointer_to_relocations: hexspell::field::Field::new(0, 24, 4),
         pointer_to_linenumbers: hexspell::field::Field::new(0, 28, 4),
         number_of_relocations: hexspell::field::Field::new(0, 32, 2),
         number_of_linenumbers: hexspell::field::Field::new(0, 34, 2),
@@ -993,6 +2121,453 @@ fn test_pe_resource_tree_synthetic() {
     }
 }
 
+/// Encodes one `VS_VERSIONINFO`-style block (header, key, value, children).
+fn version_block(key: &str, text: bool, value: &[u8], children: &[Vec<u8>]) -> Vec<u8> {
+    let mut block = vec![0u8; 6];
+    for unit in key.encode_utf16().chain([0]) {
+        block.extend_from_slice(&unit.to_le_bytes());
+    }
+    block.resize(block.len().next_multiple_of(4), 0);
+    block.extend_from_slice(value);
+    for child in children {
+        block.resize(block.len().next_multiple_of(4), 0);
+        block.extend_from_slice(child);
+    }
+    let length = block.len() as u16;
+    let value_length = if text { value.len() / 2 } else { value.len() };
+    block[0..2].copy_from_slice(&length.to_le_bytes());
+    block[2..4].copy_from_slice(&(value_length as u16).to_le_bytes());
+    block[4..6].copy_from_slice(&(text as u16).to_le_bytes());
+    block
+}
+
+fn version_string(key: &str, value: &str) -> Vec<u8> {
+    version_block(key, true, &utf16z(value), &[])
+}
+
+/// `VS_VERSIONINFO` in an inserted `.rsrc` section on sample64.exe.
+#[test]
+fn test_pe_version_info_synthetic() {
+    let mut pe = pe::PE::from_file("tests/samples/sample64.exe").expect("Failed to parse PE64");
+    assert!(pe.version_info().unwrap().is_none());
+
+    let mut fixed = Vec::new();
+    for word in [
+        0xFEEF_04BDu32,
+        0x0001_0000,
+        0x0001_0002, // file version 1.2
+        0x0003_0004, // .3.4
+        0x0005_0006, // product version 5.6
+        0x0007_0008, // .7.8
+        0x3F,
+        0,
+        0x0004_0004, // VOS_NT_WINDOWS32
+        1,           // VFT_APP
+        0,
+        0,
+        0,
+    ] {
+        fixed.extend_from_slice(&word.to_le_bytes());
+    }
+    let table = version_block(
+        "040904B0",
+        false,
+        &[],
+        &[
+            version_string("CompanyName", "HexSpell"),
+            version_string("OriginalFilename", "sample64.exe"),
+            version_string("Comments", ""),
+        ],
+    );
+    let translation = version_block("Translation", false, &[0x09, 0x04, 0xB0, 0x04], &[]);
+    let blob = version_block(
+        "VS_VERSION_INFO",
+        false,
+        &fixed,
+        &[
+            version_block("StringFileInfo", false, &[], &[table]),
+            version_block("VarFileInfo", false, &[], &[translation]),
+        ],
+    );
+
+    let mut data = vec![0u8; 0x60 + blob.len()];
+    pe.insert_section(pe::section::NewSection {
+        name: ".rsrc".to_string(),
+        data: data.clone(),
+        characteristics: pe::section::INITIALIZED_DATA | pe::section::READ,
+    })
+    .unwrap();
+    let rsrc_rva = pe.sections.last().unwrap().virtual_address.value;
+    let rsrc_offset = pe.sections.last().unwrap().pointer_to_raw_data.value as usize;
+
+    // Root -> RT_VERSION (16) -> ID 1 -> language 0x409 -> data entry at 0x48.
+    for (dir, id, target) in [
+        (0x00usize, 16u32, 0x8000_0018u32),
+        (0x18, 1, 0x8000_0030),
+        (0x30, 0x409, 0x48),
+    ] {
+        data[dir + 14..dir + 16].copy_from_slice(&1u16.to_le_bytes());
+        data[dir + 16..dir + 20].copy_from_slice(&id.to_le_bytes());
+        data[dir + 20..dir + 24].copy_from_slice(&target.to_le_bytes());
+    }
+    data[0x48..0x4c].copy_from_slice(&(rsrc_rva + 0x60).to_le_bytes());
+    data[0x4c..0x50].copy_from_slice(&(blob.len() as u32).to_le_bytes());
+    data[0x60..].copy_from_slice(&blob);
+    pe.buffer[rsrc_offset..rsrc_offset + data.len()].copy_from_slice(&data);
+
+    let directory = &mut pe.optional_header.data_directories[pe::header::RESOURCE];
+    directory
+        .virtual_address
+        .update(&mut pe.buffer, rsrc_rva)
+        .unwrap();
+    directory
+        .size
+        .update(&mut pe.buffer, data.len() as u32)
+        .unwrap();
+
+    let info = pe.version_info().unwrap().expect("version resource");
+    assert_eq!(info.offset, rsrc_offset + 0x60);
+    let fixed = info.fixed.as_ref().expect("VS_FIXEDFILEINFO");
+    assert_eq!(fixed.file_version(), (1, 2, 3, 4));
+    assert_eq!(fixed.product_version(), (5, 6, 7, 8));
+    assert_eq!(fixed.file_type.value, 1);
+    assert_eq!(fixed.signature.offset, rsrc_offset + 0x60 + 40);
+
+    assert_eq!(info.string_tables.len(), 1);
+    let table = &info.string_tables[0];
+    assert_eq!(table.key, "040904B0");
+    assert_eq!(table.language(), Some(0x0409));
+    assert_eq!(table.code_page(), Some(0x04B0));
+    assert_eq!(table.strings.len(), 3);
+    assert_eq!(table.get("CompanyName"), Some("HexSpell"));
+    assert_eq!(info.string("OriginalFilename"), Some("sample64.exe"));
+    assert_eq!(info.string("Comments"), Some(""));
+    assert_eq!(info.string("ProductName"), None);
+
+    assert_eq!(info.translations.len(), 1);
+    assert_eq!(info.translations[0].language.value, 0x0409);
+    assert_eq!(info.translations[0].code_page.value, 0x04B0);
+}
+
+/// `ResourceBuilder` output lands in a new `.rsrc` section, then is rewritten in place.
+#[test]
+fn test_pe_set_resources_round_trip() {
+    use pe::resource::ResourceEntry;
+    use pe::resource_builder::ResourceName::{Id, Name};
+
+    let mut pe = pe::PE::from_file("tests/samples/sample64.exe").expect("Failed to parse PE64");
+    let section_count = pe.sections.len();
+    let mut builder = pe.resource_builder().unwrap();
+    assert!(builder.leaves.is_empty());
+
+    let table = version_block(
+        "040904B0",
+        false,
+        &[],
+        &[version_string("ProductName", "HexSpell")],
+    );
+    let blob = version_block(
+        "VS_VERSION_INFO",
+        false,
+        &[],
+        &[version_block("StringFileInfo", false, &[], &[table])],
+    );
+    builder.set(Id(16), Id(1), 0x409, blob);
+    builder.set(Id(24), Id(1), 0, b"<assembly/>".to_vec());
+    builder.set(
+        Name("CUSTOM".into()),
+        Name("blob".into()),
+        0x409,
+        vec![1, 2, 3],
+    );
+    pe.set_resources(&builder).unwrap();
+
+    assert_eq!(pe.sections.len(), section_count + 1);
+    let rsrc_rva = pe.sections.last().unwrap().virtual_address.value;
+    let directory = &pe.optional_header.data_directories[pe::header::RESOURCE];
+    assert_eq!(directory.virtual_address.value, rsrc_rva);
+    assert_eq!(pe.optional_header.checksum.value, pe.calc_checksum());
+
+    let reparsed = pe::PE::from_buffer(pe.buffer.clone()).unwrap();
+    assert_eq!(reparsed.resource_builder().unwrap(), builder);
+    let info = reparsed.version_info().unwrap().expect("version resource");
+    assert_eq!(info.string("ProductName"), Some("HexSpell"));
+    let tree = reparsed.resources().unwrap().unwrap();
+    let top: Vec<(Option<String>, Option<u16>)> = tree
+        .root
+        .entries
+        .iter()
+        .map(|entry| match entry {
+            ResourceEntry::Directory { name, id, .. } => (name.clone(), *id),
+            ResourceEntry::Data { .. } => panic!("expected type directory"),
+        })
+        .collect();
+    assert_eq!(
+        top,
+        [
+            (Some("CUSTOM".to_string()), None),
+            (None, Some(16)),
+            (None, Some(24))
+        ]
+    );
+
+    // A smaller tree fits the existing section and is rewritten at the same RVA.
+    builder.remove(&Id(24), &Id(1), 0).expect("manifest leaf");
+    builder.set(
+        Name("CUSTOM".into()),
+        Name("blob".into()),
+        0x409,
+        vec![9; 8],
+    );
+    pe.set_resources(&builder).unwrap();
+    assert_eq!(pe.sections.len(), section_count + 1);
+    let directory = &pe.optional_header.data_directories[pe::header::RESOURCE];
+    assert_eq!(directory.virtual_address.value, rsrc_rva);
+
+    let reparsed = pe::PE::from_buffer(pe.buffer.clone()).unwrap();
+    let rebuilt = reparsed.resource_builder().unwrap();
+    assert_eq!(rebuilt, builder);
+    assert_eq!(
+        rebuilt.get(&Name("CUSTOM".into()), &Name("blob".into()), 0x409),
+        Some(&[9u8; 8][..])
+    );
+    assert!(rebuilt.get(&Id(24), &Id(1), 0).is_none());
+}
+
+/// WIN_CERTIFICATE appended by [`sign_with_overlay`]: a 100-byte PKCS#7 placeholder padded to 8.
+fn test_certificate() -> Vec<u8> {
+    let mut certificate = 108u32.to_le_bytes().to_vec();
+    certificate.extend_from_slice(&0x0200u16.to_le_bytes());
+    certificate.extend_from_slice(&0x0002u16.to_le_bytes());
+    certificate.extend_from_slice(&[0x30; 100]);
+    certificate.resize(112, 0);
+    certificate
+}
+
+/// File offset where the headers and section raw data end.
+fn raw_image_end(pe: &pe::PE) -> usize {
+    pe.sections
+        .iter()
+        .filter(|section| section.size_of_raw_data.value != 0)
+        .map(|section| {
+            section.pointer_to_raw_data.value as usize + section.size_of_raw_data.value as usize
+        })
+        .max()
+        .unwrap_or(0)
+        .max(pe.optional_header.size_of_headers.value as usize)
+}
+
+/// Replaces the file tail with `overlay` followed by an 8-byte aligned certificate table.
+fn sign_with_overlay(pe: &mut pe::PE, overlay: &[u8]) {
+    let end = raw_image_end(pe);
+    pe.buffer.truncate(end);
+    pe.buffer.extend_from_slice(overlay);
+    let offset = pe.buffer.len().next_multiple_of(8);
+    pe.buffer.resize(offset, 0);
+    pe.buffer.extend_from_slice(&test_certificate());
+    pe.sync_data_directory_rva(pe::header::SECURITY, offset as u32)
+        .unwrap();
+    pe.sync_data_directory_size(pe::header::SECURITY, 112)
+        .unwrap();
+}
+
+/// Asserts the tail written by [`sign_with_overlay`] still follows the image and the checksum
+/// is current.
+fn assert_signed_overlay(pe: &pe::PE, overlay: &[u8]) {
+    let end = raw_image_end(pe);
+    assert_eq!(&pe.buffer[end..end + overlay.len()], overlay);
+    let security = &pe.optional_header.data_directories[pe::header::SECURITY];
+    let offset = security.virtual_address.value as usize;
+    assert_eq!(offset, (end + overlay.len()).next_multiple_of(8));
+    assert_eq!(security.size.value, 112);
+    assert_eq!(&pe.buffer[offset..], test_certificate().as_slice());
+    assert_eq!(pe.optional_header.checksum.value, pe.calc_checksum());
+}
+
+/// set_resources keeps the overlay and signature and never zeroes bytes past the old tree.
+#[test]
+fn test_pe_set_resources_keeps_tail_and_section_data() {
+    use pe::resource_builder::ResourceName::Id;
+
+    let mut pe = pe::PE::from_file("tests/samples/sample64.exe").expect("Failed to parse PE64");
+    sign_with_overlay(&mut pe, &[0xaa; 16]);
+    let mut builder = pe.resource_builder().unwrap();
+    builder.set(Id(10), Id(1), 0, vec![1; 64]);
+    pe.set_resources(&builder).unwrap();
+    let rsrc = pe.sections.last().unwrap();
+    assert_eq!(rsrc.name_str(), ".rsrc");
+    assert_signed_overlay(&pe, &[0xaa; 16]);
+
+    // Data placed after the tree survives an in-place rewrite of a smaller tree.
+    let rsrc_rva = rsrc.virtual_address.value;
+    let tree_size = pe.optional_header.data_directories[pe::header::RESOURCE]
+        .size
+        .value as usize;
+    let marker = rsrc.pointer_to_raw_data.value as usize + tree_size + 8;
+    pe.buffer[marker..marker + 4].copy_from_slice(b"KEEP");
+    builder.set(Id(10), Id(1), 0, vec![2; 8]);
+    pe.set_resources(&builder).unwrap();
+    let directory = &pe.optional_header.data_directories[pe::header::RESOURCE];
+    assert_eq!(directory.virtual_address.value, rsrc_rva);
+    assert_eq!(&pe.buffer[marker..marker + 4], b"KEEP");
+
+    // A larger tree cannot grow over that data and moves to a new section.
+    builder.set(Id(10), Id(1), 0, vec![3; 256]);
+    pe.set_resources(&builder).unwrap();
+    let directory = &pe.optional_header.data_directories[pe::header::RESOURCE];
+    assert_ne!(directory.virtual_address.value, rsrc_rva);
+    assert_eq!(&pe.buffer[marker..marker + 4], b"KEEP");
+    let reparsed = pe::PE::from_buffer(pe.buffer.clone()).unwrap();
+    assert_eq!(reparsed.resource_builder().unwrap(), builder);
+    assert_signed_overlay(&reparsed, &[0xaa; 16]);
+}
+
+fn utf16z(text: &str) -> Vec<u8> {
+    text.encode_utf16()
+        .chain([0])
+        .flat_map(u16::to_le_bytes)
+        .collect()
+}
+
+/// Typed decoders for icons, manifests, string tables, message tables, and dialogs.
+#[test]
+fn test_pe_typed_resources_synthetic() {
+    use pe::resource_builder::ResourceBuilder;
+    use pe::resource_builder::ResourceName::{Id, Name};
+    use pe::resource_types::{RT_DIALOG, RT_GROUP_ICON, RT_ICON, RT_MANIFEST};
+    use pe::resource_types::{RT_MESSAGETABLE, RT_STRING};
+
+    let mut builder = ResourceBuilder::new();
+
+    // Two icon images and a group that references them.
+    builder.set(Id(RT_ICON), Id(1), 0x409, vec![0xAA; 40]);
+    builder.set(Id(RT_ICON), Id(2), 0x409, vec![0xBB; 24]);
+    let mut group = vec![0, 0, 1, 0, 2, 0];
+    for (size, bytes, id) in [(16u8, 40u32, 1u16), (32, 24, 2)] {
+        group.extend_from_slice(&[size, size, 0, 0]);
+        group.extend_from_slice(&1u16.to_le_bytes());
+        group.extend_from_slice(&32u16.to_le_bytes());
+        group.extend_from_slice(&bytes.to_le_bytes());
+        group.extend_from_slice(&id.to_le_bytes());
+    }
+    builder.set(Id(RT_GROUP_ICON), Name("MAINICON".into()), 0x409, group);
+
+    let mut manifest = b"\xEF\xBB\xBF".to_vec();
+    manifest.extend_from_slice(b"<assembly/>");
+    builder.set(Id(RT_MANIFEST), Id(1), 0, manifest);
+
+    // Block 2 holds IDs 16..=31; only slots 0 and 3 are populated.
+    let mut block = Vec::new();
+    for slot in 0..16 {
+        let text = match slot {
+            0 => "first",
+            3 => "fourth",
+            _ => "",
+        };
+        let units: Vec<u16> = text.encode_utf16().collect();
+        block.extend_from_slice(&(units.len() as u16).to_le_bytes());
+        block.extend(units.iter().flat_map(|u| u.to_le_bytes()));
+    }
+    builder.set(Id(RT_STRING), Id(2), 0x409, block);
+
+    // One block (IDs 100..=101): an ANSI entry and a UTF-16 entry.
+    let mut table = Vec::new();
+    table.extend_from_slice(&1u32.to_le_bytes());
+    table.extend_from_slice(&100u32.to_le_bytes());
+    table.extend_from_slice(&101u32.to_le_bytes());
+    table.extend_from_slice(&16u32.to_le_bytes());
+    table.extend_from_slice(&8u16.to_le_bytes());
+    table.extend_from_slice(&0u16.to_le_bytes());
+    table.extend_from_slice(b"Hi\r\n");
+    let unicode = utf16z("Bye");
+    table.extend_from_slice(&(4 + unicode.len() as u16).to_le_bytes());
+    table.extend_from_slice(&1u16.to_le_bytes());
+    table.extend_from_slice(&unicode);
+    builder.set(Id(RT_MESSAGETABLE), Id(1), 0x409, table);
+
+    // DLGTEMPLATEEX with a font and one push button.
+    let mut dialog = Vec::new();
+    dialog.extend_from_slice(&1u16.to_le_bytes());
+    dialog.extend_from_slice(&0xFFFFu16.to_le_bytes());
+    dialog.extend_from_slice(&0u32.to_le_bytes()); // helpID
+    dialog.extend_from_slice(&0u32.to_le_bytes()); // exStyle
+    dialog.extend_from_slice(&0x80C8_0048u32.to_le_bytes()); // style | DS_SHELLFONT
+    dialog.extend_from_slice(&1u16.to_le_bytes());
+    for v in [0i16, 0, 120, 60] {
+        dialog.extend_from_slice(&v.to_le_bytes());
+    }
+    dialog.extend_from_slice(&[0, 0, 0, 0]); // no menu, default class
+    dialog.extend_from_slice(&utf16z("About"));
+    dialog.extend_from_slice(&8u16.to_le_bytes());
+    dialog.extend_from_slice(&400u16.to_le_bytes());
+    dialog.extend_from_slice(&[0, 1]);
+    dialog.extend_from_slice(&utf16z("MS Shell Dlg"));
+    dialog.resize(dialog.len().next_multiple_of(4), 0);
+    dialog.extend_from_slice(&0u32.to_le_bytes());
+    dialog.extend_from_slice(&0u32.to_le_bytes());
+    dialog.extend_from_slice(&0x5001_0001u32.to_le_bytes());
+    for v in [35i16, 40, 50, 14] {
+        dialog.extend_from_slice(&v.to_le_bytes());
+    }
+    dialog.extend_from_slice(&1u32.to_le_bytes()); // IDOK
+    dialog.extend_from_slice(&[0xFF, 0xFF, 0x80, 0x00]); // button atom
+    dialog.extend_from_slice(&utf16z("OK"));
+    dialog.extend_from_slice(&0u16.to_le_bytes());
+    builder.set(Id(RT_DIALOG), Id(100), 0x409, dialog);
+
+    let mut pe = pe::PE::from_file("tests/samples/sample64.exe").expect("Failed to parse PE64");
+    pe.set_resources(&builder).unwrap();
+
+    let icons = pe.icon_groups().unwrap();
+    assert_eq!(icons.len(), 1);
+    assert_eq!(icons[0].name, Name("MAINICON".into()));
+    assert_eq!(icons[0].entries[1].id, 2);
+    let ico = &icons[0].ico;
+    assert_eq!(ico.len(), 6 + 2 * 16 + 40 + 24);
+    assert_eq!(&ico[0..6], &[0, 0, 1, 0, 2, 0]);
+    assert_eq!(ico[6], 16);
+    assert_eq!(u32::from_le_bytes(ico[18..22].try_into().unwrap()), 38);
+    assert_eq!(u32::from_le_bytes(ico[34..38].try_into().unwrap()), 78);
+    assert_eq!(&ico[38..78], &[0xAA; 40]);
+    assert_eq!(&ico[78..], &[0xBB; 24]);
+
+    let manifests = pe.manifests().unwrap();
+    assert_eq!(manifests.len(), 1);
+    assert_eq!(manifests[0].text, "<assembly/>");
+
+    let strings = pe.string_resources().unwrap();
+    let pairs: Vec<(u32, &str)> = strings.iter().map(|s| (s.id, s.value.as_str())).collect();
+    assert_eq!(pairs, [(16, "first"), (19, "fourth")]);
+    assert!(strings.iter().all(|s| s.language == 0x409));
+
+    let tables = pe.message_tables().unwrap();
+    assert_eq!(tables.len(), 1);
+    let messages: Vec<(u32, &str)> = tables[0]
+        .messages
+        .iter()
+        .map(|m| (m.id, m.text.as_str()))
+        .collect();
+    assert_eq!(messages, [(100, "Hi\r\n"), (101, "Bye")]);
+
+    let dialogs = pe.dialogs().unwrap();
+    assert_eq!(dialogs.len(), 1);
+    let template = &dialogs[0].template;
+    assert!(template.extended);
+    assert_eq!((template.cx, template.cy), (120, 60));
+    assert_eq!(template.title, "About");
+    let font = template.font.as_ref().expect("dialog font");
+    assert_eq!(font.point_size, 8);
+    assert_eq!(font.weight, 400);
+    assert_eq!(font.charset, 1);
+    assert_eq!(font.typeface, "MS Shell Dlg");
+    assert_eq!(template.items.len(), 1);
+    let button = &template.items[0];
+    assert_eq!(button.id, 1);
+    assert_eq!(button.class, Some(Id(0x80)));
+    assert_eq!(button.title, Some(Name("OK".into())));
+    assert_eq!((button.x, button.y, button.cx, button.cy), (35, 40, 50, 14));
+}
+
 /// Synthetic debug directory entry.
 #[test]
 fn test_pe_debug_directory_synthetic() {
@@ -1014,6 +2589,59 @@ fn test_pe_debug_directory_synthetic() {
     );
 }
 
+/// Synthetic RSDS and NB10 CodeView records behind debug directory entries.
+#[test]
+fn test_pe_codeview_records_synthetic() {
+    use pe::debug::{CodeViewRecord, DebugDirectory, IMAGE_DEBUG_TYPE_CODEVIEW};
+
+    let mut buffer = vec![0u8; 0x200];
+    let offset = 0x20usize;
+    let rsds_off = 0x80usize;
+    let rsds_len = 24 + b"c:\\build\\app.pdb\0".len();
+    buffer[offset + 12..offset + 16].copy_from_slice(&IMAGE_DEBUG_TYPE_CODEVIEW.to_le_bytes());
+    buffer[offset + 16..offset + 20].copy_from_slice(&(rsds_len as u32).to_le_bytes());
+    buffer[offset + 20..offset + 24].copy_from_slice(&(rsds_off as u32).to_le_bytes());
+    buffer[rsds_off..rsds_off + 4].copy_from_slice(b"RSDS");
+    buffer[rsds_off + 4..rsds_off + 20].copy_from_slice(&[
+        0x78, 0x56, 0x34, 0x12, 0xbc, 0x9a, 0xf0, 0xde, 0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd,
+        0xef,
+    ]);
+    buffer[rsds_off + 20..rsds_off + 24].copy_from_slice(&0x1au32.to_le_bytes());
+    buffer[rsds_off + 24..rsds_off + rsds_len].copy_from_slice(b"c:\\build\\app.pdb\0");
+
+    let debug = DebugDirectory::parse(&buffer, offset, 28).unwrap();
+    let record = debug.entries[0]
+        .codeview(&buffer)
+        .unwrap()
+        .expect("codeview");
+    assert_eq!(record.path(), "c:\\build\\app.pdb");
+    assert_eq!(record.age(), 0x1a);
+    assert_eq!(
+        record.symbol_server_key(),
+        "123456789ABCDEF00123456789ABCDEF1A"
+    );
+    let CodeViewRecord::Rsds(mut rsds) = record else {
+        panic!("expected RSDS record");
+    };
+    assert_eq!(rsds.guid_string(), "12345678-9ABC-DEF0-0123-456789ABCDEF");
+    assert_eq!(rsds.path.offset, rsds_off + 24);
+    rsds.path.update(&mut buffer, "app.pdb").unwrap();
+    rsds.age.update(&mut buffer, 2).unwrap();
+    let patched = debug.entries[0].codeview(&buffer).unwrap().unwrap();
+    assert_eq!(patched.path(), "app.pdb");
+    assert_eq!(patched.age(), 2);
+
+    let nb10_off = 0x100usize;
+    buffer[nb10_off..nb10_off + 4].copy_from_slice(b"NB10");
+    buffer[nb10_off + 8..nb10_off + 12].copy_from_slice(&0x3c1a_2b00u32.to_le_bytes());
+    buffer[nb10_off + 12..nb10_off + 16].copy_from_slice(&3u32.to_le_bytes());
+    buffer[nb10_off + 16..nb10_off + 24].copy_from_slice(b"old.pdb\0");
+    let nb10 = CodeViewRecord::parse(&buffer, nb10_off, 24).unwrap();
+    assert!(matches!(nb10, CodeViewRecord::Nb10(_)));
+    assert_eq!(nb10.path(), "old.pdb");
+    assert_eq!(nb10.symbol_server_key(), "3C1A2B003");
+}
+
 /// Forwarder exports are classified separately from local functions.
 #[test]
 fn test_pe_export_forwarder_synthetic() {
@@ -1130,6 +2758,125 @@ fn test_pe_rich_header_synthetic() {
     assert_eq!(rich.entries[0].count, 1);
 }
 
+/// set_rich_entries writes a keyed Rich header, moving the PE headers when the stub gap is short.
+#[test]
+fn test_pe_rich_header_editing() {
+    use pe::rich::RichEntry;
+
+    let original = pe::PE::from_file("tests/samples/sample1.exe").expect("Failed to parse PE");
+    let mut pe = pe::PE::from_buffer(original.buffer.clone()).unwrap();
+    assert!(pe.rich_header().unwrap().is_none());
+    let entries = [
+        RichEntry {
+            product_id: 0x0104,
+            build_id: 0x7809,
+            count: 12,
+        },
+        RichEntry {
+            product_id: 0x0105,
+            build_id: 0x7809,
+            count: 33,
+        },
+    ];
+
+    pe.set_rich_entries(&entries).expect("set rich entries");
+    assert_eq!(pe.dos_header.e_lfanew.value, 0xa8);
+    let rich = pe.rich_header().unwrap().expect("rich header");
+    assert_eq!(rich.offset, 0x80);
+    assert_eq!(rich.end, 0xa8);
+    assert_eq!(rich.entries, entries);
+    assert_eq!(&pe.buffer[0xa0..0xa4], b"Rich");
+    assert!(rich.is_checksum_valid(&pe.buffer).unwrap());
+    assert_eq!(pe.sections.len(), original.sections.len());
+    assert_eq!(
+        pe.imports().unwrap().dlls.len(),
+        original.imports().unwrap().dlls.len()
+    );
+    assert_eq!(pe.optional_header.checksum.value, pe.calc_checksum());
+
+    // Rewriting with fewer entries fits in place and re-keys the header.
+    pe.set_rich_entries(&entries[..1]).unwrap();
+    assert_eq!(pe.dos_header.e_lfanew.value, 0xa8);
+    let shorter = pe.rich_header().unwrap().expect("rich header");
+    assert_eq!(shorter.entries, entries[..1]);
+    assert_ne!(shorter.xor_key, rich.xor_key);
+    assert!(shorter.is_checksum_valid(&pe.buffer).unwrap());
+    assert!(pe.buffer[shorter.end..0xa8].iter().all(|&byte| byte == 0));
+
+    // A tampered DOS stub breaks the key.
+    pe.buffer[0x50] ^= 0xff;
+    assert!(!shorter.is_checksum_valid(&pe.buffer).unwrap());
+    pe.buffer[0x50] ^= 0xff;
+
+    pe.remove_rich_header().unwrap();
+    assert!(pe.rich_header().unwrap().is_none());
+    assert!(pe.buffer[0x80..0xa8].iter().all(|&byte| byte == 0));
+    assert_eq!(pe.optional_header.checksum.value, pe.calc_checksum());
+
+    // A DOS stub running past 0x80 is never overwritten.
+    pe.buffer[0x90] = 0xcd;
+    let before = pe.buffer.clone();
+    assert!(matches!(
+        pe.set_rich_entries(&entries),
+        Err(FileParseError::UnsupportedFeature(_))
+    ));
+    assert!(pe.buffer == before);
+}
+
+/// imphash matches pefile on the samples; ordinals resolve through pefile's lookup tables.
+#[test]
+fn test_pe_imphash() {
+    use pe::imphash::ordinal_name;
+
+    for (path, expected) in [
+        (
+            "tests/samples/sample1.exe",
+            "3ce2f351c4d6c5b917d23c71d96ad76f",
+        ),
+        (
+            "tests/samples/sample2.dll",
+            "6a6f76a6cc66b8c8c1dec213f5584706",
+        ),
+        (
+            "tests/samples/sample64.exe",
+            "e0e0c99ae34c4b8b156971d78cc79573",
+        ),
+    ] {
+        let pe = pe::PE::from_file(path).expect("Failed to parse PE");
+        assert_eq!(pe.imphash().unwrap().as_deref(), Some(expected), "{path}");
+    }
+
+    assert_eq!(ordinal_name("WS2_32.dll", 115), Some("WSAStartup"));
+    assert_eq!(ordinal_name("wsock32.dll", 3), Some("closesocket"));
+    assert_eq!(ordinal_name("OLEAUT32.DLL", 2), Some("SysAllocString"));
+    assert_eq!(ordinal_name("oleaut32.dll", 302), None);
+    assert_eq!(ordinal_name("ws2_32", 1), None);
+}
+
+/// rich_hash is the MD5 of the decoded header from `DanS` up to the `Rich` marker.
+#[test]
+fn test_pe_rich_hash() {
+    use hexspell::hash::{to_hex, Md5};
+    use pe::rich::RichEntry;
+
+    let mut pe = pe::PE::from_file("tests/samples/sample1.exe").expect("Failed to parse PE");
+    assert!(pe.rich_hash().unwrap().is_none());
+    let entry = RichEntry {
+        product_id: 0x0104,
+        build_id: 0x7809,
+        count: 12,
+    };
+    pe.set_rich_entries(&[entry]).unwrap();
+
+    let mut clear = DANS_MAGIC.to_le_bytes().to_vec();
+    clear.extend_from_slice(&[0; 12]);
+    clear.extend_from_slice(&entry.comp_id().to_le_bytes());
+    clear.extend_from_slice(&12u32.to_le_bytes());
+    let rich = pe.rich_header().unwrap().expect("rich header");
+    assert_eq!(rich.clear_data(&pe.buffer).unwrap(), clear);
+    assert_eq!(pe.rich_hash().unwrap(), Some(to_hex(&Md5::digest(&clear))));
+}
+
 const DANS_MAGIC: u32 = 0x536e_6144;
 const RICH_MAGIC: u32 = 0x6863_6952;
 
@@ -1155,6 +2902,416 @@ fn test_pe_certificate_table_synthetic() {
     assert_eq!(table.certificates[0].data(&buffer).unwrap(), b"PKCS7!!!");
 }
 
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Authenticode digests match reference values and ignore checksum / certificate edits.
+#[test]
+fn test_pe_authenticode_digest() {
+    use hexspell::hash::HashAlgorithm;
+
+    let pe = pe::PE::from_file("tests/samples/sample1.exe").expect("Failed to parse PE");
+    assert_eq!(
+        to_hex(&pe.authenticode_digest(HashAlgorithm::Sha1).unwrap()),
+        "e6d4fe7d3da0c38f4b74769212b8e570f367637e"
+    );
+    assert_eq!(
+        to_hex(&pe.authenticode_digest(HashAlgorithm::Sha256).unwrap()),
+        "764b6dd5d1dbd47acbae99a94cf7821282f6cd08305335fd8d24b011e7f5cbef"
+    );
+
+    // sample64.exe carries trailing COFF symbol data after the last section.
+    let mut pe = pe::PE::from_file("tests/samples/sample64.exe").expect("Failed to parse PE64");
+    let original = pe.authenticode_digest(HashAlgorithm::Sha256).unwrap();
+    assert_eq!(
+        to_hex(&original),
+        "fa4a200faae81365b16d3752f334e7a8e7fa071718139e4671a27c01fc29cbf9"
+    );
+
+    // Appending a certificate table and touching the checksum leave the digest unchanged.
+    let cert_offset = pe.buffer.len();
+    pe.buffer.extend_from_slice(&16u32.to_le_bytes());
+    pe.buffer.extend_from_slice(&0x0200u16.to_le_bytes());
+    pe.buffer.extend_from_slice(&0x0002u16.to_le_bytes());
+    pe.buffer.extend_from_slice(b"PKCS7!!!");
+    pe.sync_data_directory_rva(pe::header::SECURITY, cert_offset as u32)
+        .unwrap();
+    pe.sync_data_directory_size(pe::header::SECURITY, 16)
+        .unwrap();
+    pe.optional_header
+        .checksum
+        .update(&mut pe.buffer, 0xDEAD_BEEF)
+        .unwrap();
+    assert_eq!(
+        pe.authenticode_digest(HashAlgorithm::Sha256).unwrap(),
+        original
+    );
+
+    // Patching section data changes it.
+    let text = pe.sections[0].pointer_to_raw_data.value as usize;
+    pe.buffer[text] ^= 0xFF;
+    assert_ne!(
+        pe.authenticode_digest(HashAlgorithm::Sha256).unwrap(),
+        original
+    );
+
+    // SizeOfHeaders ending before the checksum field is rejected instead of panicking.
+    let mut pe = pe::PE::from_file("tests/samples/sample1.exe").expect("Failed to parse PE");
+    pe.optional_header
+        .size_of_headers
+        .update(&mut pe.buffer, 0x80)
+        .unwrap();
+    assert!(matches!(
+        pe.authenticode_digest(HashAlgorithm::Sha256),
+        Err(FileParseError::BufferOverflow)
+    ));
+}
+
+/// DER tag-length-value encoder for synthetic PKCS#7 blobs.
+fn der(tag: u8, parts: &[&[u8]]) -> Vec<u8> {
+    let contents = parts.concat();
+    let mut out = vec![tag];
+    match contents.len() {
+        len @ 0..=0x7F => out.push(len as u8),
+        len @ 0x80..=0xFF => out.extend_from_slice(&[0x81, len as u8]),
+        len => {
+            out.push(0x82);
+            out.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+    }
+    out.extend_from_slice(&contents);
+    out
+}
+
+fn der_oid(dotted: &str) -> Vec<u8> {
+    let arcs: Vec<u64> = dotted.split('.').map(|arc| arc.parse().unwrap()).collect();
+    let mut body = vec![(arcs[0] * 40 + arcs[1]) as u8];
+    for arc in &arcs[2..] {
+        let mut groups = vec![(arc & 0x7F) as u8];
+        let mut rest = arc >> 7;
+        while rest > 0 {
+            groups.push((rest & 0x7F) as u8 | 0x80);
+            rest >>= 7;
+        }
+        body.extend(groups.iter().rev());
+    }
+    der(0x06, &[&body])
+}
+
+fn der_algorithm(oid: &str) -> Vec<u8> {
+    der(0x30, &[&der_oid(oid), &[0x05, 0x00]])
+}
+
+fn der_name(common_name: &str) -> Vec<u8> {
+    let attribute = der(
+        0x30,
+        &[&der_oid("2.5.4.3"), &der(0x0C, &[common_name.as_bytes()])],
+    );
+    der(0x30, &[&der(0x31, &[&attribute])])
+}
+
+fn der_certificate(serial: &[u8], issuer: &str, subject: &str) -> Vec<u8> {
+    let tbs = der(
+        0x30,
+        &[
+            &der(0xA0, &[&der(0x02, &[&[2]])]),
+            &der(0x02, &[serial]),
+            &der_algorithm("1.2.840.113549.1.1.11"),
+            &der_name(issuer),
+            &der(
+                0x30,
+                &[
+                    &der(0x17, &[b"250101000000Z"]),
+                    &der(0x18, &[b"20350101000000Z"]),
+                ],
+            ),
+            &der_name(subject),
+            &der(0x30, &[]),
+        ],
+    );
+    der(
+        0x30,
+        &[
+            &tbs,
+            &der_algorithm("1.2.840.113549.1.1.11"),
+            &der(0x03, &[&[0]]),
+        ],
+    )
+}
+
+/// Authenticode `ContentInfo` signing `digest`, optionally nesting another `ContentInfo`.
+fn der_authenticode(
+    digest_oid: &str,
+    digest: &[u8],
+    signer: &str,
+    nested: Option<&[u8]>,
+) -> Vec<u8> {
+    let serial = [0x00, 0x9A, 0x01];
+    let spc = der(
+        0x30,
+        &[
+            &der(0x30, &[&der_oid("1.3.6.1.4.1.311.2.1.15"), &der(0x30, &[])]),
+            &der(0x30, &[&der_algorithm(digest_oid), &der(0x04, &[digest])]),
+        ],
+    );
+    let authenticated = der(
+        0xA0,
+        &[&der(
+            0x30,
+            &[
+                &der_oid("1.2.840.113549.1.9.4"),
+                &der(0x31, &[&der(0x04, &[&[0xEE; 4]])]),
+            ],
+        )],
+    );
+    let unauthenticated = nested.map(|inner| {
+        der(
+            0xA1,
+            &[&der(
+                0x30,
+                &[&der_oid("1.3.6.1.4.1.311.2.4.1"), &der(0x31, &[inner])],
+            )],
+        )
+    });
+    let signer_info = der(
+        0x30,
+        &[
+            &der(0x02, &[&[1]]),
+            &der(0x30, &[&der_name("Test Root"), &der(0x02, &[&serial])]),
+            &der_algorithm(digest_oid),
+            &authenticated,
+            &der_algorithm("1.2.840.113549.1.1.1"),
+            &der(0x04, &[&[0x5A; 8]]),
+            unauthenticated.as_deref().unwrap_or(&[]),
+        ],
+    );
+    let signed_data = der(
+        0x30,
+        &[
+            &der(0x02, &[&[1]]),
+            &der(0x31, &[&der_algorithm(digest_oid)]),
+            &der(
+                0x30,
+                &[&der_oid("1.3.6.1.4.1.311.2.1.4"), &der(0xA0, &[&spc])],
+            ),
+            &der(0xA0, &[&der_certificate(&serial, "Test Root", signer)]),
+            &der(0x31, &[&signer_info]),
+        ],
+    );
+    der(
+        0x30,
+        &[
+            &der_oid("1.2.840.113549.1.7.2"),
+            &der(0xA0, &[&signed_data]),
+        ],
+    )
+}
+
+/// PKCS#7 `SignedData` decoding from an appended certificate table, including a nested signature.
+#[test]
+fn test_pe_pkcs7_signed_data_synthetic() {
+    use hexspell::hash::HashAlgorithm;
+    use pe::pkcs7::{OID_NESTED_SIGNATURE, OID_SHA1, OID_SHA256};
+
+    let mut pe = pe::PE::from_file("tests/samples/sample64.exe").expect("Failed to parse PE64");
+    let sha256 = pe.authenticode_digest(HashAlgorithm::Sha256).unwrap();
+    let sha1 = pe.authenticode_digest(HashAlgorithm::Sha1).unwrap();
+
+    let nested = der_authenticode(OID_SHA1, &sha1, "Legacy Signer", None);
+    let blob = der_authenticode(OID_SHA256, &sha256, "HexSpell Signer", Some(&nested));
+    let cert_offset = pe.buffer.len();
+    let length = 8 + blob.len() as u32;
+    pe.buffer.extend_from_slice(&length.to_le_bytes());
+    pe.buffer.extend_from_slice(&0x0200u16.to_le_bytes());
+    pe.buffer.extend_from_slice(&0x0002u16.to_le_bytes());
+    pe.buffer.extend_from_slice(&blob);
+    pe.sync_data_directory_rva(pe::header::SECURITY, cert_offset as u32)
+        .unwrap();
+    pe.sync_data_directory_size(pe::header::SECURITY, length)
+        .unwrap();
+
+    let table = pe.certificates().unwrap().expect("certificate table");
+    let signed = table.certificates[0].signed_data(&pe.buffer).unwrap();
+    assert_eq!(signed.version, 1);
+    assert_eq!(signed.digest_algorithms, [OID_SHA256]);
+    assert_eq!(signed.content_type, "1.3.6.1.4.1.311.2.1.4");
+
+    let indirect = signed
+        .indirect_data
+        .as_ref()
+        .expect("SpcIndirectDataContent");
+    assert_eq!(indirect.hash_algorithm(), Some(HashAlgorithm::Sha256));
+    assert_eq!(indirect.digest, sha256);
+
+    assert_eq!(signed.certificates.len(), 1);
+    let certificate = &signed.certificates[0];
+    assert_eq!(certificate.serial_hex(), "9A01");
+    assert_eq!(certificate.subject.common_name(), Some("HexSpell Signer"));
+    assert_eq!(certificate.issuer.to_string(), "CN=Test Root");
+    assert_eq!(certificate.not_before, "250101000000Z");
+    assert_eq!(certificate.not_after, "20350101000000Z");
+    assert_eq!(certificate.signature_algorithm, "1.2.840.113549.1.1.11");
+
+    let signer = &signed.signers[0];
+    assert_eq!(signer.digest_algorithm, OID_SHA256);
+    assert_eq!(signer.signature, [0x5A; 8]);
+    assert_eq!(signer.authenticated_attributes, ["1.2.840.113549.1.9.4"]);
+    assert_eq!(signer.unauthenticated_attributes, [OID_NESTED_SIGNATURE]);
+    assert_eq!(
+        signed
+            .signer_certificate(signer)
+            .unwrap()
+            .subject
+            .common_name(),
+        Some("HexSpell Signer")
+    );
+
+    let all = signed.all_signatures();
+    assert_eq!(all.len(), 2);
+    for signature in all {
+        let indirect = signature.indirect_data.as_ref().unwrap();
+        let algorithm = indirect.hash_algorithm().unwrap();
+        assert_eq!(indirect.digest, pe.authenticode_digest(algorithm).unwrap());
+    }
+    assert_eq!(
+        signer.nested[0].certificates[0].subject.common_name(),
+        Some("Legacy Signer")
+    );
+}
+
+/// Stripping, replacing and appending signatures keep the table aligned and the digest stable.
+#[test]
+fn test_pe_signature_editing() {
+    use hexspell::hash::HashAlgorithm;
+    use pe::certificate::WIN_CERT_TYPE_PKCS_SIGNED_DATA;
+
+    let mut pe = pe::PE::from_file("tests/samples/sample64.exe").expect("Failed to parse PE64");
+    let unsigned_len = pe.buffer.len();
+    pe.set_signature(b"first").unwrap();
+    let digest = pe.authenticode_digest(HashAlgorithm::Sha256).unwrap();
+    let table = pe.certificates().unwrap().expect("certificate table");
+    assert_eq!(table.offset, unsigned_len.next_multiple_of(8));
+    assert_eq!(table.size, 16);
+    assert_eq!(table.certificates[0].data(&pe.buffer).unwrap(), b"first");
+    assert_eq!(pe.buffer.len(), table.offset + 16);
+    assert_eq!(pe.optional_header.checksum.value, pe.calc_checksum());
+
+    pe.append_signature(b"second signature").unwrap();
+    let table = pe.certificates().unwrap().expect("certificate table");
+    assert_eq!(table.size, 16 + 24);
+    assert_eq!(table.certificates.len(), 2);
+    let second = &table.certificates[1];
+    assert_eq!(second.length.offset % 8, 0);
+    assert_eq!(second.length.value, 8 + 16);
+    assert_eq!(
+        second.certificate_type.value,
+        WIN_CERT_TYPE_PKCS_SIGNED_DATA
+    );
+    assert_eq!(second.data(&pe.buffer).unwrap(), b"second signature");
+    assert_eq!(
+        pe.authenticode_digest(HashAlgorithm::Sha256).unwrap(),
+        digest
+    );
+    assert_eq!(pe.optional_header.checksum.value, pe.calc_checksum());
+
+    pe.set_signature(b"replacement").unwrap();
+    let table = pe.certificates().unwrap().expect("certificate table");
+    assert_eq!(table.certificates.len(), 1);
+    assert_eq!(
+        table.certificates[0].data(&pe.buffer).unwrap(),
+        b"replacement"
+    );
+    assert_eq!(
+        pe.authenticode_digest(HashAlgorithm::Sha256).unwrap(),
+        digest
+    );
+
+    pe.strip_signatures().unwrap();
+    assert!(pe.certificates().unwrap().is_none());
+    assert_eq!(pe.buffer.len(), table.offset);
+    let security = &pe.optional_header.data_directories[pe::header::SECURITY];
+    assert_eq!(
+        (security.virtual_address.value, security.size.value),
+        (0, 0)
+    );
+    assert_eq!(pe.optional_header.checksum.value, pe.calc_checksum());
+    let reparsed = pe::PE::from_buffer(pe.buffer.clone()).unwrap();
+    assert_eq!(
+        reparsed.authenticode_digest(HashAlgorithm::Sha256).unwrap(),
+        digest
+    );
+}
+
+/// Overlay data is appended ahead of the certificate table and stripped without breaking it.
+#[test]
+fn test_pe_overlay_editing() {
+    let mut pe = pe::PE::from_file("tests/samples/sample64.exe").expect("Failed to parse PE64");
+    pe.strip_overlay().unwrap();
+    assert!(pe.overlay().unwrap().is_none());
+    let image_end = pe.image_end_offset();
+    assert_eq!(pe.buffer.len(), image_end);
+
+    pe.append_overlay(b"payload").unwrap();
+    let overlay = pe.overlay().unwrap().expect("overlay");
+    assert_eq!((overlay.offset, overlay.size), (image_end, 7));
+    assert_eq!(overlay.data(&pe.buffer).unwrap(), b"payload");
+
+    pe.append_signature(b"signature").unwrap();
+    let overlay = pe.overlay().unwrap().expect("overlay");
+    assert_eq!(
+        overlay.size,
+        (image_end + 7).next_multiple_of(8) - image_end
+    );
+    let signed = overlay.data(&pe.buffer).unwrap().to_vec();
+    assert_eq!(&signed[..7], b"payload");
+
+    pe.append_overlay(b"-more").unwrap();
+    let overlay = pe.overlay().unwrap().expect("overlay");
+    let data = overlay.data(&pe.buffer).unwrap();
+    assert_eq!(&data[..signed.len()], signed.as_slice());
+    assert_eq!(&data[signed.len()..signed.len() + 5], b"-more");
+    let table = pe.certificates().unwrap().expect("certificate table");
+    assert_eq!(table.offset % 8, 0);
+    assert_eq!(table.offset, overlay.offset + overlay.size);
+    assert_eq!(
+        table.certificates[0].data(&pe.buffer).unwrap(),
+        b"signature"
+    );
+    assert_eq!(pe.optional_header.checksum.value, pe.calc_checksum());
+
+    pe.strip_overlay().unwrap();
+    assert!(pe.overlay().unwrap().is_none());
+    let table = pe.certificates().unwrap().expect("certificate table");
+    assert_eq!(table.offset, image_end.next_multiple_of(8));
+    assert_eq!(
+        table.certificates[0].data(&pe.buffer).unwrap(),
+        b"signature"
+    );
+    assert_eq!(pe.buffer.len(), table.offset + table.size);
+    assert_eq!(pe.optional_header.checksum.value, pe.calc_checksum());
+
+    // Data left behind the certificate table is reported separately and stripped too.
+    pe.buffer.extend_from_slice(b"appended");
+    let overlay = pe.overlay().unwrap().expect("overlay");
+    assert_eq!(overlay.size, table.offset - image_end);
+    assert_eq!(overlay.trailing_offset, table.offset + table.size);
+    assert_eq!(overlay.trailing_data(&pe.buffer).unwrap(), b"appended");
+    pe.strip_overlay().unwrap();
+    assert!(pe.overlay().unwrap().is_none());
+    assert_eq!(pe.buffer.len(), table.offset + table.size);
+    assert_eq!(
+        pe.certificates()
+            .unwrap()
+            .expect("certificate table")
+            .certificates[0]
+            .data(&pe.buffer)
+            .unwrap(),
+        b"signature"
+    );
+    assert_eq!(pe.optional_header.checksum.value, pe.calc_checksum());
+}
+
 /// Synthetic IMAGE_COR20_HEADER.
 #[test]
 fn test_pe_clr_header_synthetic() {
@@ -1177,6 +3334,281 @@ fn test_pe_clr_header_synthetic() {
     assert!(cor20.is_il_only());
 }
 
+/// Appends a NUL-terminated `#Strings` entry and returns its index.
+fn metadata_string(heap: &mut Vec<u8>, text: &str) -> u16 {
+    let index = heap.len() as u16;
+    heap.extend_from_slice(text.as_bytes());
+    heap.push(0);
+    index
+}
+
+/// Minimal ECMA-335 metadata: root, `#~`, `#Strings`, `#US`, `#GUID` and `#Blob` streams.
+fn clr_metadata_blob() -> Vec<u8> {
+    let mut strings = vec![0u8];
+    let module = metadata_string(&mut strings, "App.exe");
+    let object = metadata_string(&mut strings, "Object");
+    let system = metadata_string(&mut strings, "System");
+    let module_type = metadata_string(&mut strings, "<Module>");
+    let program = metadata_string(&mut strings, "Program");
+    let app = metadata_string(&mut strings, "App");
+    let main = metadata_string(&mut strings, "Main");
+    let ctor = metadata_string(&mut strings, ".ctor");
+    let mscorlib = metadata_string(&mut strings, "mscorlib");
+    let resource = metadata_string(&mut strings, "App.Resources.resources");
+    strings.resize(strings.len().next_multiple_of(4), 0);
+
+    let mut user_strings = vec![0u8, 0x05];
+    user_strings.extend_from_slice(&[b'H', 0, b'i', 0, 0]);
+    user_strings.resize(8, 0);
+    let mut guids = vec![0u8; 16];
+    guids[0] = 0x11;
+    guids[15] = 0xFF;
+    let mut blobs = vec![0u8, 0x03, 0x00, 0x00, 0x01, 0x08];
+    blobs.extend_from_slice(&[0xB7, 0x7A, 0x5C, 0x56, 0x19, 0x34, 0xE0, 0x89]);
+    blobs.resize(blobs.len().next_multiple_of(4), 0);
+
+    // Module, TypeRef, TypeDef, MethodDef, MemberRef, Assembly, AssemblyRef, ManifestResource.
+    let present: [(u8, u32); 8] = [
+        (0x00, 1),
+        (0x01, 1),
+        (0x02, 2),
+        (0x06, 1),
+        (0x0A, 1),
+        (0x20, 1),
+        (0x23, 1),
+        (0x28, 1),
+    ];
+    let mut tables = Vec::new();
+    tables.extend_from_slice(&0u32.to_le_bytes());
+    tables.extend_from_slice(&[2, 0, 0, 1]);
+    let valid = present.iter().fold(0u64, |acc, (id, _)| acc | 1 << id);
+    tables.extend_from_slice(&valid.to_le_bytes());
+    tables.extend_from_slice(&0u64.to_le_bytes());
+    for (_, rows) in present {
+        tables.extend_from_slice(&rows.to_le_bytes());
+    }
+    let mut row = |words: &[u16]| {
+        for word in words {
+            tables.extend_from_slice(&word.to_le_bytes());
+        }
+    };
+    row(&[0, module, 1, 0, 0]); // Module
+    row(&[(1 << 2) | 2, object, system]); // TypeRef -> AssemblyRef 1
+    row(&[0, 0, module_type, 0, 0, 1, 1]); // TypeDef <Module>
+    row(&[0x0001, 0x0010, program, app, (1 << 2) | 1, 1, 1]); // TypeDef Program
+    row(&[0x2050, 0, 0, 0x0091, main, 1, 1]); // MethodDef (RVA as two words)
+    row(&[(1 << 3) | 1, ctor, 1]); // MemberRef -> TypeRef 1
+    row(&[0x8004, 0, 1, 2, 3, 4, 0, 0, 0, app, 0]); // Assembly (SHA-1)
+    row(&[4, 0, 0, 0, 0, 0, 5, mscorlib, 0, 0]); // AssemblyRef
+    row(&[0x40, 0, 1, 0, resource, 0]); // ManifestResource
+    tables.resize(tables.len().next_multiple_of(4), 0);
+
+    let streams: [(&str, &Vec<u8>); 5] = [
+        ("#~", &tables),
+        ("#Strings", &strings),
+        ("#US", &user_strings),
+        ("#GUID", &guids),
+        ("#Blob", &blobs),
+    ];
+    let version = b"v4.0.30319\0\0";
+    let header_len = 16
+        + version.len()
+        + 4
+        + streams
+            .iter()
+            .map(|(name, _)| 8 + (name.len() + 1).next_multiple_of(4))
+            .sum::<usize>();
+
+    let mut blob = Vec::new();
+    blob.extend_from_slice(&0x424A_5342u32.to_le_bytes());
+    blob.extend_from_slice(&1u16.to_le_bytes());
+    blob.extend_from_slice(&1u16.to_le_bytes());
+    blob.extend_from_slice(&0u32.to_le_bytes());
+    blob.extend_from_slice(&(version.len() as u32).to_le_bytes());
+    blob.extend_from_slice(version);
+    blob.extend_from_slice(&0u16.to_le_bytes());
+    blob.extend_from_slice(&(streams.len() as u16).to_le_bytes());
+    let mut data_offset = header_len;
+    for (name, data) in &streams {
+        blob.extend_from_slice(&(data_offset as u32).to_le_bytes());
+        blob.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        blob.extend_from_slice(name.as_bytes());
+        blob.resize(
+            blob.len() + (name.len() + 1).next_multiple_of(4) - name.len(),
+            0,
+        );
+        data_offset += data.len();
+    }
+    for (_, data) in &streams {
+        blob.extend_from_slice(data);
+    }
+    blob
+}
+
+/// ECMA-335 metadata root, heaps and decoded tables behind the CLR header.
+#[test]
+fn test_pe_clr_metadata_synthetic() {
+    use pe::clr_metadata::{TABLE_ASSEMBLY_REF, TABLE_TYPE_DEF, TABLE_TYPE_REF};
+
+    let mut pe = pe::PE::from_file("tests/samples/sample64.exe").expect("Failed to parse PE64");
+    assert!(pe.clr_metadata().unwrap().is_none());
+
+    let metadata = clr_metadata_blob();
+    let text = pe.sections[0].pointer_to_raw_data.value as usize;
+    let text_rva = pe.sections[0].virtual_address.value;
+    let cor20 = &mut pe.buffer[text..text + 0x48];
+    cor20.fill(0);
+    cor20[0..4].copy_from_slice(&72u32.to_le_bytes());
+    cor20[4..6].copy_from_slice(&2u16.to_le_bytes());
+    cor20[6..8].copy_from_slice(&5u16.to_le_bytes());
+    cor20[8..12].copy_from_slice(&(text_rva + 0x48).to_le_bytes());
+    cor20[12..16].copy_from_slice(&(metadata.len() as u32).to_le_bytes());
+    pe.buffer[text + 0x48..text + 0x48 + metadata.len()].copy_from_slice(&metadata);
+    pe.sync_data_directory_rva(pe::header::COM_DESCRIPTOR, text_rva)
+        .unwrap();
+    pe.sync_data_directory_size(pe::header::COM_DESCRIPTOR, 72)
+        .unwrap();
+
+    let metadata = pe.clr_metadata().unwrap().expect("metadata");
+    assert_eq!(metadata.root.offset, text + 0x48);
+    assert_eq!(metadata.root.version.value, "v4.0.30319");
+    assert_eq!(metadata.root.stream_headers.len(), 5);
+    assert_eq!(metadata.root.stream_headers[1].name, "#Strings");
+    assert_eq!(metadata.user_string(&pe.buffer, 1).unwrap(), "Hi");
+    assert_eq!(metadata.blob(&pe.buffer, 1).unwrap(), [0x00, 0x00, 0x01]);
+    let guid = metadata.guid(&pe.buffer, 1).unwrap().unwrap();
+    assert_eq!((guid[0], guid[15]), (0x11, 0xFF));
+    assert!(metadata.guid(&pe.buffer, 0).unwrap().is_none());
+
+    let tables = metadata.tables.as_ref().expect("#~ stream");
+    assert_eq!(tables.major_version.value, 2);
+    assert_eq!(tables.row_count(TABLE_TYPE_DEF), 2);
+    assert_eq!(tables.table(TABLE_TYPE_DEF).unwrap().row_size, 14);
+    assert_eq!(tables.modules[0].name.value, "App.exe");
+    assert_eq!(tables.type_refs[0].full_name(), "System.Object");
+    assert_eq!(
+        tables.type_refs[0].resolution_scope.table,
+        Some(TABLE_ASSEMBLY_REF)
+    );
+    assert_eq!(tables.type_defs[1].full_name(), "App.Program");
+    assert_eq!(tables.type_defs[1].flags.value, 0x0010_0001);
+    assert_eq!(tables.type_defs[1].extends.table, Some(TABLE_TYPE_REF));
+    assert_eq!(tables.type_defs[1].extends.row, 1);
+    assert_eq!(tables.method_defs[0].rva.value, 0x2050);
+    assert_eq!(tables.method_defs[0].name.value, "Main");
+    assert_eq!(tables.member_refs[0].name.value, ".ctor");
+    assert_eq!(tables.member_refs[0].class.table, Some(TABLE_TYPE_REF));
+    assert_eq!(tables.assemblies[0].name.value, "App");
+    assert_eq!(tables.assemblies[0].version(), (1, 2, 3, 4));
+    let mscorlib = &tables.assembly_refs[0];
+    assert_eq!(mscorlib.name.value, "mscorlib");
+    assert_eq!(mscorlib.version(), (4, 0, 0, 0));
+    assert_eq!(
+        to_hex(
+            metadata
+                .blob(&pe.buffer, mscorlib.public_key_or_token.value)
+                .unwrap()
+        ),
+        "b77a5c561934e089"
+    );
+    let resource = &tables.manifest_resources[0];
+    assert_eq!(resource.name.value, "App.Resources.resources");
+    assert_eq!(resource.offset.value, 0x40);
+    assert_eq!(resource.implementation.row, 0);
+    assert_eq!(
+        tables.row_offset(TABLE_ASSEMBLY_REF, 1),
+        Some(mscorlib.major_version.offset)
+    );
+}
+
+/// Managed resources, strong-name signature and VTableFixups hang off the COR20 header.
+#[test]
+fn test_pe_clr_resources_strong_name_vtable_fixups() {
+    use pe::clr::{COR_VTABLE_32BIT, COR_VTABLE_64BIT, COR_VTABLE_FROM_UNMANAGED};
+
+    let mut pe = pe::PE::from_file("tests/samples/sample64.exe").expect("Failed to parse PE64");
+    assert!(pe.managed_resources().unwrap().is_empty());
+    assert!(pe.strong_name_signature().unwrap().is_none());
+    assert!(pe.vtable_fixups().unwrap().is_empty());
+
+    let metadata = clr_metadata_blob();
+    let text = pe.sections[0].pointer_to_raw_data.value as usize;
+    let text_rva = pe.sections[0].virtual_address.value;
+    let cor20 = &mut pe.buffer[text..text + 0x48];
+    cor20.fill(0);
+    cor20[0..4].copy_from_slice(&72u32.to_le_bytes());
+    cor20[4..6].copy_from_slice(&2u16.to_le_bytes());
+    cor20[6..8].copy_from_slice(&5u16.to_le_bytes());
+    cor20[8..12].copy_from_slice(&(text_rva + 0x48).to_le_bytes());
+    cor20[12..16].copy_from_slice(&(metadata.len() as u32).to_le_bytes());
+    cor20[24..28].copy_from_slice(&(text_rva + 0x800).to_le_bytes());
+    cor20[28..32].copy_from_slice(&0x49u32.to_le_bytes());
+    cor20[32..36].copy_from_slice(&(text_rva + 0x900).to_le_bytes());
+    cor20[36..40].copy_from_slice(&0x80u32.to_le_bytes());
+    cor20[48..52].copy_from_slice(&(text_rva + 0xA00).to_le_bytes());
+    cor20[52..56].copy_from_slice(&16u32.to_le_bytes());
+    pe.buffer[text + 0x48..text + 0x48 + metadata.len()].copy_from_slice(&metadata);
+
+    // Two entries: one unreferenced by metadata, one at ManifestResource offset 0x40.
+    let resources = &mut pe.buffer[text + 0x800..text + 0x849];
+    resources.fill(0);
+    resources[0..4].copy_from_slice(&0x38u32.to_le_bytes());
+    resources[0x40..0x44].copy_from_slice(&5u32.to_le_bytes());
+    resources[0x44..0x49].copy_from_slice(b"hello");
+    pe.buffer[text + 0x900..text + 0x980].fill(0xAA);
+    let fixups = &mut pe.buffer[text + 0xA00..text + 0xA10];
+    fixups[0..4].copy_from_slice(&0x3000u32.to_le_bytes());
+    fixups[4..6].copy_from_slice(&2u16.to_le_bytes());
+    fixups[6..8].copy_from_slice(&(COR_VTABLE_64BIT | COR_VTABLE_FROM_UNMANAGED).to_le_bytes());
+    fixups[8..12].copy_from_slice(&0x3010u32.to_le_bytes());
+    fixups[12..14].copy_from_slice(&1u16.to_le_bytes());
+    fixups[14..16].copy_from_slice(&COR_VTABLE_32BIT.to_le_bytes());
+    pe.sync_data_directory_rva(pe::header::COM_DESCRIPTOR, text_rva)
+        .unwrap();
+    pe.sync_data_directory_size(pe::header::COM_DESCRIPTOR, 72)
+        .unwrap();
+
+    let resources = pe.managed_resources().unwrap();
+    assert_eq!(resources.len(), 1);
+    assert_eq!(
+        resources[0].name.as_deref(),
+        Some("App.Resources.resources")
+    );
+    assert_eq!(resources[0].length.offset, text + 0x840);
+    assert_eq!(resources[0].data(&pe.buffer).unwrap(), b"hello");
+
+    let fixups = pe.vtable_fixups().unwrap();
+    assert_eq!(fixups.len(), 2);
+    assert_eq!(fixups[0].rva.value, 0x3000);
+    assert_eq!(fixups[0].count.value, 2);
+    assert_eq!(fixups[0].slot_size(), 8);
+    assert_eq!(fixups[1].kind.offset, text + 0xA0E);
+    assert_eq!(fixups[1].slot_size(), 4);
+
+    let signature = pe.strong_name_signature().unwrap().expect("strong name");
+    assert_eq!((signature.offset, signature.size), (text + 0x900, 0x80));
+    assert!(signature
+        .data(&pe.buffer)
+        .unwrap()
+        .iter()
+        .all(|&b| b == 0xAA));
+    pe.set_strong_name_signature(&[0x11; 0x10]).unwrap();
+    let data = signature.data(&pe.buffer).unwrap();
+    assert_eq!((data[0x0F], data[0x10]), (0x11, 0x00));
+    assert_eq!(pe.optional_header.checksum.value, pe.calc_checksum());
+    assert!(pe.set_strong_name_signature(&[0; 0x81]).is_err());
+    pe.clear_strong_name_signature().unwrap();
+    assert!(signature.data(&pe.buffer).unwrap().iter().all(|&b| b == 0));
+
+    // Without metadata the blob is walked sequentially on 8-byte boundaries.
+    pe.buffer[text + 8..text + 16].fill(0);
+    let resources = pe.managed_resources().unwrap();
+    assert_eq!(resources.len(), 2);
+    assert_eq!(resources[0].length.value, 0x38);
+    assert!(resources[0].name.is_none());
+    assert_eq!(resources[1].data(&pe.buffer).unwrap(), b"hello");
+}
+
 /// CHPE metadata blob is classified as architecture-specific data.
 #[test]
 fn test_pe_chpe_metadata_synthetic() {
@@ -1253,6 +3685,387 @@ fn test_pe_remove_section() {
     assert_eq!(pe.sections.len(), before as usize - 1);
 }
 
+/// resize_section grows `.text` across a section alignment boundary and fixes later RVAs.
+#[test]
+fn test_pe_resize_section_shifts_rvas() {
+    use pe::header::{EXPORT, IMPORT};
+
+    let original = pe::PE::from_file("tests/samples/sample2.dll").expect("Failed to parse PE");
+    let mut pe = pe::PE::from_buffer(original.buffer.clone()).unwrap();
+    let base = pe.optional_header.image_base.value.value();
+    let shift = |rva: u32| if rva >= 0x2000 { rva + 0x1000 } else { rva };
+
+    pe.resize_section(0, 0x1000, 0x1c34).expect("resize .text");
+    assert_eq!(pe.sections[0].size_of_raw_data.value, 0x1000);
+    assert_eq!(pe.sections[0].virtual_size.value, 0x1c34);
+    for (old, new) in original.sections.iter().zip(&pe.sections).skip(1) {
+        assert_eq!(
+            new.virtual_address.value,
+            old.virtual_address.value + 0x1000
+        );
+        if old.pointer_to_raw_data.value != 0 {
+            assert_eq!(
+                new.pointer_to_raw_data.value,
+                old.pointer_to_raw_data.value + 0x200
+            );
+        }
+    }
+    assert_eq!(
+        pe.optional_header.size_of_image.value,
+        original.optional_header.size_of_image.value + 0x1000
+    );
+    assert_eq!(
+        pe.optional_header.size_of_code.value,
+        original.optional_header.size_of_code.value + 0x200
+    );
+    assert_eq!(
+        pe.optional_header.entry_point.value,
+        original.optional_header.entry_point.value
+    );
+    for index in [EXPORT, IMPORT] {
+        assert_eq!(
+            pe.optional_header.data_directories[index]
+                .virtual_address
+                .value,
+            original.optional_header.data_directories[index]
+                .virtual_address
+                .value
+                + 0x1000
+        );
+    }
+    assert_eq!(pe.optional_header.checksum.value, pe.calc_checksum());
+
+    let exports = pe.export_builder().unwrap();
+    assert_eq!(exports, original.export_builder().unwrap());
+    let imports = pe.imports().unwrap();
+    let old_imports = original.imports().unwrap();
+    for (new, old) in imports.dlls.iter().zip(&old_imports.dlls) {
+        assert_eq!(new.dll_name, old.dll_name);
+        assert_eq!(
+            new.descriptor.first_thunk.value,
+            shift(old.descriptor.first_thunk.value)
+        );
+        assert_eq!(
+            pe::import::import_names_for_dll(&imports, &new.dll_name),
+            pe::import::import_names_for_dll(&old_imports, &old.dll_name)
+        );
+    }
+
+    let relocations = original.base_relocation_builder().unwrap();
+    assert_eq!(
+        pe.base_relocation_builder().unwrap().entries.len(),
+        relocations.entries.len()
+    );
+    for &rva in relocations.entries.keys() {
+        let read = |pe: &pe::PE, rva: u32| {
+            let offset = pe.rva_to_offset(rva).unwrap();
+            u32::from_le_bytes(pe.buffer[offset..offset + 4].try_into().unwrap()) as u64
+        };
+        let old = read(&original, rva);
+        assert_eq!(
+            read(&pe, shift(rva)),
+            base + shift((old - base) as u32) as u64
+        );
+    }
+
+    pe.resize_section(0, 0xe00, 0xc34)
+        .expect("shrink .text back");
+    assert!(pe.buffer == original.buffer);
+}
+
+/// resize_section leaves the image untouched when RVA-bearing data cannot be rewritten.
+#[test]
+fn test_pe_resize_section_reports_blockers() {
+    let mut pe = pe::PE::from_file("tests/samples/sample64.exe").expect("Failed to parse PE64");
+    let before = pe.buffer.clone();
+    match pe.resize_section(0, 0x1800, 0x2800) {
+        Err(FileParseError::UnsupportedFeature(message)) => {
+            assert!(message.contains("language-specific exception handler data"))
+        }
+        _ => panic!("expected an unsupported feature error"),
+    }
+    assert!(pe.buffer == before);
+
+    // Raw growth inside the current virtual span only moves file offsets.
+    let raw_before: Vec<u32> = pe
+        .sections
+        .iter()
+        .map(|section| section.pointer_to_raw_data.value)
+        .collect();
+    pe.resize_section(0, 0x1a00, 0).expect("raw-only resize");
+    assert_eq!(pe.sections[0].virtual_size.value, 0x1a00);
+    assert_eq!(pe.sections[1].virtual_address.value, 0x3000);
+    assert_eq!(
+        pe.sections[1].pointer_to_raw_data.value,
+        raw_before[1] + 0x200
+    );
+    assert_eq!(pe.imports().unwrap().dlls.len(), 2);
+}
+
+/// Shrinking refuses to drop data that live structures still reference.
+#[test]
+fn test_pe_resize_section_shrink_blockers() {
+    let mut pe = pe::PE::from_file("tests/samples/sample2.dll").expect("Failed to parse PE");
+    let before = pe.buffer.clone();
+    let idata = pe
+        .sections
+        .iter()
+        .position(|section| section.name_str() == ".idata")
+        .unwrap();
+    match pe.resize_section(idata, 0, 0) {
+        Err(FileParseError::UnsupportedFeature(message)) => {
+            assert!(message.contains("data directory 1 refers to the removed range"));
+            assert!(message.contains("data directory 12 refers to the removed range"));
+        }
+        _ => panic!("expected an unsupported feature error"),
+    }
+    match pe.resize_section(0, 0x200, 0x200) {
+        Err(FileParseError::UnsupportedFeature(message)) => {
+            assert!(message.contains("refers to the removed range"))
+        }
+        _ => panic!("expected an unsupported feature error"),
+    }
+    assert!(pe.buffer == before);
+}
+
+/// Without base relocations the TLS directory VAs are still moved with their RVAs.
+#[test]
+fn test_pe_resize_section_relocs_stripped_tls() {
+    let mut pe = pe::PE::from_file("tests/samples/sample1.exe").expect("Failed to parse PE");
+    assert!(pe.base_relocations.is_empty());
+    let tls = pe.tls().unwrap().unwrap();
+    let callbacks: Vec<u64> = pe
+        .tls_callbacks()
+        .unwrap()
+        .into_iter()
+        .map(|callback| callback.value.value())
+        .collect();
+    let text = &pe.sections[0];
+    let (raw, virtual_size) = (text.size_of_raw_data.value, text.virtual_size.value);
+    pe.resize_section(0, raw, virtual_size + 0x1000).unwrap();
+
+    let moved = pe.tls().unwrap().unwrap();
+    assert_eq!(
+        moved.address_of_callbacks.value.value(),
+        tls.address_of_callbacks.value.value() + 0x1000
+    );
+    assert_eq!(
+        moved.address_of_index.value.value(),
+        tls.address_of_index.value.value() + 0x1000
+    );
+    assert_eq!(
+        moved.start_address_of_raw_data.value.value(),
+        tls.start_address_of_raw_data.value.value() + 0x1000
+    );
+    // The callbacks point into `.text`, below the shifted range.
+    let after: Vec<u64> = pe
+        .tls_callbacks()
+        .unwrap()
+        .into_iter()
+        .map(|callback| callback.value.value())
+        .collect();
+    assert_eq!(after, callbacks);
+}
+
+/// map_image lays sections out at their RVAs, relocates for the new base and binds the IAT.
+#[test]
+fn test_pe_map_image() {
+    use pe::header::ImageBase;
+    use pe::import_builder::ImportSymbol;
+
+    let pe = pe::PE::from_file("tests/samples/sample2.dll").expect("Failed to parse PE");
+    let ImageBase::Base32(preferred) = pe.optional_header.image_base.value else {
+        panic!("expected a PE32 image base");
+    };
+    let base = preferred + 0x0100_0000;
+
+    let image = pe.map_image(ImageBase::Base32(base)).expect("map image");
+    assert_eq!(image.len(), pe.optional_header.size_of_image.value as usize);
+    let image_base_offset = pe.optional_header.image_base.offset;
+    assert_eq!(
+        u32::from_le_bytes(
+            image[image_base_offset..image_base_offset + 4]
+                .try_into()
+                .unwrap()
+        ),
+        base
+    );
+    let text = &pe.sections[0];
+    let va = text.virtual_address.value as usize;
+    let raw = text.pointer_to_raw_data.value as usize;
+    assert_eq!(image[va..va + 6], pe.buffer[raw..raw + 6]);
+
+    let relocations = pe.base_relocation_builder().unwrap();
+    assert!(!relocations.entries.is_empty());
+    for &rva in relocations.entries.keys() {
+        let offset = pe.rva_to_offset(rva).unwrap();
+        let on_disk = u32::from_le_bytes(pe.buffer[offset..offset + 4].try_into().unwrap());
+        let rva = rva as usize;
+        let mapped = u32::from_le_bytes(image[rva..rva + 4].try_into().unwrap());
+        assert_eq!(mapped, on_disk.wrapping_add(0x0100_0000));
+    }
+
+    let mut requested = Vec::new();
+    let image = pe
+        .map_image_with_imports(ImageBase::Base32(preferred), |dll, symbol| {
+            requested.push((dll.to_string(), symbol.clone()));
+            Some(0x7700_0000 + requested.len() as u64 * 0x10)
+        })
+        .expect("map image with imports");
+    let imports = pe.imports().unwrap();
+    let mut index = 0;
+    for dll in &imports.dlls {
+        let iat = dll.descriptor.first_thunk.value as usize;
+        for (slot, entry) in dll.entries.iter().enumerate() {
+            index += 1;
+            let name = match entry {
+                pe::import::ImportEntry::ByName { by_name, .. } => by_name.name.clone(),
+                pe::import::ImportEntry::Ordinal { .. } => panic!("unexpected ordinal import"),
+            };
+            let (requested_dll, symbol) = &requested[index - 1];
+            assert_eq!(requested_dll, &dll.dll_name);
+            assert!(matches!(symbol, ImportSymbol::Name { name: n, .. } if *n == name));
+            let at = iat + slot * 4;
+            assert_eq!(
+                u32::from_le_bytes(image[at..at + 4].try_into().unwrap()),
+                0x7700_0000 + index as u32 * 0x10
+            );
+        }
+    }
+    assert_eq!(index, requested.len());
+
+    let stripped = pe::PE::from_file("tests/samples/sample1.exe").expect("Failed to parse PE");
+    assert!(matches!(
+        stripped.map_image(ImageBase::Base32(0x1000_0000)),
+        Err(FileParseError::UnsupportedFeature(_))
+    ));
+}
+
+/// from_mapped_image turns a loaded image back into a file and rebuilds imports from the IAT.
+#[test]
+fn test_pe_from_mapped_image() {
+    use pe::dump::{LoadedModule, ModuleExport};
+    use pe::header::{ImageBase, IAT};
+
+    let original = pe::PE::from_file("tests/samples/sample2.dll").expect("Failed to parse PE");
+    let iat = &original.optional_header.data_directories[IAT];
+    let ImageBase::Base32(preferred) = original.optional_header.image_base.value else {
+        panic!("expected a PE32 image base");
+    };
+    let loaded_base = preferred as u64 + 0x0200_0000;
+
+    let mut modules: Vec<LoadedModule> = Vec::new();
+    let image = original
+        .map_image_with_imports(ImageBase::Base32(loaded_base as u32), |dll, symbol| {
+            let index = match modules.iter().position(|module| module.name == dll) {
+                Some(index) => index,
+                None => {
+                    modules.push(LoadedModule {
+                        name: dll.to_string(),
+                        exports: Vec::new(),
+                    });
+                    modules.len() - 1
+                }
+            };
+            let address = 0x7000_0000
+                + index as u64 * 0x0100_0000
+                + 0x10 * modules[index].exports.len() as u64;
+            modules[index].exports.push(ModuleExport {
+                address,
+                symbol: symbol.clone(),
+            });
+            Some(address)
+        })
+        .expect("map image");
+
+    let pe = pe::PE::from_mapped_image(image.clone(), loaded_base, None).expect("unmap");
+    assert!(matches!(
+        pe.optional_header.image_base.value,
+        ImageBase::Base32(value) if value as u64 == loaded_base
+    ));
+    for (section, old) in pe.sections.iter().zip(&original.sections) {
+        assert_eq!(
+            section.pointer_to_raw_data.value,
+            section.virtual_address.value
+        );
+        assert_eq!(section.virtual_address.value, old.virtual_address.value);
+    }
+    let first = pe.sections[0].virtual_address.value as usize;
+    assert!(pe.buffer[first..] == image[first..]);
+
+    let mut pe =
+        pe::PE::from_mapped_image(image, loaded_base, Some(preferred as u64)).expect("unmap");
+    assert!(matches!(
+        pe.optional_header.image_base.value,
+        ImageBase::Base32(value) if value == preferred
+    ));
+    for old in &original.sections {
+        let size = old.size_of_raw_data.value.min(old.virtual_size.value) as usize;
+        let va = old.virtual_address.value;
+        let raw = old.pointer_to_raw_data.value as usize;
+        let new = pe.rva_to_offset(va).unwrap();
+        if (va..va + old.virtual_size.value).contains(&iat.virtual_address.value) {
+            continue;
+        }
+        assert!(
+            pe.buffer[new..new + size] == original.buffer[raw..raw + size],
+            "section at {va:#x}"
+        );
+    }
+
+    // A module sharing only the first address of each group (a forwarder) must not win it.
+    let decoy = LoadedModule {
+        name: "FORWARD.dll".to_string(),
+        exports: modules
+            .iter()
+            .filter(|module| module.exports.len() > 1)
+            .map(|module| ModuleExport {
+                address: module.exports[0].address,
+                symbol: pe::import_builder::ImportSymbol::Ordinal(1),
+            })
+            .collect(),
+    };
+    assert!(!decoy.exports.is_empty());
+    let modules: Vec<LoadedModule> = std::iter::once(decoy).chain(modules).collect();
+    pe.rebuild_imports_from_iat(iat.virtual_address.value, iat.size.value, &modules)
+        .expect("rebuild imports");
+    assert_eq!(pe.imphash().unwrap(), original.imphash().unwrap());
+    let imports = pe.imports().unwrap();
+    let old_imports = original.imports().unwrap();
+    assert_eq!(imports.dlls.len(), old_imports.dlls.len());
+    for (new, old) in imports.dlls.iter().zip(&old_imports.dlls) {
+        assert_eq!(new.dll_name, old.dll_name);
+        assert_eq!(
+            new.descriptor.first_thunk.value,
+            old.descriptor.first_thunk.value
+        );
+        for (index, entry) in new.entries.iter().enumerate() {
+            let pe::import::ImportEntry::ByName { by_name, .. } = entry else {
+                panic!("expected a named import");
+            };
+            let at = pe
+                .rva_to_offset(new.descriptor.first_thunk.value + index as u32 * 4)
+                .unwrap();
+            let slot = u32::from_le_bytes(pe.buffer[at..at + 4].try_into().unwrap());
+            let name = pe.rva_to_offset(slot).unwrap() + 2;
+            assert!(pe.buffer[name..].starts_with(by_name.name.as_bytes()));
+        }
+    }
+
+    let mut missing = pe::PE::from_mapped_image(
+        original
+            .map_image_with_imports(ImageBase::Base32(preferred), |_, _| Some(0x1234))
+            .unwrap(),
+        preferred as u64,
+        None,
+    )
+    .unwrap();
+    assert!(matches!(
+        missing.rebuild_imports_from_iat(iat.virtual_address.value, iat.size.value, &modules),
+        Err(FileParseError::UnsupportedFeature(_))
+    ));
+}
+
 /// grow_optional_header inserts bytes before the section table.
 #[test]
 fn test_pe_grow_optional_header() {